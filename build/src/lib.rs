@@ -0,0 +1,261 @@
+//! Build-time token codegen for `build.rs` scripts:
+//!
+//! ```no_run
+//! design_tokens_build::Builder::new()
+//!     .file("tokens.json")
+//!     .css(true)
+//!     .rust(true)
+//!     .compile()
+//!     .unwrap();
+//! ```
+//!
+//! The outputs land in `OUT_DIR` as `ambient.css` and `ambient.rs` unless renamed with
+//! [`Builder::name`], ready for `include_str!`/`include!`.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use ambient_design_tokens_core::{
+    emit::{
+        css_files::{to_css_files, CssSplit},
+        CssEmitter, Emitter, ResolvedTokens, RustEmitter,
+    },
+    load_design_tokens, DesignTokens, OutputOrder,
+};
+pub use ambient_design_tokens_core::{
+    extensions::ExtensionHandler, naming::Naming, CssOptions, RustColor, RustOptions,
+};
+
+/// Token files to load when no [`Builder::file`] is given, separated like `PATH`.
+pub const PATH_ENV: &str = "DESIGN_TOKENS_PATH";
+
+pub struct Builder {
+    files: Vec<PathBuf>,
+    css: bool,
+    rust: bool,
+    name: String,
+    out_dir: Option<PathBuf>,
+    css_options: CssOptions,
+    css_split: Option<CssSplit>,
+    rust_options: RustOptions,
+    naming: Naming,
+    extensions: Vec<(String, ExtensionHandler)>,
+    asset_dir: Option<PathBuf>,
+    order: OutputOrder,
+}
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            css: true,
+            rust: true,
+            name: "ambient".to_string(),
+            out_dir: None,
+            css_options: CssOptions::default(),
+            css_split: None,
+            rust_options: RustOptions::default(),
+            naming: Naming::default(),
+            extensions: Vec::new(),
+            asset_dir: None,
+            order: OutputOrder::Source,
+        }
+    }
+}
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a token file. Without any, files come from `DESIGN_TOKENS_PATH`, falling back to the
+    /// embedded Ambient tokens.
+    pub fn file(mut self, path: impl AsRef<Path>) -> Self {
+        self.files.push(path.as_ref().to_path_buf());
+        self
+    }
+    pub fn css(mut self, enabled: bool) -> Self {
+        self.css = enabled;
+        self
+    }
+    pub fn rust(mut self, enabled: bool) -> Self {
+        self.rust = enabled;
+        self
+    }
+    /// Base name of the generated files, `ambient` by default.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+    /// Where to write the outputs instead of `OUT_DIR`.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+    pub fn css_options(mut self, options: CssOptions) -> Self {
+        self.css_options = options;
+        self
+    }
+    /// Writes the CSS as separate files in a directory named like the outputs, with an
+    /// `index.css` importing them, instead of a single file.
+    pub fn split_css(mut self, split: CssSplit) -> Self {
+        self.css_split = Some(split);
+        self
+    }
+    pub fn rust_options(mut self, options: RustOptions) -> Self {
+        self.rust_options = options;
+        self
+    }
+    /// How token paths are named in both outputs.
+    pub fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self
+    }
+    /// Applies `handler` to tokens with `$extensions` from `vendor`, e.g. `com.example`.
+    pub fn extension(mut self, vendor: &str, handler: ExtensionHandler) -> Self {
+        self.extensions.push((vendor.to_string(), handler));
+        self
+    }
+    /// Fails the build when an `asset` token names a file that doesn't exist under `dir`.
+    pub fn asset_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.asset_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+    /// The order tokens are written in by both outputs.
+    pub fn order(mut self, order: OutputOrder) -> Self {
+        self.order = order;
+        self
+    }
+    /// The files that will be loaded, after applying `DESIGN_TOKENS_PATH`.
+    pub fn input_files(&self) -> Vec<PathBuf> {
+        if !self.files.is_empty() {
+            return self.files.clone();
+        }
+        match env::var_os(PATH_ENV) {
+            Some(paths) => env::split_paths(&paths).collect(),
+            None => Vec::new(),
+        }
+    }
+    pub fn load(&self) -> io::Result<Vec<DesignTokens>> {
+        let mut data = self.load_files()?;
+        for set in &mut data {
+            set.options.naming = self.naming.clone();
+            set.options
+                .extensions
+                .extend(self.extensions.iter().cloned());
+            set.reorder(self.order);
+        }
+        Ok(data)
+    }
+    fn load_files(&self) -> io::Result<Vec<DesignTokens>> {
+        let files = self.input_files();
+        if files.is_empty() {
+            #[cfg(feature = "ambient-tokens")]
+            return Ok(ambient_design_tokens_core::get_design_tokens());
+            #[cfg(not(feature = "ambient-tokens"))]
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no token files given and {PATH_ENV} is not set"),
+            ));
+        }
+        let mut data = Vec::new();
+        for path in files {
+            data.extend(
+                load_design_tokens(&path).map_err(|err| {
+                    io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+                })?,
+            );
+        }
+        Ok(data)
+    }
+    /// Cargo directives so the build script reruns when an input file or `DESIGN_TOKENS_PATH`
+    /// changes.
+    fn rerun_if_changed(&self) -> Vec<String> {
+        let mut out = vec![format!("cargo:rerun-if-env-changed={PATH_ENV}")];
+        for path in self.input_files() {
+            out.push(format!("cargo:rerun-if-changed={}", path.display()));
+        }
+        out
+    }
+    pub fn compile(&self) -> io::Result<()> {
+        for line in self.rerun_if_changed() {
+            println!("{line}");
+        }
+        let out_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?,
+        };
+        let data = self.load()?;
+        if let Some(dir) = &self.asset_dir {
+            let missing = data
+                .iter()
+                .flat_map(|x| x.validate_assets(dir))
+                .map(|x| format!("{}: {}", x.path, x.message))
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, missing.join("\n")));
+            }
+        }
+        let tokens = ResolvedTokens::new(&data);
+        if let (true, Some(split)) = (self.css, self.css_split) {
+            let dir = out_dir.join(&self.name);
+            fs::create_dir_all(&dir)?;
+            for (name, css) in to_css_files(&data, split, &self.css_options) {
+                fs::write(dir.join(name), css)?;
+            }
+        } else if self.css {
+            let css = CssEmitter {
+                options: self.css_options.clone(),
+            };
+            fs::write(
+                out_dir.join(format!("{}.css", self.name)),
+                css.emit(&tokens),
+            )?;
+        }
+        if self.rust {
+            let rust = RustEmitter {
+                options: self.rust_options.clone(),
+            };
+            fs::write(
+                out_dir.join(format!("{}.rs", self.name)),
+                rust.emit(&tokens),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    let dir = env::temp_dir().join("design-tokens-build-test");
+    fs::create_dir_all(&dir).unwrap();
+    let tokens = dir.join("tokens.json");
+    fs::write(&tokens, r##"{ "red": { "$value": "#ff0000" } }"##).unwrap();
+    Builder::new()
+        .file(&tokens)
+        .css(false)
+        .name("tokens")
+        .out_dir(&dir)
+        .compile()
+        .unwrap();
+    let rust = fs::read_to_string(dir.join("tokens.rs")).unwrap();
+    assert!(rust.contains("pub const RED: &str = \"#ff0000\";"));
+    assert!(!dir.join("tokens.css").exists());
+    Builder::new()
+        .file(&tokens)
+        .rust(false)
+        .name("split")
+        .split_css(CssSplit::Theme)
+        .out_dir(&dir)
+        .compile()
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("split/index.css")).unwrap(),
+        "@import \"tokens.css\";"
+    );
+    assert_eq!(
+        Builder::new().file(&tokens).rerun_if_changed()[1],
+        format!("cargo:rerun-if-changed={}", tokens.display())
+    );
+}
@@ -0,0 +1,1141 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use ambient_design_tokens_core::{
+    a11y::{ContrastPair, ContrastResult, WcagLevel},
+    diff::{Bump, TokenDiff},
+    emit::{
+        css_files::{to_css_files, CssSplit},
+        fonts::FontFiles,
+        incremental::{Generator, RegeneratedOutputs},
+        raw_value,
+        template::TemplateEmitter,
+        CssEmitter, CustomMediaEmitter, EmitterRegistry, FontFaceEmitter, FontManifestEmitter,
+        KotlinEmitter, ResolvedTokens, RustEmitter, ScssBreakpointsEmitter, ThemedCssEmitter,
+        UtilityEmitter,
+    },
+    get_design_tokens,
+    lint::LintConfig,
+    load_design_tokens,
+    modes::Collection,
+    naming::Naming,
+    palette::RampScale,
+    scale::{SpacingScale, TypeScale},
+    schema,
+    token_set::{ResolveContext, TokenSet},
+    validate::{Diagnostic, Severity},
+    watch, ColorFormat, CssNaming, CssOptions, CssReferences, DesignTokens, OutputOrder,
+    ResolutionMode, ResolveStrategy, RustColor, RustOptions, TokenOrGroup, TokenValue,
+};
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+
+mod serve;
+
+#[derive(Parser)]
+#[command(
+    name = "design-tokens",
+    about = "Convert design token files to CSS and Rust"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate CSS and/or Rust from the input tokens
+    Build {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Rebuild the outputs whenever an input file changes
+    Watch {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Check that the input tokens parse and every reference resolves
+    Validate {
+        #[command(flatten)]
+        input: Input,
+        /// Also check that `asset` tokens name files under this directory
+        #[arg(long)]
+        assets: Option<PathBuf>,
+        /// `text` for one line per diagnostic or `json` for a report, see `diagnostic_json`
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+    /// Check the tokens against the conventions enabled in a JSON lint config, failing on any
+    /// finding
+    Lint {
+        #[command(flatten)]
+        input: Input,
+        /// JSON `{ "semantic"?, "palette"?, "maxDepth"?, "keyCase"? }`, see `LintConfig`
+        #[arg(long)]
+        config: PathBuf,
+        /// `text` for one line per finding or `json` for a report, see `diagnostic_json`
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+    /// List tokens that no file under the given directories uses, by Rust constant or CSS
+    /// custom property, failing if there are any
+    Unused {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long, required = true)]
+        dir: Vec<PathBuf>,
+        /// Prefix the CSS custom properties were generated with
+        #[arg(long)]
+        css_prefix: Option<String>,
+    },
+    /// Check WCAG contrast of foreground/background pairs, failing below the required level
+    A11y {
+        #[command(flatten)]
+        input: Input,
+        /// JSON list of `{ "foreground", "background", "level"?, "largeText"? }` token paths
+        #[arg(long)]
+        pairs: PathBuf,
+        /// Require this level (AA or AAA) for every pair instead of the per-pair level
+        #[arg(long, value_parser = parse_level)]
+        level: Option<WcagLevel>,
+    },
+    /// Show added, removed, renamed and changed tokens between two token files, with the semver
+    /// bump they call for
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        /// `text` for one line per change or `json` for a report per set
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+    /// Print the JSON Schema of the accepted token file formats
+    Schema,
+    /// List tokens, optionally filtered by type or path
+    List {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long)]
+        filter: Option<String>,
+    },
+    /// Print the tokens whose path matches a glob such as `Brand/Primary/*`, with their raw
+    /// values; `**` matches any number of groups
+    Query {
+        #[command(flatten)]
+        input: Input,
+        pattern: String,
+        /// Also print each token's resolved value, or why it can't be resolved
+        #[arg(long)]
+        resolve: bool,
+        /// `text` for tab-separated lines or `json` for an array of objects
+        #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+    /// Print which tokens reference which as a Graphviz DOT graph per set
+    Graph {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Serve the style guide, `/tokens.css`, `/tokens.json` and `/token/<path>` over HTTP,
+    /// re-reading the input files on every request. `?theme=NAME` selects a mode.
+    Serve {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+}
+
+#[derive(Args)]
+struct Input {
+    /// Token files to load; defaults to the embedded Ambient tokens
+    #[arg(long, short)]
+    input: Vec<PathBuf>,
+    /// Merge all inputs into one set, later files overriding earlier ones
+    #[arg(long)]
+    merge: bool,
+    /// Only keep this mode of collections that define it, e.g. `dark`
+    #[arg(long)]
+    mode: Option<String>,
+    /// Skip tokens with broken references instead of failing, printing a warning for each
+    #[arg(long)]
+    lenient: bool,
+    /// Generate 50-900 shades of a color token into a group, e.g. `Brand/Primary=Palette/Brand`
+    #[arg(long, value_parser = parse_ramp)]
+    ramp: Vec<(String, String)>,
+    /// Generate spacing steps `1`, `2`, ... from a dimension token into a group, e.g.
+    /// `Space/Base=Space`
+    #[arg(long, value_parser = parse_ramp)]
+    spacing: Vec<(String, String)>,
+    /// Number of generated spacing steps
+    #[arg(long, default_value_t = 12)]
+    spacing_steps: usize,
+    /// Make each spacing step this many times the one before instead of a multiple of the base
+    #[arg(long)]
+    spacing_ratio: Option<f64>,
+    /// Generate font sizes `xs` to `4xl` from a size and a unitless ratio token into a group,
+    /// e.g. `Font/Base:Font/Ratio=Font/Size`
+    #[arg(long, value_parser = parse_type_scale)]
+    type_scale: Vec<(String, String, String)>,
+    /// JSON naming strategy for every format, e.g. `{ "prefix": "ds", "case": "snake" }`
+    #[arg(long)]
+    naming: Option<PathBuf>,
+    /// Prefer sets whose file name has this part when several define a referenced token, e.g.
+    /// `mode=dark` for `Ambient.dark.tokens.json`. Keys are `theme`, `mode` and `platform`
+    #[arg(long, value_name = "KEY=NAME", value_parser = parse_context, conflicts_with = "merge")]
+    context: Vec<(String, String)>,
+    /// Order of tokens, groups and composite fields in every output: source or alphabetical
+    #[arg(long, default_value = "source", value_parser = parse_order)]
+    order: OutputOrder,
+}
+impl Input {
+    fn load(&self) -> Result<Vec<DesignTokens>, Box<dyn Error>> {
+        self.load_mode(self.mode.as_deref())
+    }
+    fn load_mode(&self, mode: Option<&str>) -> Result<Vec<DesignTokens>, Box<dyn Error>> {
+        let data = self.load_all()?;
+        let data = match mode {
+            Some(mode) => Collection::from_sets(data)
+                .into_iter()
+                .flat_map(|x| match x.modes.contains_key(mode) {
+                    true => x.into_mode(mode).into_iter().collect(),
+                    false => x.into_themes(),
+                })
+                .collect(),
+            None => data,
+        };
+        let mut data = match self.merge {
+            true => vec![DesignTokens::merge(data)],
+            false => TokenSet::new(data).into_sets_in(&self.resolve_context()),
+        };
+        for (base, target) in &self.ramp {
+            let mut found = false;
+            for set in &mut data {
+                found |= set.add_ramp(base, target, &RampScale::default());
+            }
+            if !found {
+                return Err(format!("--ramp: {base} is not a color token").into());
+            }
+        }
+        let scale = SpacingScale {
+            steps: self.spacing_steps,
+            ratio: self.spacing_ratio,
+        };
+        for (base, target) in &self.spacing {
+            let mut found = false;
+            for set in &mut data {
+                found |= set.add_spacing(base, target, &scale);
+            }
+            if !found {
+                return Err(format!("--spacing: {base} is not a dimension token").into());
+            }
+        }
+        for (base, ratio, target) in &self.type_scale {
+            let mut found = false;
+            for set in &mut data {
+                found |= set.add_type_scale(base, ratio, target, &TypeScale::default());
+            }
+            if !found {
+                return Err(format!(
+                    "--type-scale: {base} is not a dimension token or {ratio} not a number token"
+                )
+                .into());
+            }
+        }
+        if let Some(path) = &self.naming {
+            let naming: Naming = serde_json::from_str(&fs::read_to_string(path)?)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            for set in &mut data {
+                set.options.naming = naming.clone();
+            }
+        }
+        for set in &mut data {
+            set.reorder(self.order);
+        }
+        for set in &data {
+            for x in set.unsupported_modifiers() {
+                eprintln!("warning: {}: {}", x.path, x.message);
+            }
+        }
+        if self.lenient {
+            for set in &mut data {
+                set.options.mode = ResolutionMode::Lenient;
+                for x in set.unresolved() {
+                    eprintln!(
+                        "warning: {}: reference {{{}}} does not resolve",
+                        x.path,
+                        x.reference.join(".")
+                    );
+                }
+                for x in set.unit_errors() {
+                    eprintln!("warning: {}: {}", x.path, x.message);
+                }
+            }
+        }
+        Ok(data)
+    }
+    fn resolve_context(&self) -> ResolveContext {
+        let mut context = ResolveContext::default();
+        for (key, name) in &self.context {
+            let field = match key.as_str() {
+                "theme" => &mut context.theme,
+                "mode" => &mut context.mode,
+                _ => &mut context.platform,
+            };
+            *field = Some(name.clone());
+        }
+        context
+    }
+    fn load_all(&self) -> Result<Vec<DesignTokens>, Box<dyn Error>> {
+        if self.input.is_empty() {
+            return Ok(get_design_tokens());
+        }
+        let mut data = Vec::new();
+        for path in &self.input {
+            let json =
+                fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            if let Err(errors) = schema::validate_json(&json) {
+                let errors = errors
+                    .iter()
+                    .map(|x| format!("{}#{}: {}", path.display(), x.pointer, x.reason))
+                    .collect::<Vec<_>>();
+                return Err(errors.join("\n").into());
+            }
+            data.extend(
+                load_design_tokens(path).map_err(|err| format!("{}: {}", path.display(), err))?,
+            );
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Args)]
+struct Output {
+    /// Write a registered format to a file, e.g. `--out swift=Tokens.swift`
+    #[arg(long = "out", value_name = "FORMAT=PATH", value_parser = parse_out)]
+    out: Vec<(String, PathBuf)>,
+    /// Register a Handlebars template as a format named after its file stem
+    #[arg(long)]
+    template: Vec<PathBuf>,
+    /// JSON mapping font families to files, used by the `font-face` and `font-manifest` formats
+    #[arg(long)]
+    fonts: Option<PathBuf>,
+    #[arg(long)]
+    out_css: Option<PathBuf>,
+    /// Directory receiving the CSS as separate files with an `index.css` importing them
+    #[arg(long)]
+    out_css_dir: Option<PathBuf>,
+    /// How `--out-css-dir` splits the CSS: a file per `theme` or per top-level `group`
+    #[arg(long, default_value = "theme", value_parser = parse_css_split)]
+    css_split: CssSplit,
+    /// How colors are written in CSS output: hex, hex-rgba, rgb, hsl or oklch
+    #[arg(long, default_value = "hex", value_parser = str::parse::<ColorFormat>)]
+    color_format: ColorFormat,
+    /// Write pixel values in CSS as rem relative to this root font size, e.g. 16
+    #[arg(long)]
+    rem_base: Option<f64>,
+    /// Comment each CSS declaration with its source file, token path and expression
+    #[arg(long)]
+    provenance: bool,
+    /// Prepended to CSS custom properties, e.g. `ds` for `--ds-brand-primary`
+    #[arg(long)]
+    css_prefix: Option<String>,
+    /// Keep the case and punctuation of token paths in CSS properties, escaping where needed
+    #[arg(long)]
+    css_escape: bool,
+    /// Register typed CSS properties with `@property` rules so they can be animated
+    #[arg(long)]
+    register_properties: bool,
+    /// How CSS writes references to other tokens: `var`, `fallback` for `var()` with the
+    /// resolved value as fallback, or `inline` for just the resolved value
+    #[arg(long, default_value = "var", value_parser = parse_css_references)]
+    css_references: CssReferences,
+    /// Write fully resolved values in CSS instead of `var()` and `calc()`, like the Rust output
+    #[arg(long)]
+    css_resolve: bool,
+    /// Strip comments and whitespace from CSS output
+    #[arg(long, conflicts_with = "css_indent")]
+    minify: bool,
+    /// Write every CSS rule over multiple lines, indented by this many spaces
+    #[arg(long)]
+    css_indent: Option<usize>,
+    /// Class names of the `utilities` format, with `{utility}` and `{name}` placeholders
+    #[arg(long, default_value = "{utility}-{name}")]
+    utility_class: String,
+    #[arg(long)]
+    out_rust: Option<PathBuf>,
+    /// How colors are generated in Rust output: hex strings, linear [f32; 4] or glam Vec4
+    #[arg(long, default_value = "hex", value_parser = parse_rust_color)]
+    rust_color: RustColor,
+    /// Group holding breakpoint widths, e.g. `Layout/Breakpoints`; by default the first group
+    /// named `breakpoints`
+    #[arg(long)]
+    breakpoints: Option<String>,
+    /// Also generate a `phf` map from token path to value in Rust output
+    #[arg(long)]
+    rust_lookup: bool,
+    /// Also generate a `Theme` struct with setters for runtime overrides in Rust output
+    #[arg(long)]
+    rust_theme: bool,
+    /// Also generate `TOKEN_METADATA` describing every token in Rust output
+    #[arg(long)]
+    rust_metadata: bool,
+    /// Token file to check the Rust constants against in generated tests, relative to the
+    /// crate running them
+    #[arg(long)]
+    rust_tests: Option<String>,
+    /// Generate numbers with a unit as `Dimension { value, unit }` in Rust output instead of `f32`
+    #[arg(long)]
+    rust_dimensions: bool,
+    /// Group generated as `SPACING: &[f32]` in Rust output, e.g. the target of `--spacing`
+    #[arg(long)]
+    rust_spacing: Option<String>,
+    #[arg(long)]
+    out_swift: Option<PathBuf>,
+    #[arg(long)]
+    out_kotlin: Option<PathBuf>,
+    /// Package declared at the top of the generated Kotlin file
+    #[arg(long, default_value = "design.tokens")]
+    kotlin_package: String,
+    #[arg(long)]
+    out_dart: Option<PathBuf>,
+    /// Directory receiving `<set>/colors.xml` and `<set>/dimens.xml` per token set
+    #[arg(long)]
+    out_android: Option<PathBuf>,
+    /// Prefix for generated Android resource names
+    #[arg(long, default_value = "")]
+    android_prefix: String,
+}
+impl Output {
+    fn css_options(&self) -> CssOptions {
+        CssOptions {
+            color_format: self.color_format,
+            rem_base: self.rem_base,
+            provenance: self.provenance,
+            prefix: self.css_prefix.clone(),
+            naming: match self.css_escape {
+                true => CssNaming::Escaped,
+                false => CssNaming::Slug,
+            },
+            register_properties: self.register_properties,
+            minify: self.minify,
+            indent: self.css_indent,
+            references: self.css_references,
+            resolve: match self.css_resolve {
+                true => ResolveStrategy::Inline,
+                false => ResolveStrategy::Preserve,
+            },
+        }
+    }
+    fn rust_options(&self) -> RustOptions {
+        RustOptions {
+            color: self.rust_color,
+            breakpoints: self.breakpoints.clone(),
+            lookup: self.rust_lookup,
+            theme: self.rust_theme,
+            metadata: self.rust_metadata,
+            tests: self.rust_tests.clone(),
+            dimensions: self.rust_dimensions,
+            spacing: self.rust_spacing.clone(),
+        }
+    }
+    /// Keeps the CSS and Rust outputs of `data` for regenerating them incrementally.
+    fn generator(&self, data: Vec<DesignTokens>) -> Generator {
+        Generator::new(
+            data,
+            CssEmitter {
+                options: self.css_options(),
+            },
+            RustEmitter {
+                options: self.rust_options(),
+            },
+        )
+    }
+    fn registry(&self) -> Result<EmitterRegistry, Box<dyn Error>> {
+        let mut registry = EmitterRegistry::with_builtins();
+        let options = self.css_options();
+        registry.register(CssEmitter {
+            options: options.clone(),
+        });
+        registry.register(UtilityEmitter {
+            class_name: self.utility_class.clone(),
+            options: options.clone(),
+        });
+        registry.register(ThemedCssEmitter {
+            default: "light".to_string(),
+            options,
+            ..Default::default()
+        });
+        registry.register(RustEmitter {
+            options: self.rust_options(),
+        });
+        registry.register(CustomMediaEmitter {
+            path: self.breakpoints.clone(),
+        });
+        registry.register(ScssBreakpointsEmitter {
+            path: self.breakpoints.clone(),
+        });
+        registry.register(KotlinEmitter {
+            package: self.kotlin_package.clone(),
+        });
+        if let Some(path) = &self.fonts {
+            let files: FontFiles = serde_json::from_str(&fs::read_to_string(path)?)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            registry.register(FontFaceEmitter {
+                files: files.clone(),
+            });
+            registry.register(FontManifestEmitter { files });
+        }
+        for path in &self.template {
+            let name = path
+                .file_stem()
+                .and_then(|x| x.to_str())
+                .ok_or_else(|| format!("{}: invalid template name", path.display()))?;
+            let template = fs::read_to_string(path)?;
+            registry.register(
+                TemplateEmitter::new(name, &template)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?,
+            );
+        }
+        Ok(registry)
+    }
+    /// Fails on the first set that can't be generated, see [`DesignTokens::check`].
+    fn check(&self, data: &[DesignTokens]) -> Result<(), Box<dyn Error>> {
+        for set in data {
+            set.check(&self.css_options())
+                .map_err(|err| format!("{}: {err}", set.get_name()))?;
+        }
+        Ok(())
+    }
+    fn write(&self, data: &[DesignTokens]) -> Result<(), Box<dyn Error>> {
+        self.write_with(data, None)
+    }
+    /// Like [`Output::write`], but takes CSS and Rust from `regenerated`, leaving them as they
+    /// are when it didn't change them.
+    fn write_with(
+        &self,
+        data: &[DesignTokens],
+        regenerated: Option<&RegeneratedOutputs>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut outputs = self.out.clone();
+        for (name, path) in [
+            ("css", &self.out_css),
+            ("rust", &self.out_rust),
+            ("swift", &self.out_swift),
+            ("kotlin", &self.out_kotlin),
+            ("dart", &self.out_dart),
+        ] {
+            if let Some(path) = path {
+                outputs.push((name.to_string(), path.clone()));
+            }
+        }
+        if outputs.is_empty() && self.out_android.is_none() && self.out_css_dir.is_none() {
+            return Err("nothing to build, pass at least one --out option".into());
+        }
+        let registry = self.registry()?;
+        let tokens = ResolvedTokens::new(data);
+        for (name, path) in outputs {
+            let Some(emitter) = registry.get(&name) else {
+                let known = registry.names().collect::<Vec<_>>().join(", ");
+                return Err(format!("unknown format {name:?}, expected one of {known}").into());
+            };
+            if name == "rust" {
+                for set in data {
+                    for (name, paths) in set.rust_collisions() {
+                        eprintln!(
+                            "warning: {}: {} share the Rust name {name}, numbering them",
+                            set.get_name(),
+                            paths
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+                }
+            }
+            match regenerated {
+                Some(regenerated) if ["css", "rust"].contains(&name.as_str()) => {
+                    if let Some(output) = regenerated.outputs.get(&name) {
+                        fs::write(path, output)?;
+                    }
+                }
+                _ => fs::write(path, emitter.emit(&tokens))?,
+            }
+        }
+        if let Some(dir) = &self.out_css_dir {
+            fs::create_dir_all(dir)?;
+            for (name, css) in to_css_files(data, self.css_split, &self.css_options()) {
+                fs::write(dir.join(name), css)?;
+            }
+        }
+        if let Some(dir) = &self.out_android {
+            for tokens in data {
+                let dir = dir.join(tokens.get_name());
+                let res = tokens.to_android_resources(&self.android_prefix);
+                fs::create_dir_all(&dir)?;
+                fs::write(dir.join("colors.xml"), res.colors)?;
+                fs::write(dir.join("dimens.xml"), res.dimens)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_level(x: &str) -> Result<WcagLevel, String> {
+    match x.to_uppercase().as_str() {
+        "AA" => Ok(WcagLevel::AA),
+        "AAA" => Ok(WcagLevel::AAA),
+        _ => Err(format!("unknown WCAG level {x:?}, expected AA or AAA")),
+    }
+}
+
+fn parse_css_split(x: &str) -> Result<CssSplit, String> {
+    match x {
+        "theme" => Ok(CssSplit::Theme),
+        "group" => Ok(CssSplit::Group),
+        _ => Err(format!("unknown CSS split {x:?}, expected theme or group")),
+    }
+}
+
+fn parse_css_references(x: &str) -> Result<CssReferences, String> {
+    match x {
+        "var" => Ok(CssReferences::Var),
+        "fallback" => Ok(CssReferences::Fallback),
+        "inline" => Ok(CssReferences::Inline),
+        _ => Err(format!(
+            "unknown CSS references {x:?}, expected var, fallback or inline"
+        )),
+    }
+}
+
+fn parse_order(x: &str) -> Result<OutputOrder, String> {
+    match x {
+        "source" => Ok(OutputOrder::Source),
+        "alphabetical" => Ok(OutputOrder::Alphabetical),
+        _ => Err(format!(
+            "unknown order {x:?}, expected source or alphabetical"
+        )),
+    }
+}
+
+fn parse_rust_color(x: &str) -> Result<RustColor, String> {
+    match x {
+        "hex" => Ok(RustColor::Hex),
+        "linear" => Ok(RustColor::LinearRgba),
+        "glam" => Ok(RustColor::GlamVec4),
+        _ => Err(format!(
+            "unknown Rust color {x:?}, expected hex, linear or glam"
+        )),
+    }
+}
+
+fn parse_ramp(x: &str) -> Result<(String, String), String> {
+    let (base, target) = x
+        .split_once('=')
+        .ok_or_else(|| format!("expected BASE=TARGET, got {x:?}"))?;
+    Ok((base.to_string(), target.to_string()))
+}
+
+fn parse_context(x: &str) -> Result<(String, String), String> {
+    let (key, name) = x
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=NAME, got {x:?}"))?;
+    match key {
+        "theme" | "mode" | "platform" => Ok((key.to_string(), name.to_string())),
+        _ => Err(format!(
+            "unknown context {key:?}, expected theme, mode or platform"
+        )),
+    }
+}
+
+fn parse_type_scale(x: &str) -> Result<(String, String, String), String> {
+    let (base, target) = parse_ramp(x)?;
+    let (base, ratio) = base
+        .split_once(':')
+        .ok_or_else(|| format!("expected BASE:RATIO=TARGET, got {x:?}"))?;
+    Ok((base.to_string(), ratio.to_string(), target))
+}
+
+fn parse_out(x: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = x
+        .split_once('=')
+        .ok_or_else(|| format!("expected FORMAT=PATH, got {x:?}"))?;
+    Ok((name.to_string(), PathBuf::from(path)))
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Box<dyn Error>> {
+    match cli.command {
+        Command::Build { input, output } => {
+            let data = input.load()?;
+            output.check(&data)?;
+            output.write(&data)?;
+        }
+        Command::Watch { input, output } => {
+            if input.input.is_empty() {
+                return Err("watch needs at least one --input file".into());
+            }
+            // Generating leaves mistakes a save can introduce, such as a broken reference, to
+            // `check`, so they are checked up front and reported.
+            let load = || -> Result<Vec<DesignTokens>, Box<dyn Error>> {
+                let data = input.load()?;
+                output.check(&data)?;
+                Ok(data)
+            };
+            let data = load()?;
+            output.write(&data)?;
+            let mut generator = output.generator(data);
+            println!("watching {} file(s)", input.input.len());
+            watch(&input.input, |changed| {
+                // Keep watching through bad intermediate saves; the next save will retry.
+                let result = load().and_then(|data| {
+                    let regenerated = generator.replace(data)?;
+                    output.write_with(generator.sets(), Some(&regenerated))?;
+                    Ok(regenerated.tokens.len())
+                });
+                match result {
+                    Ok(n) => println!(
+                        "rebuilt {n} token(s) after change to {}",
+                        changed[0].display()
+                    ),
+                    Err(err) => eprintln!("error: {err}"),
+                }
+                true
+            })?;
+        }
+        Command::Schema => println!("{:#}", schema::schema()),
+        Command::Validate {
+            input,
+            assets,
+            format,
+        } => {
+            let data = input.load()?;
+            let mut errors = 0;
+            let mut report = Vec::new();
+            for tokens in &data {
+                let assets = assets.as_ref().map(|dir| tokens.validate_assets(dir));
+                for x in tokens
+                    .validate()
+                    .into_iter()
+                    .chain(assets.into_iter().flatten())
+                {
+                    let severity = match x.severity {
+                        Severity::Error => {
+                            errors += 1;
+                            "error"
+                        }
+                        Severity::Warning => "warning",
+                    };
+                    if format == "json" {
+                        report.push(diagnostic_json(tokens, &x));
+                        continue;
+                    }
+                    println!(
+                        "{severity}[{}]: {}/{}: {}",
+                        x.rule,
+                        tokens.get_name(),
+                        x.path,
+                        x.message
+                    );
+                }
+            }
+            if format == "json" {
+                println!("{:#}", json!({ "diagnostics": report }));
+            }
+            if errors > 0 {
+                if format == "text" {
+                    eprintln!("{errors} error(s)");
+                }
+                return Ok(ExitCode::FAILURE);
+            }
+            if format == "text" {
+                println!("{} token set(s) OK", data.len());
+            }
+        }
+        Command::Lint {
+            input,
+            config,
+            format,
+        } => {
+            let config: LintConfig = serde_json::from_str(&fs::read_to_string(&config)?)
+                .map_err(|err| format!("{}: {}", config.display(), err))?;
+            let mut found = 0;
+            let mut report = Vec::new();
+            for tokens in &input.load()? {
+                for x in tokens.lint(&config) {
+                    found += 1;
+                    if format == "json" {
+                        report.push(diagnostic_json(tokens, &x));
+                        continue;
+                    }
+                    println!(
+                        "warning[{}]: {}/{}: {}",
+                        x.rule,
+                        tokens.get_name(),
+                        x.path,
+                        x.message
+                    );
+                }
+            }
+            if format == "json" {
+                println!("{:#}", json!({ "diagnostics": report }));
+            }
+            if found > 0 {
+                if format == "text" {
+                    eprintln!("{found} finding(s)");
+                }
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Unused {
+            input,
+            dir,
+            css_prefix,
+        } => {
+            let mut sources = Vec::new();
+            for dir in &dir {
+                read_sources(dir, &mut sources)?;
+            }
+            let options = CssOptions {
+                prefix: css_prefix,
+                ..Default::default()
+            };
+            let mut found = 0;
+            for tokens in &input.load()? {
+                for path in tokens.unused(&options, sources.iter().map(|x| x.as_str())) {
+                    found += 1;
+                    println!("{}/{path}", tokens.get_name());
+                }
+            }
+            if found > 0 {
+                eprintln!("{found} unused token(s)");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::A11y {
+            input,
+            pairs,
+            level,
+        } => {
+            let mut pairs: Vec<ContrastPair> =
+                serde_json::from_str(&fs::read_to_string(&pairs)?)
+                    .map_err(|err| format!("{}: {}", pairs.display(), err))?;
+            if let Some(level) = level {
+                pairs.iter_mut().for_each(|x| x.level = level);
+            }
+            let mut failures = 0;
+            for tokens in &input.load()? {
+                let report = tokens.contrast_report(&pairs);
+                // Sets like the shared typography tokens have no colors to check.
+                if report
+                    .iter()
+                    .all(|x| matches!(x, ContrastResult::Unresolved { .. }))
+                {
+                    continue;
+                }
+                for result in report {
+                    match &result {
+                        ContrastResult::Checked {
+                            pair,
+                            ratio,
+                            passes,
+                        } => println!(
+                            "{} {}: {} on {} {:.2}:1 ({:?}{})",
+                            if *passes { "pass" } else { "FAIL" },
+                            tokens.get_name(),
+                            pair.foreground,
+                            pair.background,
+                            ratio,
+                            pair.level,
+                            if pair.large_text { ", large text" } else { "" }
+                        ),
+                        ContrastResult::Unresolved { path, .. } => {
+                            println!("FAIL {}: {} is not a color token", tokens.get_name(), path)
+                        }
+                    }
+                    if !result.passes() {
+                        failures += 1;
+                    }
+                }
+            }
+            if failures > 0 {
+                eprintln!("{failures} pair(s) below the required contrast");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Diff { old, new, format } => {
+            let load = |path: &PathBuf| {
+                load_design_tokens(path).map_err(|err| format!("{}: {}", path.display(), err))
+            };
+            let (old, new) = (load(&old)?, load(&new)?);
+            let mut diffs = Vec::new();
+            let (mut removed, mut added) = (Vec::new(), Vec::new());
+            if let ([a], [b]) = (old.as_slice(), new.as_slice()) {
+                // Two plain token trees, compare them regardless of their file names.
+                diffs.push((b.get_name(), a.diff(b)));
+            } else {
+                for a in &old {
+                    match new.iter().find(|b| b.get_name() == a.get_name()) {
+                        Some(b) => diffs.push((a.get_name(), a.diff(b))),
+                        None => removed.push(a.get_name()),
+                    }
+                }
+                for b in &new {
+                    if !old.iter().any(|a| a.get_name() == b.get_name()) {
+                        added.push(b.get_name());
+                    }
+                }
+            }
+            let bump = diffs
+                .iter()
+                .map(|(_, diff)| diff.bump())
+                .chain(removed.iter().map(|_| Bump::Major))
+                .chain(added.iter().map(|_| Bump::Patch))
+                .max()
+                .unwrap_or_default();
+            if format == "json" {
+                println!("{:#}", diff_json(bump, &diffs, &removed, &added));
+                return Ok(ExitCode::SUCCESS);
+            }
+            for (set, diff) in &diffs {
+                print_diff(set, diff);
+            }
+            for set in &removed {
+                println!("- {set} (token set removed)");
+            }
+            for set in &added {
+                println!("+ {set} (token set added)");
+            }
+            println!("suggested bump: {}", bump.as_str());
+        }
+        Command::List { input, filter } => {
+            let filter = filter.map(|x| x.to_lowercase());
+            for tokens in &input.load()? {
+                let mut out = Vec::new();
+                list(&tokens.body, tokens.get_name(), &mut out);
+                for (path, type_, value) in out {
+                    if let Some(filter) = &filter {
+                        if type_ != *filter && !path.to_lowercase().contains(filter) {
+                            continue;
+                        }
+                    }
+                    println!("{path}\t{type_}\t{value}");
+                }
+            }
+        }
+        Command::Query {
+            input,
+            pattern,
+            resolve,
+            format,
+        } => {
+            let mut out = Vec::new();
+            for set in &input.load()? {
+                for (path, token) in set.iter().filter(|(path, _)| path.matches(&pattern)) {
+                    let TokenOrGroup::Token { value, type_, .. } = token else {
+                        continue;
+                    };
+                    let resolved = match resolve {
+                        true => Some(token.try_resolve_css(set)),
+                        false => None,
+                    };
+                    if format == "text" {
+                        let resolved = match resolved {
+                            Some(Ok(x)) => format!("\t{x}"),
+                            Some(Err(err)) => format!("\terror: {err}"),
+                            None => String::new(),
+                        };
+                        println!(
+                            "{}/{path}\t{}\t{}{resolved}",
+                            set.get_name(),
+                            type_.as_str(),
+                            raw_value(value)
+                        );
+                        continue;
+                    }
+                    let mut x = json!({
+                        "set": set.get_name(),
+                        "path": path.to_string(),
+                        "type": type_,
+                        "value": value,
+                    });
+                    match resolved {
+                        Some(Ok(resolved)) => x["resolved"] = json!(resolved),
+                        Some(Err(err)) => x["error"] = json!(err),
+                        None => {}
+                    }
+                    out.push(x);
+                }
+            }
+            if format == "json" {
+                println!("{:#}", serde_json::Value::Array(out));
+            }
+        }
+        Command::Graph { input } => {
+            for tokens in &input.load()? {
+                print!("{}", tokens.reference_graph().to_dot());
+            }
+        }
+        Command::Serve { input, addr } => serve::serve(&addr, &input)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reads the text files under `dir`, skipping hidden directories, `target` and `node_modules`.
+fn read_sources(dir: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                read_sources(&path, out)?;
+            }
+        } else if let Ok(text) = fs::read_to_string(&path) {
+            out.push(text);
+        }
+    }
+    Ok(())
+}
+
+/// `{ severity, rule, set, path, message, source }` for `--format json`, where `source` is the
+/// file and JSON pointer of the token.
+fn diagnostic_json(tokens: &DesignTokens, x: &Diagnostic) -> serde_json::Value {
+    let mut out = json!(x);
+    out["set"] = json!(tokens.get_name());
+    out["source"] = json!(tokens.source(&x.path).to_string());
+    out
+}
+
+/// `{ bump, sets, removedSets, addedSets }` for `diff --format json`, with the changes and bump
+/// of each set under `sets`.
+fn diff_json(
+    bump: Bump,
+    diffs: &[(&str, TokenDiff)],
+    removed: &[&str],
+    added: &[&str],
+) -> serde_json::Value {
+    let sets: serde_json::Map<_, _> = diffs
+        .iter()
+        .map(|(set, diff)| {
+            let mut x = json!(diff);
+            x["bump"] = json!(diff.bump());
+            (set.to_string(), x)
+        })
+        .collect();
+    json!({
+        "bump": bump,
+        "sets": sets,
+        "removedSets": removed,
+        "addedSets": added,
+    })
+}
+
+fn print_diff(set: &str, diff: &TokenDiff) {
+    for x in &diff.removed {
+        println!("- {set}/{} = {}", x.path, x.value);
+    }
+    for x in &diff.added {
+        println!("+ {set}/{} = {}", x.path, x.value);
+    }
+    for x in &diff.renamed {
+        println!("> {set}/{} -> {set}/{}", x.from, x.to);
+    }
+    for x in &diff.changed {
+        println!("~ {set}/{}: {} -> {}", x.path, x.old, x.new);
+    }
+}
+
+fn list(token: &TokenOrGroup, path: &str, out: &mut Vec<(String, String, String)>) {
+    match token {
+        TokenOrGroup::Token { value, type_, .. } => {
+            let value = match value {
+                TokenValue::Single(expr) => expr.to_css(),
+                TokenValue::Dict(dict) => format!(
+                    "{{{}}}",
+                    dict.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            };
+            out.push((path.to_string(), type_.as_str().to_string(), value));
+        }
+        TokenOrGroup::Group(group) => {
+            for (key, value) in group {
+                list(value, &format!("{path}/{key}"), out);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_json_reports() {
+    let parse = |json: &str| {
+        ambient_design_tokens_core::parse_design_tokens(json, Some("theme.json"))
+            .unwrap()
+            .remove(0)
+    };
+    let old = parse(r##"{ "brand": { "$value": "#ff0000" }, "link": { "$value": "{missing}" } }"##);
+    let report = |x: Vec<Diagnostic>| {
+        let x: Vec<_> = x.iter().map(|x| diagnostic_json(&old, x)).collect();
+        json!({ "diagnostics": x })
+    };
+    assert_eq!(
+        report(old.validate()),
+        json!({ "diagnostics": [{
+            "severity": "error",
+            "rule": "missing-reference",
+            "set": "theme",
+            "path": "link",
+            "message": "reference {missing} does not resolve",
+            "source": "theme.json#/link",
+        }] })
+    );
+    let config: LintConfig = serde_json::from_str(r#"{ "palette": [] }"#).unwrap();
+    assert_eq!(
+        report(old.lint(&config)),
+        json!({ "diagnostics": [{
+            "severity": "warning",
+            "rule": "color-outside-palette",
+            "set": "theme",
+            "path": "brand",
+            "message": "color literal outside the palette",
+            "source": "theme.json#/brand",
+        }] })
+    );
+    let old = parse(r##"{ "brand": { "$value": "#ff0000" }, "pad": { "$value": "4px" } }"##);
+    let new = parse(r##"{ "brand": { "$value": "#00ff00" }, "gap": { "$value": "8px" } }"##);
+    let diff = old.diff(&new);
+    assert_eq!(
+        diff_json(Bump::Major, &[("theme", diff)], &["icons"], &[]),
+        json!({
+            "bump": "major",
+            "sets": { "theme": {
+                "removed": [{ "path": "pad", "value": "4px" }],
+                "added": [{ "path": "gap", "value": "8px" }],
+                "renamed": [],
+                "changed": [{ "path": "brand", "old": "#ff0000", "new": "#00ff00" }],
+                "bump": "major",
+            } },
+            "removedSets": ["icons"],
+            "addedSets": [],
+        })
+    );
+}
@@ -0,0 +1,138 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use ambient_design_tokens_core::{
+    emit::{styleguide::to_styleguide, CssEmitter, Emitter, JsonEmitter, ResolvedTokens},
+    TokenOrGroup,
+};
+use serde_json::json;
+
+use crate::Input;
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+    fn error(status: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.to_string(),
+        }
+    }
+}
+
+/// Serves until the listener fails. Requests are handled one at a time, which is plenty for a
+/// preview environment.
+pub fn serve(addr: &str, input: &Input) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(addr)?;
+    println!("serving on http://{}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        if let Err(err) = handle(stream?, input) {
+            eprintln!("error: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, input: &Input) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers; nothing here needs them.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let response = match request.split(' ').collect::<Vec<_>>()[..] {
+        ["GET", target, _] => {
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            let theme = query
+                .split('&')
+                .filter_map(|x| x.split_once('='))
+                .find(|(key, _)| *key == "theme")
+                .map(|(_, value)| decode(value));
+            respond(input, &decode(path), theme.as_deref())
+        }
+        _ => Response::error("405 Method Not Allowed", "only GET is supported"),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    Ok(())
+}
+
+fn respond(input: &Input, path: &str, theme: Option<&str>) -> Response {
+    // Loading on every request picks up edits to the input files without a watcher.
+    let data = match input.load_mode(theme.or(input.mode.as_deref())) {
+        Ok(data) => data,
+        Err(err) => return Response::error("500 Internal Server Error", err),
+    };
+    let tokens = ResolvedTokens::new(&data);
+    match path {
+        "/" => Response::ok("text/html; charset=utf-8", to_styleguide(&data)),
+        "/tokens.css" => Response::ok(
+            "text/css; charset=utf-8",
+            CssEmitter::default().emit(&tokens),
+        ),
+        "/tokens.json" => Response::ok("application/json", JsonEmitter.emit(&tokens)),
+        _ => {
+            let Some(token) = path.strip_prefix("/token/") else {
+                return Response::error("404 Not Found", format!("no such endpoint {path}"));
+            };
+            let found = data.iter().find_map(|set| match set.get(token)? {
+                TokenOrGroup::Token { type_, .. } => Some(json!({
+                    "set": set.get_name(),
+                    "path": token,
+                    "type": type_,
+                    "value": set.resolve_css(token),
+                })),
+                TokenOrGroup::Group(_) => None,
+            });
+            match found {
+                Some(x) => Response::ok("application/json", format!("{x:#}")),
+                None => Response::error("404 Not Found", format!("no such token {token}")),
+            }
+        }
+    }
+}
+
+/// Decodes `%XX` escapes and `+` in a URL path or query value.
+fn decode(x: &str) -> String {
+    let mut out = Vec::new();
+    let mut bytes = x.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex = [bytes.next(), bytes.next()];
+                match hex.map(|x| x.and_then(|x| (x as char).to_digit(16))) {
+                    [Some(hi), Some(lo)] => out.push((hi * 16 + lo) as u8),
+                    _ => {
+                        out.push(b);
+                        out.extend(hex.into_iter().flatten());
+                    }
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
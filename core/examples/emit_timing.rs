@@ -0,0 +1,30 @@
+//! Times CSS and Rust generation for the embedded Ambient tokens. Compare
+//! `cargo run --release --example emit_timing` with and without `--features rayon`.
+
+use std::time::Instant;
+
+use ambient_design_tokens_core::{
+    design_tokens,
+    emit::{CssEmitter, Emitter, ResolvedTokens, RustEmitter},
+};
+
+const RUNS: u32 = 20;
+
+fn main() {
+    let tokens = ResolvedTokens::new(design_tokens());
+    for emitter in [
+        &CssEmitter::default() as &dyn Emitter,
+        &RustEmitter::default(),
+    ] {
+        let start = Instant::now();
+        let mut len = 0;
+        for _ in 0..RUNS {
+            len = emitter.emit(&tokens).len();
+        }
+        println!(
+            "{}: {:?} per run, {len} bytes",
+            emitter.name(),
+            start.elapsed() / RUNS
+        );
+    }
+}
@@ -0,0 +1,40 @@
+/* C interface of ambient_design_tokens_core, enabled by the `capi` feature. Build a library with
+ *
+ *     cargo rustc -p ambient_design_tokens_core --release --features capi --crate-type staticlib
+ *
+ * Strings are NUL-terminated UTF-8. Strings returned by these functions are owned by the caller
+ * and must be released with design_tokens_string_free. */
+
+#ifndef DESIGN_TOKENS_H
+#define DESIGN_TOKENS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Parsed token sets. References may point into any of the sets. */
+typedef struct DesignTokens DesignTokens;
+
+/* Parses a token tree or a list of { fileName, body } sets. file_name may be NULL.
+ * Returns NULL on error, see design_tokens_last_error. */
+DesignTokens *design_tokens_parse(const char *json, const char *file_name);
+
+/* CSS custom properties for every set. */
+char *design_tokens_to_css(const DesignTokens *tokens);
+
+/* Output of a built-in format such as "css", "rust" or "json".
+ * Returns NULL for unknown formats, see design_tokens_last_error. */
+char *design_tokens_emit(const DesignTokens *tokens, const char *format);
+
+/* The error of the last failed call on this thread, or NULL. Valid until the next call. */
+const char *design_tokens_last_error(void);
+
+void design_tokens_free(DesignTokens *tokens);
+
+void design_tokens_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
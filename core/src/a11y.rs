@@ -0,0 +1,133 @@
+use csscolorparser::Color;
+use serde::Deserialize;
+
+use crate::{expression::Value, DesignTokens};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum WcagLevel {
+    #[default]
+    AA,
+    AAA,
+}
+impl WcagLevel {
+    pub fn min_ratio(&self, large_text: bool) -> f64 {
+        match (self, large_text) {
+            (WcagLevel::AA, false) => 4.5,
+            (WcagLevel::AA, true) => 3.,
+            (WcagLevel::AAA, false) => 7.,
+            (WcagLevel::AAA, true) => 4.5,
+        }
+    }
+}
+
+/// A foreground/background combination that must stay readable. Paths are `/`-separated.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastPair {
+    pub foreground: String,
+    pub background: String,
+    #[serde(default)]
+    pub level: WcagLevel,
+    #[serde(default)]
+    pub large_text: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContrastResult {
+    Checked {
+        pair: ContrastPair,
+        ratio: f64,
+        passes: bool,
+    },
+    /// One of the paths doesn't point at a color token.
+    Unresolved { pair: ContrastPair, path: String },
+}
+impl ContrastResult {
+    pub fn passes(&self) -> bool {
+        matches!(self, ContrastResult::Checked { passes: true, .. })
+    }
+}
+
+fn luminance(color: &Color) -> f64 {
+    let (r, g, b, _) = color.to_linear_rgba();
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// WCAG 2.x contrast ratio, from 1 to 21. A translucent foreground is composited over the
+/// background first; the background is treated as opaque.
+pub fn contrast_ratio(foreground: &Color, background: &Color) -> f64 {
+    let a = foreground.a;
+    let foreground = Color::new(
+        foreground.r * a + background.r * (1. - a),
+        foreground.g * a + background.g * (1. - a),
+        foreground.b * a + background.b * (1. - a),
+        1.,
+    );
+    let (l1, l2) = (luminance(&foreground), luminance(background));
+    (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+}
+
+impl DesignTokens {
+    pub fn contrast_report(&self, pairs: &[ContrastPair]) -> Vec<ContrastResult> {
+        let color = |path: &str| {
+            let path = path.split('/').map(|x| x.to_string()).collect::<Vec<_>>();
+            match self.resolve(&path) {
+                Some(Value::Color(color)) => Some(color),
+                _ => None,
+            }
+        };
+        pairs
+            .iter()
+            .map(|pair| {
+                let (Some(fg), Some(bg)) = (color(&pair.foreground), color(&pair.background))
+                else {
+                    let path = if color(&pair.foreground).is_none() {
+                        &pair.foreground
+                    } else {
+                        &pair.background
+                    };
+                    return ContrastResult::Unresolved {
+                        pair: pair.clone(),
+                        path: path.clone(),
+                    };
+                };
+                let ratio = contrast_ratio(&fg, &bg);
+                ContrastResult::Checked {
+                    pair: pair.clone(),
+                    ratio,
+                    passes: ratio >= pair.level.min_ratio(pair.large_text),
+                }
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test() {
+    let black = csscolorparser::parse("#000").unwrap();
+    let white = csscolorparser::parse("#fff").unwrap();
+    assert!((contrast_ratio(&black, &white) - 21.).abs() < 1e-9);
+    assert!((contrast_ratio(&white, &white) - 1.).abs() < 1e-9);
+
+    let tokens = crate::parse_design_tokens(
+        r##"{ "fg": { "$value": "#777777" }, "bg": { "$value": "#ffffff" } }"##,
+        None,
+    )
+    .unwrap();
+    let pair = |level, large_text| ContrastPair {
+        foreground: "fg".to_string(),
+        background: "bg".to_string(),
+        level,
+        large_text,
+    };
+    let report = tokens[0].contrast_report(&[
+        pair(WcagLevel::AA, false),
+        pair(WcagLevel::AA, true),
+        pair(WcagLevel::AAA, true),
+    ]);
+    // #777 on white is 4.48:1, just short of AA for body text.
+    assert_eq!(
+        report.iter().map(|x| x.passes()).collect::<Vec<_>>(),
+        vec![false, true, false]
+    );
+}
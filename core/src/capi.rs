@@ -0,0 +1,163 @@
+//! `extern "C"` functions for embedding in C and C++ toolchains, declared in
+//! `include/design_tokens.h`.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    emit::{EmitterRegistry, ResolvedTokens},
+    parse_design_tokens, to_css,
+    token_set::TokenSet,
+};
+
+/// Parsed token sets, opaque to C.
+pub struct DesignTokens {
+    sets: Vec<crate::DesignTokens>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(err: impl ToString) {
+    let err = CString::new(err.to_string().replace('\0', "")).unwrap();
+    LAST_ERROR.with(|x| *x.borrow_mut() = Some(err));
+}
+
+fn into_c_string(x: String) -> *mut c_char {
+    CString::new(x.replace('\0', "")).unwrap().into_raw()
+}
+
+/// Reads a C string, recording an error and returning `None` when it isn't valid UTF-8.
+///
+/// # Safety
+/// `x` must be null or a valid NUL-terminated string.
+unsafe fn str_arg<'a>(x: *const c_char, name: &str) -> Option<&'a str> {
+    if x.is_null() {
+        set_error(format!("{name} is null"));
+        return None;
+    }
+    match CStr::from_ptr(x).to_str() {
+        Ok(x) => Some(x),
+        Err(err) => {
+            set_error(format!("{name}: {err}"));
+            None
+        }
+    }
+}
+
+/// # Safety
+/// `json` must be a valid NUL-terminated string and `file_name` null or one.
+#[no_mangle]
+pub unsafe extern "C" fn design_tokens_parse(
+    json: *const c_char,
+    file_name: *const c_char,
+) -> *mut DesignTokens {
+    let Some(json) = str_arg(json, "json") else {
+        return ptr::null_mut();
+    };
+    let file_name = match file_name.is_null() {
+        true => None,
+        false => match str_arg(file_name, "file_name") {
+            Some(x) => Some(x),
+            None => return ptr::null_mut(),
+        },
+    };
+    match parse_design_tokens(json, file_name) {
+        Ok(sets) => Box::into_raw(Box::new(DesignTokens {
+            sets: TokenSet::new(sets).into_sets(),
+        })),
+        Err(err) => {
+            set_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// `tokens` must come from [`design_tokens_parse`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn design_tokens_to_css(tokens: *const DesignTokens) -> *mut c_char {
+    into_c_string(to_css(&(*tokens).sets))
+}
+
+/// # Safety
+/// `tokens` must come from [`design_tokens_parse`] and not have been freed, and `format` must be
+/// a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn design_tokens_emit(
+    tokens: *const DesignTokens,
+    format: *const c_char,
+) -> *mut c_char {
+    let Some(format) = str_arg(format, "format") else {
+        return ptr::null_mut();
+    };
+    match EmitterRegistry::with_builtins().emit(format, &ResolvedTokens::new(&(*tokens).sets)) {
+        Some(x) => into_c_string(x),
+        None => {
+            set_error(format!("unknown format {format:?}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn design_tokens_last_error() -> *const c_char {
+    LAST_ERROR.with(|x| x.borrow().as_ref().map_or(ptr::null(), |x| x.as_ptr()))
+}
+
+/// # Safety
+/// `tokens` must be null or come from [`design_tokens_parse`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn design_tokens_free(tokens: *mut DesignTokens) {
+    if !tokens.is_null() {
+        drop(Box::from_raw(tokens));
+    }
+}
+
+/// # Safety
+/// `string` must be null or a string returned by this library that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn design_tokens_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[test]
+fn test() {
+    let header = include_str!("../include/design_tokens.h");
+    for name in include_str!("./capi.rs")
+        .lines()
+        .filter_map(|x| x.split("extern \"C\" fn ").nth(1))
+        .filter_map(|x| x.split('(').next())
+    {
+        assert!(
+            header.contains(&format!("{name}(")),
+            "{name} missing from the header"
+        );
+    }
+    unsafe {
+        let tokens = design_tokens_parse(
+            c"{ \"gap\": { \"$value\": \"4px\" } }".as_ptr(),
+            c"tokens.json".as_ptr(),
+        );
+        assert!(!tokens.is_null());
+        let css = design_tokens_to_css(tokens);
+        assert_eq!(
+            CStr::from_ptr(css).to_str().unwrap(),
+            ".tokens { --gap: 4px; }"
+        );
+        design_tokens_string_free(css);
+        assert!(design_tokens_emit(tokens, c"nope".as_ptr()).is_null());
+        assert_eq!(
+            CStr::from_ptr(design_tokens_last_error()).to_str().unwrap(),
+            "unknown format \"nope\""
+        );
+        design_tokens_free(tokens);
+        assert!(design_tokens_parse(c"{".as_ptr(), ptr::null()).is_null());
+    }
+}
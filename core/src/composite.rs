@@ -0,0 +1,280 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::{
+    expression::{Expression, NumberType, Value},
+    CssOptions, DesignTokens, TokenOrGroup, TokenType, TokenValue,
+};
+
+/// Rust definitions referenced by composite constants, emitted once next to the set modules.
+pub(crate) fn rust_prelude(types: &[TokenType]) -> String {
+    types
+        .iter()
+        .unique_by(|x| x.as_str().to_string())
+        .filter_map(|type_| match type_ {
+            TokenType::Transition => Some(
+                "#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Transition { pub duration: std::time::Duration, pub delay: std::time::Duration, pub timing_function: TimingFunction }\n#[derive(Debug, Clone, Copy, PartialEq)]\npub enum TimingFunction { CubicBezier([f32; 4]), Steps(u32, StepPosition) }\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum StepPosition { JumpStart, JumpEnd, JumpNone, JumpBoth }",
+            ),
+            _ => None,
+        })
+        .join("\n")
+}
+
+/// A single CSS value for composite types that have a shorthand, e.g. `transition`.
+pub(crate) fn css_shorthand(
+    tokens: &DesignTokens,
+    options: &CssOptions,
+    type_: &TokenType,
+    dict: &IndexMap<String, Expression>,
+) -> Option<String> {
+    let field = |key: &str| dict.get(key).map(|x| x.to_css_in(tokens, options));
+    // Unitless times are not valid CSS, so they are taken as milliseconds.
+    let time = |key: &str| match dict.get(key) {
+        Some(Expression::Value(Value::Number(v, NumberType::None))) => {
+            Some(Value::Number(*v, NumberType::Milliseconds).to_css())
+        }
+        _ => field(key),
+    };
+    let width = match dict.get("width") {
+        Some(Expression::Value(Value::Number(v, NumberType::None))) => {
+            Some(Value::Number(*v, NumberType::Pixels).to_css_with(options))
+        }
+        _ => field("width"),
+    };
+    match type_ {
+        TokenType::Transition => Some(
+            [time("duration"), field("timingFunction"), time("delay")]
+                .into_iter()
+                .flatten()
+                .join(" "),
+        ),
+        TokenType::Border => Some(
+            [width, field("style"), field("color")]
+                .into_iter()
+                .flatten()
+                .join(" "),
+        ),
+        // CSS has no custom dash patterns, so object stroke styles fall back to the closest keyword.
+        TokenType::StrokeStyle => Some(
+            match dict.contains_key("dashArray") {
+                true => "dashed",
+                false => "solid",
+            }
+            .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/// The Rust type and value of a composite constant, with every field resolved, or why a field
+/// has no Rust equivalent. Such tokens are written as fields instead.
+pub(crate) fn rust_composite(
+    tokens: &DesignTokens,
+    type_: &TokenType,
+    dict: &IndexMap<String, Expression>,
+) -> Option<Result<(&'static str, String), String>> {
+    let field = |key: &str| dict.get(key).map(|x| x.get_value(tokens));
+    let transition = || {
+        Ok((
+            "super::Transition",
+            format!(
+                "super::Transition {{ duration: {}, delay: {}, timing_function: {} }}",
+                rust_duration(field("duration"))?,
+                rust_duration(field("delay"))?,
+                rust_timing_function(field("timingFunction"))?
+            ),
+        ))
+    };
+    match type_ {
+        TokenType::Transition => Some(transition()),
+        _ => None,
+    }
+}
+
+impl DesignTokens {
+    /// Why a composite token has no Rust equivalent and is written as fields, if it is. Tokens
+    /// that can't be resolved are left to [`TokenOrGroup::try_resolve_css`].
+    pub(crate) fn composite_error(&self, token: &TokenOrGroup) -> Option<String> {
+        let TokenOrGroup::Token {
+            value: TokenValue::Dict(dict),
+            type_,
+            ..
+        } = token
+        else {
+            return None;
+        };
+        if token.resolve_error(self).is_some() {
+            return None;
+        }
+        rust_composite(self, type_, dict)?.err()
+    }
+}
+
+/// Durations without a time unit are taken as milliseconds.
+fn rust_duration(value: Option<Value>) -> Result<String, String> {
+    match value {
+        Some(value @ Value::Number(_, NumberType::Seconds | NumberType::Milliseconds)) => {
+            Ok(value.to_rust())
+        }
+        Some(Value::Number(v, _)) => Ok(Value::Number(v, NumberType::Milliseconds).to_rust()),
+        None => Ok("std::time::Duration::ZERO".to_string()),
+        Some(value) => Err(format!("{} is not a duration", value.describe())),
+    }
+}
+
+/// Keywords and `steps()` are written as the easing curve or steps they stand for.
+fn rust_timing_function(value: Option<Value>) -> Result<String, String> {
+    let points = match value {
+        Some(Value::CubicBezier(points)) => points,
+        Some(Value::Any(name)) => match name.as_str() {
+            "linear" => [0., 0., 1., 1.],
+            "ease" => [0.25, 0.1, 0.25, 1.],
+            "ease-in" => [0.42, 0., 1., 1.],
+            "ease-out" => [0., 0., 0.58, 1.],
+            "ease-in-out" => [0.42, 0., 0.58, 1.],
+            "step-start" => return Ok(rust_steps(1, "JumpStart")),
+            "step-end" => return Ok(rust_steps(1, "JumpEnd")),
+            _ => {
+                let (count, position) =
+                    steps(&name).ok_or_else(|| format!("unsupported timing function {name:?}"))?;
+                return Ok(rust_steps(count, position));
+            }
+        },
+        None => [0.25, 0.1, 0.25, 1.],
+        Some(value) => return Err(format!("{} is not a timing function", value.describe())),
+    };
+    Ok(format!(
+        "super::TimingFunction::CubicBezier({})",
+        Value::CubicBezier(points).to_rust()
+    ))
+}
+
+fn rust_steps(count: u32, position: &str) -> String {
+    format!("super::TimingFunction::Steps({count}, super::StepPosition::{position})")
+}
+
+/// The step count and position of `steps(count[, position])`.
+fn steps(name: &str) -> Option<(u32, &'static str)> {
+    let args = name.strip_prefix("steps(")?.strip_suffix(')')?;
+    let (count, position) = args.split_once(',').unwrap_or((args, "end"));
+    let position = match position.trim() {
+        "jump-start" | "start" => "JumpStart",
+        "jump-end" | "end" => "JumpEnd",
+        "jump-none" => "JumpNone",
+        "jump-both" => "JumpBoth",
+        _ => return None,
+    };
+    Some((count.trim().parse().ok().filter(|&x| x > 0)?, position))
+}
+
+#[test]
+fn test_transition() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "ease": { "$value": [0.4, 0, 0.2, 1], "$type": "cubicBezier" },
+            "fade": {
+                "$type": "transition",
+                "$value": { "duration": "200ms", "delay": 0, "timingFunction": "{ease}" }
+            },
+            "slide": { "$type": "transition", "$value": { "duration": "0.3s", "timingFunction": "ease-out" } }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css_with_selector(":root");
+    assert!(css.contains(":root { --fade: 200ms var(--ease) 0ms; }"));
+    assert!(css.contains(":root { --slide: 0.3s ease-out; }"));
+    let rust = crate::to_rust(&tokens);
+    assert!(rust.starts_with("#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Transition {"));
+    assert!(rust.contains(
+        "    pub const FADE: super::Transition = super::Transition {
+        duration: std::time::Duration::from_millis(200),
+        delay: std::time::Duration::from_millis(0),
+        timing_function: super::TimingFunction::CubicBezier([0.4, 0., 0.2, 1.]),
+    };"
+    ));
+    assert!(
+        rust.contains("timing_function: super::TimingFunction::CubicBezier([0., 0., 0.58, 1.])")
+    );
+}
+
+#[test]
+fn test_steps() {
+    let mut tokens = crate::parse_design_tokens(
+        r#"{
+            "blink": { "$type": "transition", "$value": { "duration": "1s", "timingFunction": "step-end" } },
+            "tick": { "$type": "transition", "$value": { "duration": "1s", "timingFunction": "steps(4, jump-start)" } },
+            "wobble": { "$type": "transition", "$value": { "duration": "1s", "timingFunction": "wobbly" } }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let rust = tokens.to_rust();
+    assert!(rust.contains(
+        "timing_function: super::TimingFunction::Steps(1, super::StepPosition::JumpEnd)"
+    ));
+    assert!(rust.contains(
+        "timing_function: super::TimingFunction::Steps(4, super::StepPosition::JumpStart)"
+    ));
+    assert!(rust.contains(
+        "pub const WOBBLE: &[(&str, &str)] = &[(\"duration\", \"1s\"), (\"timingFunction\", \"wobbly\")];"
+    ));
+    assert_eq!(
+        tokens.validate(),
+        [crate::validate::Diagnostic {
+            severity: crate::validate::Severity::Error,
+            rule: "unsupported-composite",
+            path: crate::TokenPath::parse("wobble"),
+            message: "unsupported timing function \"wobbly\", written as fields in Rust"
+                .to_string(),
+        }]
+    );
+    assert_eq!(
+        tokens.check(&CssOptions::default()),
+        Err("wobble: unsupported timing function \"wobbly\"".to_string())
+    );
+    tokens.options.mode = crate::ResolutionMode::Lenient;
+    assert_eq!(tokens.check(&CssOptions::default()), Ok(()));
+}
+
+#[test]
+fn test_number_lists() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "dash": { "$type": "strokeStyle", "$value": { "dashArray": [4, 2, 4, 2], "lineCap": "round" } },
+            "fade": { "$type": "transition", "$value": { "duration": "1s", "timingFunction": [0.4, 0, 0.2, 1] } }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("(\"dashArray\", \"4 2 4 2\")"));
+    assert!(
+        rust.contains("timing_function: super::TimingFunction::CubicBezier([0.4, 0., 0.2, 1.])")
+    );
+    assert!(tokens[0]
+        .to_dart()
+        .contains("static const String dashDasharray = '4 2 4 2';"));
+}
+
+#[test]
+fn test_border() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "dashed": { "$type": "strokeStyle", "$value": { "dashArray": ["0.5rem", "{gap}"], "lineCap": "round" } },
+            "dotted": { "$type": "strokeStyle", "$value": "dotted" },
+            "gap": { "$value": "0.25rem" },
+            "outline": { "$type": "border", "$value": { "color": "#ff0000", "width": 2, "style": "{dotted}" } }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css_with_selector(":root");
+    assert!(css.contains(":root { --dashed: dashed; }"));
+    assert!(css.contains(":root { --dotted: dotted; }"));
+    assert!(css.contains(":root { --outline: 2px var(--dotted) #ff0000; }"));
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("(\"dashArray\", \"0.5rem 0.25rem\")"));
+    assert!(rust.contains("(\"style\", \"dotted\")"));
+}
@@ -0,0 +1,304 @@
+//! Deserializes token trees in one pass. An untagged enum would buffer every group before
+//! deciding whether it is a token, copying the whole tree once per level of nesting.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::{
+    de::{self, value::SeqAccessDeserializer, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    expression::ExpressionVisitor, extensions::Extensions, Deprecated, TokenOrGroup, TokenType,
+    TokenValue,
+};
+
+/// Keys read as token properties when an object has no `$value`, and ignored otherwise.
+const LEGACY_KEYS: [&str; 4] = ["value", "type", "description", "deprecated"];
+
+/// A token or group, or why the object is neither. The error only matters once the parent
+/// turns out to be a group, since tokens ignore keys they don't know.
+type Parsed = Result<TokenOrGroup, String>;
+
+impl<'de> Deserialize<'de> for TokenOrGroup {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_map(NodeVisitor(&mut Vec::new()))?
+            .map_err(de::Error::custom)
+    }
+}
+impl<'de> Deserialize<'de> for TokenValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor(&[]))
+    }
+}
+
+/// The group at the root of a token file.
+pub(crate) struct Body(pub(crate) TokenOrGroup);
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match TokenOrGroup::deserialize(deserializer)? {
+            TokenOrGroup::Token { .. } => Err(de::Error::custom("expected a group of tokens")),
+            group => Ok(Body(group)),
+        }
+    }
+}
+
+/// `err` prefixed with the `/`-separated token path, if any.
+pub(crate) fn located(path: &[String], err: impl fmt::Display) -> String {
+    match path {
+        [] => err.to_string(),
+        path => format!("{}: {err}", path.join("/")),
+    }
+}
+
+/// A token or group at the path, which names it in errors.
+struct NodeVisitor<'a>(&'a mut Vec<String>);
+impl<'de> Visitor<'de> for NodeVisitor<'_> {
+    type Value = Parsed;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a token or group object")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Parsed, A::Error> {
+        let mut value = None;
+        let mut type_ = None;
+        let mut extensions = None;
+        let mut description = None;
+        let mut deprecated = None;
+        let mut children = IndexMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "$value" => value = Some(map.next_value_seed(ValueVisitor(self.0))?),
+                "$type" => type_ = Some(map.next_value::<TokenType>()?),
+                "$extensions" => extensions = Some(map.next_value::<Option<Extensions>>()?),
+                "$description" => description = Some(map.next_value::<Option<String>>()?),
+                "$deprecated" => deprecated = Some(map.next_value::<Option<Deprecated>>()?),
+                _ if LEGACY_KEYS.contains(&key.as_str()) => {
+                    children.insert(key, Child::Other(map.next_value()?));
+                }
+                _ => {
+                    self.0.push(key);
+                    let child = map.next_value_seed(ChildVisitor(self.0))?;
+                    children.insert(self.0.pop().unwrap(), child);
+                }
+            }
+        }
+        let legacy = |key: &str| match children.get(key) {
+            Some(Child::Other(x)) => Some(x),
+            _ => None,
+        };
+        if value.is_none() {
+            value = legacy("value").and_then(|x| TokenValue::deserialize(x).ok());
+        }
+        if let Some(value) = value {
+            return Ok(token(type_, description, deprecated, legacy).map(
+                |(type_, description, deprecated)| TokenOrGroup::Token {
+                    value,
+                    type_,
+                    extensions: extensions.flatten(),
+                    description,
+                    deprecated,
+                },
+            ));
+        }
+        if type_.is_some() || extensions.is_some() || description.is_some() || deprecated.is_some()
+        {
+            return Ok(Err(located(self.0, "group properties are not supported")));
+        }
+        let mut group = IndexMap::with_capacity(children.len());
+        for (key, child) in children {
+            let node = match child {
+                Child::Node(Ok(x)) => x,
+                Child::Node(Err(err)) => return Ok(Err(err)),
+                Child::Other(x) => {
+                    self.0.push(key.clone());
+                    let node = x
+                        .deserialize_map(NodeVisitor(self.0))
+                        .unwrap_or_else(|err| Err(located(self.0, err)));
+                    self.0.pop();
+                    match node {
+                        Ok(x) => x,
+                        Err(err) => return Ok(Err(err)),
+                    }
+                }
+            };
+            group.insert(key, node);
+        }
+        Ok(Ok(TokenOrGroup::Group(group)))
+    }
+}
+
+/// The type, description and deprecation of a token, falling back to the legacy keys.
+fn token<'a>(
+    type_: Option<TokenType>,
+    description: Option<Option<String>>,
+    deprecated: Option<Option<Deprecated>>,
+    legacy: impl Fn(&str) -> Option<&'a serde_json::Value>,
+) -> Result<(TokenType, Option<String>, Option<Deprecated>), String> {
+    fn read<'a, T: Deserialize<'a> + Default>(
+        value: Option<T>,
+        legacy: Option<&'a serde_json::Value>,
+    ) -> Result<T, String> {
+        match (value, legacy) {
+            (Some(x), _) => Ok(x),
+            (None, Some(x)) => T::deserialize(x).map_err(|x| x.to_string()),
+            (None, None) => Ok(T::default()),
+        }
+    }
+    Ok((
+        read(type_, legacy("type"))?,
+        read(description, legacy("description"))?,
+        read(deprecated, legacy("deprecated"))?,
+    ))
+}
+
+/// A child of a token or group. Anything but an object is kept as is, since tokens ignore
+/// unknown keys.
+enum Child {
+    Node(Parsed),
+    Other(serde_json::Value),
+}
+struct ChildVisitor<'a>(&'a mut Vec<String>);
+impl<'de> DeserializeSeed<'de> for ChildVisitor<'_> {
+    type Value = Child;
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Child, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+impl<'de> Visitor<'de> for ChildVisitor<'_> {
+    type Value = Child;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Child, A::Error> {
+        NodeVisitor(self.0).visit_map(map).map(Child::Node)
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Child, A::Error> {
+        serde_json::Value::deserialize(SeqAccessDeserializer::new(seq)).map(Child::Other)
+    }
+    fn visit_bool<E>(self, x: bool) -> Result<Child, E> {
+        Ok(Child::Other(x.into()))
+    }
+    fn visit_i64<E>(self, x: i64) -> Result<Child, E> {
+        Ok(Child::Other(x.into()))
+    }
+    fn visit_u64<E>(self, x: u64) -> Result<Child, E> {
+        Ok(Child::Other(x.into()))
+    }
+    fn visit_f64<E>(self, x: f64) -> Result<Child, E> {
+        Ok(Child::Other(x.into()))
+    }
+    fn visit_str<E>(self, x: &str) -> Result<Child, E> {
+        Ok(Child::Other(x.into()))
+    }
+    fn visit_unit<E>(self) -> Result<Child, E> {
+        Ok(Child::Other(serde_json::Value::Null))
+    }
+}
+
+/// A token's value, either one expression or the fields of a composite token.
+struct ValueVisitor<'a>(&'a [String]);
+impl<'de> DeserializeSeed<'de> for ValueVisitor<'_> {
+    type Value = TokenValue;
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<TokenValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+impl<'de> Visitor<'de> for ValueVisitor<'_> {
+    type Value = TokenValue;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an expression or an object of expressions")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TokenValue, A::Error> {
+        let mut dict = IndexMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            dict.insert(key, map.next_value_seed(ExpressionVisitor(self.0))?);
+        }
+        Ok(TokenValue::Dict(dict))
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<TokenValue, A::Error> {
+        ExpressionVisitor(self.0)
+            .visit_seq(seq)
+            .map(TokenValue::Single)
+    }
+    fn visit_bool<E: de::Error>(self, x: bool) -> Result<TokenValue, E> {
+        ExpressionVisitor(self.0)
+            .visit_bool(x)
+            .map(TokenValue::Single)
+    }
+    fn visit_i64<E: de::Error>(self, x: i64) -> Result<TokenValue, E> {
+        ExpressionVisitor(self.0)
+            .visit_i64(x)
+            .map(TokenValue::Single)
+    }
+    fn visit_u64<E: de::Error>(self, x: u64) -> Result<TokenValue, E> {
+        ExpressionVisitor(self.0)
+            .visit_u64(x)
+            .map(TokenValue::Single)
+    }
+    fn visit_f64<E: de::Error>(self, x: f64) -> Result<TokenValue, E> {
+        ExpressionVisitor(self.0)
+            .visit_f64(x)
+            .map(TokenValue::Single)
+    }
+    fn visit_str<E: de::Error>(self, x: &str) -> Result<TokenValue, E> {
+        ExpressionVisitor(self.0)
+            .visit_str(x)
+            .map(TokenValue::Single)
+    }
+}
+
+#[test]
+fn test() {
+    let json = r##"{
+        "font": {
+            "value": { "fontSize": 20 },
+            "type": "custom-fontStyle",
+            "extensions": { "org.example": { "styleId": "S:1" } }
+        },
+        "brand": { "red": { "$value": "#ff0000", "$description": "Red" } }
+    }"##;
+    let read = crate::read_design_tokens(json.as_bytes(), Some("t.json")).unwrap();
+    assert_eq!(
+        read[0].body,
+        crate::parse_design_tokens(json, None).unwrap()[0].body
+    );
+    assert_eq!(read[0].file_name.as_deref(), Some("t.json"));
+    let paths = read[0]
+        .iter()
+        .map(|(x, _)| x.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["font", "brand/red"]);
+
+    let list = r##" [{ "fileName": "a", "body": { "gap": { "$value": "4px" } } }]"##;
+    assert_eq!(
+        crate::read_design_tokens(list.as_bytes(), None).unwrap()[0].get_name(),
+        "a"
+    );
+
+    let err = crate::parse_design_tokens(r##"{ "g": { "a": "x" } }"##, None).unwrap_err();
+    assert!(
+        err.to_string().contains("expected a token or group object"),
+        "{err}"
+    );
+    assert!(crate::parse_design_tokens(r##"{ "g": { "$type": "color" } }"##, None).is_err());
+    for json in [
+        r##"{ "a": { "b": { "$value": "{x.y" } } }"##,
+        r##"{ "a": { "b": { "$value": { "color": "{x.y" } } } }"##,
+        r##"{ "a": { "b": { "$value": ["{x.y"] } } }"##,
+    ] {
+        let err = crate::parse_design_tokens(json, None).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(r#"a/b: Invalid expression "{x.y" at column 5: expected"#),
+            "{err}"
+        );
+    }
+    let err = crate::parse_design_tokens(r##"{ "a": { "$value": "#zz" } }"##, None).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with(r##"a: Invalid expression "#zz" at column 4: expected"##),
+        "{err}"
+    );
+}
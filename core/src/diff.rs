@@ -0,0 +1,319 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{expression::Expression, DesignTokens, TokenOrGroup, TokenValue};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffEntry {
+    pub path: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Renamed {
+    pub from: String,
+    pub to: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Changed {
+    pub path: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Token-level differences between two sets. Paths are `/`-separated and values are resolved,
+/// or written as in the source followed by the error when they can't be.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TokenDiff {
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub renamed: Vec<Renamed>,
+    pub changed: Vec<Changed>,
+}
+impl TokenDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+    /// The version bump the difference calls for: removed and renamed tokens break users of the
+    /// generated code, changed values are minor and added tokens a patch.
+    pub fn bump(&self) -> Bump {
+        if !self.removed.is_empty() || !self.renamed.is_empty() {
+            Bump::Major
+        } else if !self.changed.is_empty() {
+            Bump::Minor
+        } else if !self.added.is_empty() {
+            Bump::Patch
+        } else {
+            Bump::None
+        }
+    }
+}
+
+/// A semver bump, ordered from none to major.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    #[default]
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+impl Bump {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+struct Snapshot {
+    raw: String,
+    value: String,
+}
+
+fn snapshot(tokens: &DesignTokens) -> IndexMap<String, Snapshot> {
+    tokens
+        .tokens()
+        .into_iter()
+        .map(|(path, token)| {
+            // Values that can't be resolved are written as in the source, followed by the error.
+            let error = token.resolve_error(tokens);
+            let (raw, value) = match token {
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(expr),
+                    ..
+                } => (
+                    expr.to_css(),
+                    error.is_none().then(|| token.resolve_css(tokens)),
+                ),
+                TokenOrGroup::Token {
+                    value: TokenValue::Dict(dict),
+                    ..
+                } => (
+                    fields(dict, |x| x.to_css()),
+                    error
+                        .is_none()
+                        .then(|| fields(dict, |x| x.get_value(tokens).to_css())),
+                ),
+                TokenOrGroup::Group(_) => unreachable!(),
+            };
+            let value = value.unwrap_or_else(|| format!("{raw} ({})", error.unwrap_or_default()));
+            (path.join("/"), Snapshot { raw, value })
+        })
+        .collect()
+}
+
+/// Composite fields sorted by key, so that reordering them isn't a change.
+fn fields(dict: &IndexMap<String, Expression>, css: impl Fn(&Expression) -> String) -> String {
+    dict.iter()
+        .sorted_by(|a, b| a.0.cmp(b.0))
+        .map(|(key, value)| format!("{}: {}", key, css(value)))
+        .join("; ")
+}
+
+impl DesignTokens {
+    pub fn diff(&self, other: &DesignTokens) -> TokenDiff {
+        let old = snapshot(self);
+        let new = snapshot(other);
+        let mut diff = TokenDiff::default();
+        let mut added = Vec::new();
+        for (path, b) in &new {
+            match old.get(path) {
+                Some(a) if a.value != b.value => diff.changed.push(Changed {
+                    path: path.clone(),
+                    old: a.value.clone(),
+                    new: b.value.clone(),
+                }),
+                Some(_) => {}
+                None => added.push(path),
+            }
+        }
+        let mut removed = old.keys().filter(|x| !new.contains_key(*x)).collect_vec();
+
+        // A removed token whose raw and resolved value reappear under a new path is a rename.
+        // Prefer candidates that keep the last path segment, e.g. `Brand/Red` -> `Palette/Red`.
+        let last = |x: &str| x.rsplit('/').next().unwrap_or_default().to_string();
+        removed.retain(|from| {
+            let a = &old[*from];
+            let candidates = added
+                .iter()
+                .enumerate()
+                .filter(|(_, to)| new[**to].raw == a.raw && new[**to].value == a.value)
+                .collect_vec();
+            let Some(&(index, to)) = candidates
+                .iter()
+                .find(|(_, to)| last(to) == last(from))
+                .or(candidates.first())
+            else {
+                return true;
+            };
+            diff.renamed.push(Renamed {
+                from: (*from).clone(),
+                to: (*to).clone(),
+                value: a.value.clone(),
+            });
+            added.remove(index);
+            false
+        });
+
+        diff.added = added
+            .into_iter()
+            .map(|path| DiffEntry {
+                path: path.clone(),
+                value: new[path].value.clone(),
+            })
+            .collect();
+        diff.removed = removed
+            .into_iter()
+            .map(|path| DiffEntry {
+                path: path.clone(),
+                value: old[path].value.clone(),
+            })
+            .collect();
+        diff
+    }
+}
+
+#[test]
+fn test() {
+    let old = crate::parse_design_tokens(
+        r##"{ "a": { "$value": "#ff0000" }, "b": { "$value": 4 }, "c": { "$value": 8 } }"##,
+        None,
+    )
+    .unwrap();
+    let new = crate::parse_design_tokens(
+        r##"{ "x": { "a": { "$value": "#ff0000" } }, "b": { "$value": 6 }, "d": { "$value": 1 } }"##,
+        None,
+    )
+    .unwrap();
+    let diff = old[0].diff(&new[0]);
+    assert_eq!(
+        diff.renamed,
+        vec![Renamed {
+            from: "a".to_string(),
+            to: "x/a".to_string(),
+            value: "#ff0000".to_string()
+        }]
+    );
+    assert_eq!(
+        diff.changed,
+        vec![Changed {
+            path: "b".to_string(),
+            old: "4".to_string(),
+            new: "6".to_string()
+        }]
+    );
+    assert_eq!(diff.added[0].path, "d");
+    assert_eq!(diff.removed[0].path, "c");
+    assert!(old[0].diff(&old[0]).is_empty());
+    assert_eq!(diff.bump(), Bump::Major);
+    assert_eq!(old[0].diff(&old[0]).bump(), Bump::None);
+    let added = TokenDiff {
+        added: diff.added.clone(),
+        ..Default::default()
+    };
+    assert_eq!(added.bump(), Bump::Patch);
+    assert_eq!(
+        serde_json::to_value(&added).unwrap()["added"],
+        serde_json::json!([{ "path": "d", "value": "1" }])
+    );
+}
+
+#[test]
+fn test_field_order() {
+    let tokens = |fields: &str| {
+        crate::parse_design_tokens(
+            &format!(r#"{{ "body": {{ "$value": {{ {fields} }}, "$type": "typography" }} }}"#),
+            None,
+        )
+        .unwrap()
+        .remove(0)
+    };
+    let old = tokens(r#""fontSize": "16px", "fontWeight": 400"#);
+    let new = tokens(r#""fontWeight": 400, "fontSize": "16px""#);
+    assert!(old.diff(&new).is_empty());
+    let changed = old.diff(&tokens(r#""fontWeight": 700, "fontSize": "16px""#));
+    assert_eq!(
+        changed.changed,
+        vec![Changed {
+            path: "body".to_string(),
+            old: "fontSize: 16px; fontWeight: 400".to_string(),
+            new: "fontSize: 16px; fontWeight: 700".to_string()
+        }]
+    );
+}
+
+#[test]
+fn test_bump() {
+    let tokens = |json: &str| crate::parse_design_tokens(json, None).unwrap().remove(0);
+    let old = tokens(r##"{ "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" } }"##);
+    for (new, bump) in [
+        (r##"{ "red": { "$value": "#ff0000" } }"##, Bump::Major),
+        (
+            r##"{ "brand": { "red": { "$value": "#ff0000" } }, "gap": { "$value": "4px" } }"##,
+            Bump::Major,
+        ),
+        (
+            r##"{ "red": { "$value": "#ee0000" }, "gap": { "$value": "4px" } }"##,
+            Bump::Minor,
+        ),
+        (
+            r##"{ "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" }, "pad": { "$value": "8px" } }"##,
+            Bump::Patch,
+        ),
+        (
+            r##"{ "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" } }"##,
+            Bump::None,
+        ),
+    ] {
+        assert_eq!(old.diff(&tokens(new)).bump(), bump, "{new}");
+    }
+
+    let new = tokens(
+        r##"{ "brand": { "red": { "$value": "#ff0000" } }, "pad": { "$value": "2px" }, "gap": { "$value": "8px" } }"##,
+    );
+    let old = tokens(
+        r##"{ "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" }, "margin": { "$value": "1px" } }"##,
+    );
+    assert_eq!(
+        serde_json::to_value(old.diff(&new)).unwrap(),
+        serde_json::json!({
+            "added": [{ "path": "pad", "value": "2px" }],
+            "removed": [{ "path": "margin", "value": "1px" }],
+            "renamed": [{ "from": "red", "to": "brand/red", "value": "#ff0000" }],
+            "changed": [{ "path": "gap", "old": "4px", "new": "8px" }]
+        })
+    );
+}
+
+#[test]
+fn test_unresolved() {
+    let tokens = |json: &str| crate::parse_design_tokens(json, None).unwrap().remove(0);
+    let old = tokens(r##"{ "a": { "$value": "{missing}" }, "b": { "$value": "#ff0000" } }"##);
+    let new = tokens(r##"{ "a": { "$value": "4px" }, "b": { "$value": "{b} * 2" } }"##);
+    assert_eq!(
+        old.diff(&new).changed,
+        [
+            Changed {
+                path: "a".to_string(),
+                old: "var(--missing) (unresolved reference {missing})".to_string(),
+                new: "4px".to_string(),
+            },
+            Changed {
+                path: "b".to_string(),
+                old: "#ff0000".to_string(),
+                new: "calc(var(--b) * 2) (unresolved reference {b})".to_string(),
+            },
+        ]
+    );
+}
@@ -0,0 +1,129 @@
+use convert_case::{Case, Casing};
+
+use super::fmt_float;
+use crate::{
+    expression::{NumberType, Value},
+    naming::Naming,
+    resolve_single, DesignTokens, TokenOrGroup, TokenValue,
+};
+
+/// Contents of `res/values/colors.xml` and `res/values/dimens.xml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AndroidResources {
+    pub colors: String,
+    pub dimens: String,
+}
+
+fn resource_name(naming: &Naming, prefix: &str, path: &[String]) -> String {
+    let name = format!(
+        "{prefix}{}",
+        naming.name("android", path, Some(Case::Snake), "_")
+    );
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+fn resource(name: &str, key: &str, value: &Value) -> Option<(bool, String)> {
+    match value {
+        Value::Color(c) => {
+            let [r, g, b, a] = c.to_rgba8();
+            Some((
+                true,
+                format!("    <color name=\"{name}\">#{a:02X}{r:02X}{g:02X}{b:02X}</color>"),
+            ))
+        }
+        Value::Number(v, NumberType::None | NumberType::Pixels) => {
+            let unit = match key.to_case(Case::Kebab).as_str() {
+                "font-weight" => return None,
+                "font-size" | "line-height" | "letter-spacing" => "sp",
+                _ => "dp",
+            };
+            Some((
+                false,
+                format!("    <dimen name=\"{name}\">{}{unit}</dimen>", fmt_float(*v)),
+            ))
+        }
+        _ => None,
+    }
+}
+
+impl DesignTokens {
+    /// Colors and dimensions as Android resource files. Names are snake_case token paths
+    /// starting with `prefix`; values with no Android resource equivalent are skipped.
+    pub fn to_android_resources(&self, prefix: &str) -> AndroidResources {
+        let (mut colors, mut dimens) = (Vec::new(), Vec::new());
+        for (path, token) in self.tokens() {
+            let TokenOrGroup::Token {
+                value, extensions, ..
+            } = token
+            else {
+                continue;
+            };
+            let entries = match value {
+                TokenValue::Single(expr) => {
+                    let key = path.last().map(|x| x.as_str()).unwrap_or_default();
+                    vec![(
+                        resource_name(&self.options.naming, prefix, &path),
+                        key.to_string(),
+                        resolve_single(self, expr, extensions),
+                    )]
+                }
+                TokenValue::Dict(dict) => dict
+                    .iter()
+                    .map(|(key, expr)| {
+                        let mut path = path.to_vec();
+                        path.push(key.clone());
+                        (
+                            resource_name(&self.options.naming, prefix, &path),
+                            key.clone(),
+                            expr.get_value(self),
+                        )
+                    })
+                    .collect(),
+            };
+            for (name, key, value) in entries {
+                match resource(&name, &key, &value) {
+                    Some((true, line)) => colors.push(line),
+                    Some((false, line)) => dimens.push(line),
+                    None => {}
+                }
+            }
+        }
+        let file = |lines: Vec<String>| {
+            format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n{}\n</resources>\n",
+                lines.join("\n")
+            )
+        };
+        AndroidResources {
+            colors: file(colors),
+            dimens: file(dimens),
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#2871f9" }, "Radius": { "$value": "4px" } },
+            "font": { "$type": "typography", "$value": { "fontSize": 20, "fontFamily": "Inter" } }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let res = tokens[0].to_android_resources("ds_");
+    assert!(res
+        .colors
+        .contains("<color name=\"ds_brand_primary\">#FF2871F9</color>"));
+    assert!(res
+        .dimens
+        .contains("<dimen name=\"ds_brand_radius\">4dp</dimen>"));
+    assert!(res
+        .dimens
+        .contains("<dimen name=\"ds_font_fontsize\">20sp</dimen>"));
+    assert!(!res.dimens.contains("Inter"));
+}
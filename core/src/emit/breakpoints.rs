@@ -0,0 +1,116 @@
+use itertools::Itertools;
+
+use crate::{
+    expression::{NumberType, Value},
+    naming::Naming,
+    resolve_single, DesignTokens, TokenOrGroup, TokenPath, TokenValue,
+};
+
+impl DesignTokens {
+    /// The widths in the group at the `/`-separated `path`, or in the first group named
+    /// `breakpoints` in any case when `None`. Tokens that aren't lengths are skipped.
+    pub fn breakpoints(&self, path: Option<&str>) -> Vec<(String, Value)> {
+        let tokens = self.tokens();
+        let group = match path {
+            Some(path) => TokenPath::parse(path).to_vec(),
+            None => {
+                let found = tokens.iter().find(|(path, _)| {
+                    path.len() > 1 && path[path.len() - 2].eq_ignore_ascii_case("breakpoints")
+                });
+                match found {
+                    Some((path, _)) => path[..path.len() - 1].to_vec(),
+                    None => return Vec::new(),
+                }
+            }
+        };
+        tokens
+            .into_iter()
+            .filter(|(path, _)| path.len() == group.len() + 1 && path[..].starts_with(&group))
+            .filter(|(_, token)| self.missing_ref(token).is_none())
+            .filter_map(|(path, token)| match token {
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(expr),
+                    extensions,
+                    ..
+                } => match resolve_single(self, expr, extensions) {
+                    value @ Value::Number(
+                        _,
+                        NumberType::Pixels | NumberType::Rem | NumberType::Em,
+                    ) => Some((path[path.len() - 1].clone(), value)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+    /// `pub const BREAKPOINTS`, or nothing when there are no breakpoints.
+    pub(crate) fn rust_breakpoints(&self, path: Option<&str>) -> Option<String> {
+        let breakpoints = self.breakpoints(path);
+        if breakpoints.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "/// Minimum widths, in the unit of each token.\npub const BREAKPOINTS: &[(&str, f32)] = &[{}];",
+            breakpoints
+                .iter()
+                .map(|(name, value)| format!("({name:?}, {})", value.to_rust()))
+                .join(", ")
+        ))
+    }
+}
+
+/// The breakpoints of the first set that has any, with CSS names.
+fn first_breakpoints(tokens: &[DesignTokens], path: Option<&str>) -> Vec<(String, Value)> {
+    tokens
+        .iter()
+        .map(|x| (&x.options.naming, x.breakpoints(path)))
+        .find(|(_, x)| !x.is_empty())
+        .map(|(naming, x): (&Naming, _)| {
+            x.into_iter()
+                .map(|(name, value)| (naming.slug(&name, "-"), value))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A `@custom-media` query per breakpoint, e.g. `@custom-media --md (min-width: 768px);`.
+pub fn to_custom_media(tokens: &[DesignTokens], path: Option<&str>) -> String {
+    first_breakpoints(tokens, path)
+        .into_iter()
+        .map(|(name, value)| format!("@custom-media --{} (min-width: {});", name, value.to_css()))
+        .join("\n")
+}
+
+/// A SCSS `$breakpoints` map and a `breakpoint($name)` mixin wrapping its content in the
+/// matching media query.
+pub fn to_scss_breakpoints(tokens: &[DesignTokens], path: Option<&str>) -> String {
+    let breakpoints = first_breakpoints(tokens, path)
+        .into_iter()
+        .map(|(name, value)| format!("  \"{name}\": {},", value.to_css()))
+        .join("\n");
+    format!(
+        "$breakpoints: (\n{breakpoints}\n);\n\n@mixin breakpoint($name) {{\n  @media (min-width: map-get($breakpoints, $name)) {{\n    @content;\n  }}\n}}\n"
+    )
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "Screens": { "Breakpoints": { "sm": { "$value": "640px" }, "md": { "$value": "{Screens.Breakpoints.sm} * 1.2" }, "xl": { "$value": "80rem" }, "label": { "$value": "wide" } } },
+            "gap": { "$value": "4px" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        to_custom_media(&tokens, None),
+        "@custom-media --sm (min-width: 640px);\n@custom-media --md (min-width: 768px);\n@custom-media --xl (min-width: 80rem);"
+    );
+    assert!(to_scss_breakpoints(&tokens, Some("Screens/Breakpoints"))
+        .starts_with("$breakpoints: (\n  \"sm\": 640px,\n  \"md\": 768px,\n  \"xl\": 80rem,\n);"));
+    assert_eq!(to_custom_media(&tokens, Some("Screens")), "");
+    assert!(tokens[0].to_rust().ends_with(
+        "pub const BREAKPOINTS: &[(&str, f32)] = &[(\"sm\", 640.), (\"md\", 768.), (\"xl\", 80.)];"
+    ));
+}
@@ -0,0 +1,101 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use super::css_format::format_css;
+use crate::{to_css_property_rules, CssOptions, DesignTokens, TokenOrGroup};
+
+/// How [`to_css_files`] splits the CSS output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CssSplit {
+    /// A file per token set, e.g. `dark.css`.
+    #[default]
+    Theme,
+    /// A file per top-level group across all sets, e.g. `colors.css`. Tokens outside any group
+    /// go to `tokens.css`.
+    Group,
+}
+
+/// The CSS output as separate files, by file name, followed by an `index.css` importing them.
+/// With [`CssOptions::register_properties`], the `@property` rules come first in
+/// `properties.css`.
+pub fn to_css_files(
+    tokens: &[DesignTokens],
+    split: CssSplit,
+    options: &CssOptions,
+) -> Vec<(String, String)> {
+    let selector = |set: &DesignTokens| format!(".{}", set.get_name_css());
+    let mut files: IndexMap<String, Vec<String>> = IndexMap::new();
+    if options.register_properties {
+        files.insert(
+            "properties.css".to_string(),
+            vec![to_css_property_rules(tokens, options)],
+        );
+    }
+    for set in tokens {
+        match split {
+            CssSplit::Theme => files
+                .entry(format!("{}.css", set.get_name_css()))
+                .or_default()
+                .push(set.to_css_with(&selector(set), options)),
+            CssSplit::Group => {
+                let TokenOrGroup::Group(group) = &set.body else {
+                    continue;
+                };
+                for (key, node) in group {
+                    let name = match node {
+                        TokenOrGroup::Group(_) => set.options.naming.slug(key, "-"),
+                        TokenOrGroup::Token { .. } => "tokens".to_string(),
+                    };
+                    files
+                        .entry(format!("{name}.css"))
+                        .or_default()
+                        .push(node.to_css(set, options, &selector(set), std::slice::from_ref(key)));
+                }
+            }
+        }
+    }
+    let index = files
+        .keys()
+        .map(|name| format!("@import \"{name}\";"))
+        .join("\n");
+    files
+        .into_iter()
+        .map(|(name, parts)| (name, format_css(&parts.join("\n"), options)))
+        .chain([("index.css".to_string(), index)])
+        .collect()
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"[
+            { "fileName": "light.json", "body": { "colors": { "bg": { "$value": "#ffffff" } }, "space": { "s": { "$value": "4px" } }, "radius": { "$value": "2px" } } },
+            { "fileName": "dark.json", "body": { "colors": { "bg": { "$value": "#000000" } } } }
+        ]"##,
+        None,
+    )
+    .unwrap();
+    let names = |split| {
+        to_css_files(&tokens, split, &CssOptions::default())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect_vec()
+    };
+    assert_eq!(
+        names(CssSplit::Theme),
+        ["light.css", "dark.css", "index.css"]
+    );
+    assert_eq!(
+        names(CssSplit::Group),
+        ["colors.css", "space.css", "tokens.css", "index.css"]
+    );
+    let files = to_css_files(&tokens, CssSplit::Group, &CssOptions::default());
+    assert_eq!(
+        files[0].1,
+        ".light { --colors-bg: #ffffff; }\n.dark { --colors-bg: #000000; }"
+    );
+    assert_eq!(
+        files[3].1,
+        "@import \"colors.css\";\n@import \"space.css\";\n@import \"tokens.css\";"
+    );
+}
@@ -0,0 +1,221 @@
+use crate::CssOptions;
+
+enum Node {
+    Comment(String),
+    /// A declaration, or a statement such as `@import` outside a block.
+    Statement(String),
+    Block(String, Vec<Node>),
+}
+
+/// Copies the escape or string starting at `c` into `out`, returning whether there was one.
+fn copy_literal(
+    c: char,
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    out: &mut String,
+) -> bool {
+    match c {
+        '\\' => {
+            out.push(c);
+            out.extend(chars.next());
+        }
+        '"' | '\'' => {
+            out.push(c);
+            while let Some(x) = chars.next() {
+                out.push(x);
+                match x {
+                    '\\' => out.extend(chars.next()),
+                    x if x == c => break,
+                    _ => {}
+                }
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn parse(css: &str) -> Vec<Node> {
+    let mut stack = vec![(String::new(), Vec::new())];
+    let mut buf = String::new();
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        if copy_literal(c, &mut chars, &mut buf) {
+            continue;
+        }
+        let depth = stack.len();
+        let nodes = &mut stack.last_mut().unwrap().1;
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut comment = String::new();
+                while let Some(x) = chars.next() {
+                    if x == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        break;
+                    }
+                    comment.push(x);
+                }
+                nodes.push(Node::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                if !buf.trim().is_empty() {
+                    nodes.push(Node::Statement(buf.trim().to_string()));
+                }
+                buf.clear();
+            }
+            '{' => {
+                stack.push((buf.trim().to_string(), Vec::new()));
+                buf.clear();
+            }
+            '}' if depth > 1 => {
+                if !buf.trim().is_empty() {
+                    nodes.push(Node::Statement(buf.trim().to_string()));
+                }
+                buf.clear();
+                let (prelude, children) = stack.pop().unwrap();
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(Node::Block(prelude, children));
+            }
+            c => buf.push(c),
+        }
+    }
+    merge(stack.swap_remove(0).1)
+}
+
+/// Joins consecutive blocks with the same selector, as each token is generated as its own rule.
+fn merge(nodes: Vec<Node>) -> Vec<Node> {
+    let mut out: Vec<Node> = Vec::new();
+    for node in nodes {
+        match (out.last_mut(), node) {
+            (Some(Node::Block(last, children)), Node::Block(prelude, more)) if *last == prelude => {
+                children.extend(more)
+            }
+            (_, Node::Block(prelude, children)) => out.push(Node::Block(prelude, merge(children))),
+            (_, node) => out.push(node),
+        }
+    }
+    out
+}
+
+/// Collapses whitespace outside strings and escapes. Minified, it also drops the spaces around
+/// `:` and after `,`, which is safe in the declarations and selectors generated here.
+fn squeeze(text: &str, minify: bool) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if copy_literal(c, &mut chars, &mut out) {
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => {
+                while chars.peek().is_some_and(|x| x.is_whitespace()) {
+                    chars.next();
+                }
+                // `calc()` needs the spaces around `+` and `-`, so those stay.
+                let dropped = minify
+                    && (out.ends_with([':', ','])
+                        || chars.peek().is_some_and(|x| matches!(x, ':' | ',' | '{')));
+                if !dropped {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write(nodes: &[Node], indent: usize, depth: usize, out: &mut Vec<String>) {
+    let pad = " ".repeat(indent * depth);
+    for node in nodes {
+        match node {
+            Node::Comment(text) => out.push(format!("{pad}/* {text} */")),
+            Node::Statement(text) => out.push(format!("{pad}{};", squeeze(text, false))),
+            Node::Block(prelude, children) => {
+                out.push(format!("{pad}{} {{", squeeze(prelude, false)));
+                write(children, indent, depth + 1, out);
+                out.push(format!("{pad}}}"));
+            }
+        }
+    }
+}
+
+fn write_minified(nodes: &[Node], out: &mut String) {
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Comment(_) => {}
+            Node::Statement(text) => {
+                *out += &squeeze(text, true);
+                // The last declaration of a block doesn't need its semicolon.
+                if i + 1 < nodes.len() {
+                    out.push(';');
+                }
+            }
+            Node::Block(prelude, children) => {
+                *out += &squeeze(prelude, true);
+                out.push('{');
+                write_minified(children, out);
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Lays out generated CSS according to [`CssOptions::minify`] and [`CssOptions::indent`],
+/// leaving it as generated when neither is set.
+pub fn format_css(css: &str, options: &CssOptions) -> String {
+    if options.minify {
+        let mut out = String::new();
+        write_minified(&parse(css), &mut out);
+        out
+    } else if let Some(indent) = options.indent {
+        let mut out = Vec::new();
+        write(&parse(css), indent, 0, &mut out);
+        out.join("\n") + "\n"
+    } else {
+        css.to_string()
+    }
+}
+
+#[test]
+fn test() {
+    let css = "/* tokens.json: a = 4px */\n:root { --a: 4px; }\n:root { --z: 0; }\n:root .heading {\nfont-family: \"Open  Sans\", serif;\nfont-size: 16px;\n}\n@media (prefers-color-scheme: dark) {\n:root { --b\\ c: rgba(0, 0, 0, 0.5); }\n}";
+    let pretty = CssOptions {
+        indent: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        format_css(css, &pretty),
+        "\
+/* tokens.json: a = 4px */
+:root {
+  --a: 4px;
+  --z: 0;
+}
+:root .heading {
+  font-family: \"Open  Sans\", serif;
+  font-size: 16px;
+}
+@media (prefers-color-scheme: dark) {
+  :root {
+    --b\\ c: rgba(0, 0, 0, 0.5);
+  }
+}
+"
+    );
+    assert_eq!(
+        format_css(&format_css(css, &pretty), &pretty),
+        format_css(css, &pretty)
+    );
+    let minify = CssOptions {
+        minify: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        format_css(css, &minify),
+        ":root{--a:4px;--z:0}:root .heading{font-family:\"Open  Sans\",serif;font-size:16px}@media (prefers-color-scheme:dark){:root{--b\\ c:rgba(0,0,0,0.5)}}"
+    );
+}
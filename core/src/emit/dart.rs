@@ -0,0 +1,289 @@
+use std::collections::{HashMap, HashSet};
+
+use convert_case::{Case, Casing};
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use super::{dimension::NativeNumber, fmt_float};
+use crate::{
+    expression::{Expression, NumberType, Value},
+    naming::Naming,
+    resolve_single, DesignTokens, TokenOrGroup, TokenType, TokenValue,
+};
+
+const KEYWORDS: &[&str] = &[
+    "assert", "async", "await", "break", "case", "catch", "class", "const", "continue", "default",
+    "do", "else", "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new",
+    "null", "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void",
+    "while", "with", "yield",
+];
+
+/// Dart has no nested classes, so members are named after the full token path. Names taken by
+/// an earlier member are numbered, e.g. `size2_2`, as with the Rust constants.
+fn member_name(naming: &Naming, path: &[String], taken: &mut HashSet<String>) -> String {
+    let x = naming
+        .join(path, None, "_")
+        .to_case(naming.case(Case::Camel));
+    // A leading `_` would make the member library-private.
+    let base = if x.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("${x}")
+    } else if KEYWORDS.contains(&x.as_str()) {
+        format!("{x}_")
+    } else {
+        x
+    };
+    let mut name = base.clone();
+    let mut n = 1;
+    while !taken.insert(name.clone()) {
+        n += 1;
+        name = format!("{base}_{n}");
+    }
+    name
+}
+
+fn string(x: &str) -> String {
+    format!(
+        "'{}'",
+        x.replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('$', "\\$")
+    )
+}
+
+/// `None` for values Flutter has no equivalent for.
+fn dart_value(tokens: &DesignTokens, value: &Value) -> Option<(&'static str, String)> {
+    Some(match value {
+        Value::Color(c) => {
+            let [r, g, b, a] = c.to_rgba8();
+            ("Color", format!("Color(0x{a:02X}{r:02X}{g:02X}{b:02X})"))
+        }
+        Value::Number(v, unit) => match NativeNumber::new(tokens, *v, unit) {
+            NativeNumber::Length(v) | NativeNumber::Fraction(v) => ("double", fmt_float(v)),
+            NativeNumber::Seconds(v) => {
+                let micros = (v * 1e6).round() as i64;
+                let duration = match micros % 1000 {
+                    0 => format!("Duration(milliseconds: {})", micros / 1000),
+                    _ => format!("Duration(microseconds: {micros})"),
+                };
+                ("Duration", duration)
+            }
+            NativeNumber::Unsupported => return None,
+        },
+        Value::CubicBezier(p) => (
+            "Cubic",
+            format!("Cubic({})", p.iter().map(|x| fmt_float(*x)).join(", ")),
+        ),
+        Value::Bool(v) => ("bool", v.to_string()),
+        Value::Any(v) => ("String", string(v)),
+    })
+}
+
+fn member(tokens: &DesignTokens, name: &str, value: &Value) -> String {
+    match dart_value(tokens, value) {
+        Some((type_, v)) => format!("  static const {type_} {name} = {v};"),
+        None => format!("  // {name} = {} has no Dart equivalent", value.to_css()),
+    }
+}
+
+fn text_style(tokens: &DesignTokens, dict: &IndexMap<String, Expression>) -> String {
+    let values = dict
+        .iter()
+        .map(|(k, v)| (k.to_case(Case::Camel), v.get_value(tokens)))
+        .collect::<HashMap<_, _>>();
+    let number = |key: &str| match values.get(key) {
+        Some(Value::Number(v, unit)) => match NativeNumber::new(tokens, *v, unit) {
+            NativeNumber::Length(v) => Some(v),
+            _ => None,
+        },
+        _ => None,
+    };
+    let text = |key: &str| match values.get(key) {
+        Some(Value::Any(v)) => Some(v.as_str()),
+        _ => None,
+    };
+    let mut args = Vec::new();
+    if let Some(family) = text("fontFamily") {
+        args.push(format!("fontFamily: {}", string(family)));
+    }
+    if let Some(size) = number("fontSize") {
+        args.push(format!("fontSize: {}", fmt_float(size)));
+    }
+    if let Some(weight) = number("fontWeight") {
+        let weight = ((weight / 100.).round() * 100.).clamp(100., 900.);
+        args.push(format!("fontWeight: FontWeight.w{weight}"));
+    }
+    if text("fontStyle") == Some("italic") {
+        args.push("fontStyle: FontStyle.italic".to_string());
+    }
+    if let Some(spacing) = number("letterSpacing") {
+        args.push(format!("letterSpacing: {}", fmt_float(spacing)));
+    }
+    // Flutter expresses line height as a multiple of the font size, as unitless CSS line heights
+    // are. Percentages are of the font size and lengths are divided by it.
+    let height = match values.get("lineHeight") {
+        Some(Value::Number(v, NumberType::None)) => Some(*v),
+        Some(Value::Number(v, NumberType::Percentage)) => Some(v / 100.),
+        Some(Value::Number(..)) => number("lineHeight")
+            .zip(number("fontSize"))
+            .map(|(line_height, size)| line_height / size),
+        _ => None,
+    };
+    if let Some(height) = height {
+        args.push(format!("height: {}", fmt_float(height)));
+    }
+    match text("textDecoration") {
+        Some("underline") => args.push("decoration: TextDecoration.underline".to_string()),
+        Some("line-through") => args.push("decoration: TextDecoration.lineThrough".to_string()),
+        _ => {}
+    }
+    format!("TextStyle({})", args.join(", "))
+}
+
+impl DesignTokens {
+    /// A Dart class with a `static const` member per token, typography as `TextStyle`.
+    pub fn to_dart(&self) -> String {
+        let mut members = Vec::new();
+        let mut taken = HashSet::new();
+        for (path, token) in self.tokens() {
+            let TokenOrGroup::Token {
+                value,
+                type_,
+                extensions,
+                ..
+            } = token
+            else {
+                continue;
+            };
+            match value {
+                TokenValue::Single(expr) => {
+                    members.push(member(
+                        self,
+                        &member_name(&self.options.naming, &path, &mut taken),
+                        &resolve_single(self, expr, extensions),
+                    ));
+                }
+                TokenValue::Dict(dict) if *type_ == TokenType::Typography => {
+                    members.push(format!(
+                        "  static const TextStyle {} = {};",
+                        member_name(&self.options.naming, &path, &mut taken),
+                        text_style(self, dict)
+                    ));
+                }
+                TokenValue::Dict(dict) => {
+                    for (key, expr) in dict {
+                        let mut path = path.to_vec();
+                        path.push(key.clone());
+                        members.push(member(
+                            self,
+                            &member_name(&self.options.naming, &path, &mut taken),
+                            &expr.get_value(self),
+                        ));
+                    }
+                }
+            }
+        }
+        let name = self
+            .options
+            .naming
+            .segment(self.get_name(), Some(Case::Pascal), "_");
+        format!(
+            "class {} {{\n  {}._();\n\n{}\n}}",
+            name,
+            name,
+            members.join("\n")
+        )
+    }
+}
+
+/// A complete Dart library with one class per token set.
+pub fn to_dart(tokens: &[DesignTokens]) -> String {
+    format!(
+        "import 'package:flutter/widgets.dart';\n\n{}\n",
+        tokens.iter().map(|x| x.to_dart()).join("\n\n")
+    )
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#2871f9" }, "Size 2": { "$value": 8 } },
+            "font": {
+                "$type": "typography",
+                "$value": { "fontSize": 20, "fontWeight": 450, "lineHeight": "24px", "fontFamily": "Inter" }
+            }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].to_dart(),
+        r#"class Tokens {
+  Tokens._();
+
+  static const Color brandPrimary = Color(0xFF2871F9);
+  static const double brandSize2 = 8;
+  static const TextStyle font = TextStyle(fontFamily: 'Inter', fontSize: 20, fontWeight: FontWeight.w500, height: 1.2);
+}"#
+    );
+
+    let units = crate::parse_design_tokens(
+        r#"{
+            "gap": { "$value": "2em" },
+            "full": { "$value": "100vw" },
+            "fast": { "$value": "0.3s", "$type": "duration" },
+            "odd": { "$value": "0.2505s", "$type": "duration" },
+            "body": { "$type": "typography", "$value": { "fontSize": "1rem", "lineHeight": "1.5rem" } }
+        }"#,
+        Some("units.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        units[0].to_dart(),
+        r#"class Units {
+  Units._();
+
+  static const double gap = 32;
+  // full = 100vw has no Dart equivalent
+  static const Duration fast = Duration(milliseconds: 300);
+  static const Duration odd = Duration(microseconds: 250500);
+  static const TextStyle body = TextStyle(fontSize: 16, height: 1.5);
+}"#
+    );
+
+    let heights = crate::parse_design_tokens(
+        r#"{
+            "unitless": { "$type": "typography", "$value": { "fontSize": 16, "lineHeight": 1.5 } },
+            "percent": { "$type": "typography", "$value": { "fontSize": 16, "lineHeight": "125%" } },
+            "length": { "$type": "typography", "$value": { "fontSize": 16, "lineHeight": "20px" } }
+        }"#,
+        Some("heights.json"),
+    )
+    .unwrap();
+    let dart = heights[0].to_dart();
+    assert!(dart.contains("unitless = TextStyle(fontSize: 16, height: 1.5);"));
+    assert!(dart.contains("percent = TextStyle(fontSize: 16, height: 1.25);"));
+    assert!(dart.contains("length = TextStyle(fontSize: 16, height: 1.25);"));
+
+    let names = crate::parse_design_tokens(
+        r#"{
+            "default": { "$value": 1 },
+            "new": { "$value": 2 },
+            "size 2": { "$value": 3 },
+            "size-2": { "$value": 4 }
+        }"#,
+        Some("names.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        names[0].to_dart(),
+        r#"class Names {
+  Names._();
+
+  static const double default_ = 1;
+  static const double new_ = 2;
+  static const double size2 = 3;
+  static const double size2_2 = 4;
+}"#
+    );
+}
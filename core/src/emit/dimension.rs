@@ -0,0 +1,78 @@
+use crate::{expression::NumberType, DesignTokens, RustOptions};
+
+pub(crate) const DIMENSION: &str = "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Unit { Px, Percent, Rem, Em, Vh, Vw }\n#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Dimension { pub value: f64, pub unit: Unit }";
+
+/// The `Unit` variant of `unit` when it is generated as a `Dimension`, see
+/// [`RustOptions::dimensions`].
+pub(crate) fn rust_unit(options: &RustOptions, unit: &NumberType) -> Option<&'static str> {
+    if !options.dimensions {
+        return None;
+    }
+    match unit {
+        NumberType::Pixels => Some("Px"),
+        NumberType::Percentage => Some("Percent"),
+        NumberType::Rem => Some("Rem"),
+        NumberType::Em => Some("Em"),
+        NumberType::Vh => Some("Vh"),
+        NumberType::Vw => Some("Vw"),
+        NumberType::None | NumberType::Seconds | NumberType::Milliseconds => None,
+    }
+}
+
+/// A number as the native emitters write it, as they have no relative lengths.
+pub(crate) enum NativeNumber {
+    /// Points, dp or logical pixels.
+    Length(f64),
+    /// A percentage as a fraction.
+    Fraction(f64),
+    Seconds(f64),
+    /// Viewport units, which only exist in CSS.
+    Unsupported,
+}
+impl NativeNumber {
+    /// `rem` and `em` are scaled by [`crate::ResolveOptions::rem_base`].
+    pub(crate) fn new(tokens: &DesignTokens, value: f64, unit: &NumberType) -> Self {
+        match unit {
+            NumberType::None | NumberType::Pixels => NativeNumber::Length(value),
+            NumberType::Rem | NumberType::Em => {
+                NativeNumber::Length(value * tokens.options.rem_base.unwrap_or(16.))
+            }
+            NumberType::Percentage => NativeNumber::Fraction(value * 0.01),
+            NumberType::Seconds => NativeNumber::Seconds(value),
+            NumberType::Milliseconds => NativeNumber::Seconds(value / 1000.),
+            NumberType::Vh | NumberType::Vw => NativeNumber::Unsupported,
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use crate::emit::Emitter;
+    let tokens = crate::parse_design_tokens(
+        r#"{ "gap": { "$value": "16px" }, "wide": { "$value": "50%" }, "ratio": { "$value": 1.5 },
+            "line": { "$value": "232.8300018310547px" } }"#,
+        None,
+    )
+    .unwrap();
+    let options = RustOptions {
+        dimensions: true,
+        lookup: true,
+        ..Default::default()
+    };
+    let rust = crate::emit::RustEmitter {
+        options: options.clone(),
+    }
+    .emit(&crate::emit::ResolvedTokens::new(&tokens));
+    assert!(rust.contains("pub struct Dimension {"));
+    assert!(rust.contains(
+        "pub const GAP: super::Dimension = super::Dimension { value: 16., unit: super::Unit::Px };"
+    ));
+    assert!(rust.contains(
+        "pub const WIDE: super::Dimension = super::Dimension { value: 50., unit: super::Unit::Percent };"
+    ));
+    assert!(rust.contains("pub struct Dimension { pub value: f64, pub unit: Unit }"));
+    assert!(rust.contains("value: 232.8300018310547,"));
+    assert!(rust.contains("pub const RATIO: f32 = 1.5;"));
+    assert!(rust.contains("\"gap\" => super::TokenValue::Dimension(GAP),"));
+    assert!(!tokens[0].to_rust().contains("Dimension"));
+}
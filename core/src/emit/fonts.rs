@@ -0,0 +1,270 @@
+use convert_case::{Case, Casing};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{expression::Value, DesignTokens, TokenOrGroup, TokenType, TokenValue};
+
+/// Font files by family, as provided by the user, e.g.
+/// `{ "Inter": [{ "src": "fonts/Inter.woff2", "weight": [100, 900] }] }`.
+pub type FontFiles = IndexMap<String, Vec<FontFile>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FontFile {
+    pub src: String,
+    /// Any weight when left out.
+    #[serde(default)]
+    pub weight: Option<FontWeight>,
+    /// `normal` when left out.
+    #[serde(default)]
+    pub style: Option<String>,
+}
+impl FontFile {
+    fn style(&self) -> &str {
+        self.style.as_deref().unwrap_or("normal")
+    }
+    fn covers(&self, font: &FontUsage) -> bool {
+        let weight = match (&self.weight, font.weight) {
+            (Some(FontWeight::Single(x)), Some(weight)) => *x == weight,
+            (Some(FontWeight::Range([min, max])), Some(weight)) => (*min..=*max).contains(&weight),
+            _ => true,
+        };
+        weight && self.style() == font.style
+    }
+    fn format(&self) -> Option<&'static str> {
+        match self.src.rsplit('.').next()? {
+            "woff2" => Some("woff2"),
+            "woff" => Some("woff"),
+            "ttf" => Some("truetype"),
+            "otf" => Some("opentype"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FontWeight {
+    Single(u16),
+    /// The weight axis of a variable font, e.g. `[100, 900]`.
+    Range([u16; 2]),
+}
+
+/// A family, weight and style the tokens use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontUsage {
+    pub family: String,
+    /// Unknown for plain `fontFamily` tokens.
+    pub weight: Option<u16>,
+    pub style: String,
+}
+
+const GENERIC_FAMILIES: &[&str] = &[
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-sans-serif",
+    "ui-serif",
+    "ui-monospace",
+    "inherit",
+];
+
+/// The first family of a CSS font stack, unless it's a generic one.
+fn family(value: &Value) -> Option<String> {
+    let Value::Any(stack) = value else {
+        return None;
+    };
+    let family = stack.split(',').next()?.trim().trim_matches(['"', '\'']);
+    (!family.is_empty() && !GENERIC_FAMILIES.contains(&family)).then(|| family.to_string())
+}
+
+/// Numeric weights, or names such as `Semi Bold` as exported by Figma.
+fn weight(value: &Value) -> Option<u16> {
+    match value {
+        Value::Number(x, _) => Some(*x as u16),
+        Value::Any(name) => match name.to_lowercase().replace([' ', '-'], "").as_str() {
+            "thin" | "hairline" => Some(100),
+            "extralight" | "ultralight" => Some(200),
+            "light" => Some(300),
+            "normal" | "regular" | "book" => Some(400),
+            "medium" => Some(500),
+            "semibold" | "demibold" => Some(600),
+            "bold" => Some(700),
+            "extrabold" | "ultrabold" => Some(800),
+            "black" | "heavy" => Some(900),
+            _ => name.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+/// Every font the typography and `fontFamily` tokens of `tokens` need, sorted.
+pub fn required_fonts(tokens: &[DesignTokens]) -> Vec<FontUsage> {
+    tokens
+        .iter()
+        .flat_map(|set| {
+            set.iter()
+                .filter(|(_, token)| set.missing_ref(token).is_none())
+                .filter_map(|(_, token)| match token {
+                    TokenOrGroup::Token {
+                        value: TokenValue::Dict(dict),
+                        type_: TokenType::Typography,
+                        ..
+                    } => {
+                        let field = |key: &str| {
+                            dict.iter()
+                                .find(|(k, _)| k.to_case(Case::Camel) == key)
+                                .map(|(_, v)| v.get_value(set))
+                        };
+                        Some(FontUsage {
+                            family: family(&field("fontFamily")?)?,
+                            weight: field("fontWeight").as_ref().and_then(weight),
+                            style: match field("fontStyle") {
+                                Some(Value::Any(style)) => style.to_lowercase(),
+                                _ => "normal".to_string(),
+                            },
+                        })
+                    }
+                    TokenOrGroup::Token {
+                        value: TokenValue::Single(expr),
+                        type_: TokenType::Other(type_),
+                        ..
+                    } if type_ == "fontFamily" => Some(FontUsage {
+                        family: family(&expr.get_value(set))?,
+                        weight: None,
+                        style: "normal".to_string(),
+                    }),
+                    _ => None,
+                })
+                .collect_vec()
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+/// The files needed for `fonts`, in the order of `files`, and the fonts no file covers.
+fn resolve<'a>(
+    fonts: &'a [FontUsage],
+    files: &'a FontFiles,
+) -> (Vec<(&'a str, &'a FontFile)>, Vec<&'a FontUsage>) {
+    let mut needed = Vec::new();
+    let mut missing = Vec::new();
+    for font in fonts {
+        let matches = files
+            .get(&font.family)
+            .into_iter()
+            .flatten()
+            .filter(|file| file.covers(font))
+            .map(|file| (font.family.as_str(), file))
+            .collect_vec();
+        if matches.is_empty() {
+            missing.push(font);
+        }
+        needed.extend(matches);
+    }
+    let needed = files
+        .iter()
+        .flat_map(|(family, x)| x.iter().map(move |file| (family.as_str(), file)))
+        .filter(|x| needed.contains(x))
+        .collect();
+    (needed, missing)
+}
+
+/// `@font-face` rules for the font files the tokens use. Fonts without a file are left out,
+/// see [`to_font_manifest`].
+pub fn to_font_faces(tokens: &[DesignTokens], files: &FontFiles) -> String {
+    let fonts = required_fonts(tokens);
+    let (needed, _) = resolve(&fonts, files);
+    needed
+        .into_iter()
+        .map(|(family, file)| {
+            let src = match file.format() {
+                Some(format) => format!("url({:?}) format({format:?})", file.src),
+                None => format!("url({:?})", file.src),
+            };
+            let weight = match &file.weight {
+                Some(FontWeight::Single(x)) => format!(" font-weight: {x};"),
+                Some(FontWeight::Range([min, max])) => format!(" font-weight: {min} {max};"),
+                None => String::new(),
+            };
+            format!(
+                "@font-face {{ font-family: {family:?}; src: {src};{weight} font-style: {}; font-display: swap; }}",
+                file.style()
+            )
+        })
+        .join("\n")
+}
+
+/// A JSON list of the font files to ship with the CSS, and the fonts the tokens use that no
+/// file covers.
+pub fn to_font_manifest(tokens: &[DesignTokens], files: &FontFiles) -> String {
+    let fonts = required_fonts(tokens);
+    let (needed, missing) = resolve(&fonts, files);
+    let manifest = json!({
+        "files": needed
+            .into_iter()
+            .map(|(family, file)| json!({
+                "family": family,
+                "src": file.src,
+                "format": file.format(),
+                "weight": file.weight,
+                "style": file.style(),
+            }))
+            .collect_vec(),
+        "missing": missing
+            .into_iter()
+            .map(|x| json!({ "family": x.family, "weight": x.weight, "style": x.style }))
+            .collect_vec(),
+    });
+    serde_json::to_string_pretty(&manifest).unwrap()
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "body": { "$type": "typography", "$value": { "fontFamily": "{family}", "fontWeight": "Regular" } },
+            "strong": { "$type": "typography", "$value": { "fontFamily": "{family}", "fontWeight": 700 } },
+            "quote": { "$type": "typography", "$value": { "fontFamily": "Lora, serif", "fontWeight": 400, "fontStyle": "italic" } },
+            "code": { "$type": "fontFamily", "$value": "monospace" },
+            "family": { "$type": "fontFamily", "$value": "Inter, sans-serif" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let files: FontFiles = serde_json::from_str(
+        r#"{
+            "Inter": [{ "src": "fonts/Inter.woff2", "weight": [100, 900] }, { "src": "fonts/Inter-Italic.woff2", "weight": [100, 900], "style": "italic" }],
+            "Lora": [{ "src": "fonts/Lora-Regular.ttf", "weight": 400 }]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        required_fonts(&tokens)
+            .into_iter()
+            .map(|x| format!("{} {:?} {}", x.family, x.weight, x.style))
+            .collect_vec(),
+        [
+            "Inter None normal",
+            "Inter Some(400) normal",
+            "Inter Some(700) normal",
+            "Lora Some(400) italic"
+        ]
+    );
+    assert_eq!(
+        to_font_faces(&tokens, &files),
+        r#"@font-face { font-family: "Inter"; src: url("fonts/Inter.woff2") format("woff2"); font-weight: 100 900; font-style: normal; font-display: swap; }"#
+    );
+    let manifest: serde_json::Value =
+        serde_json::from_str(&to_font_manifest(&tokens, &files)).unwrap();
+    assert_eq!(manifest["files"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        manifest["missing"],
+        json!([{ "family": "Lora", "weight": 400, "style": "italic" }])
+    );
+}
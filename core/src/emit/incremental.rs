@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use super::{with_property_rules, CssEmitter, Emitter, ResolvedTokens, RustEmitter};
+use crate::{DesignTokens, TokenOrGroup, TokenPath};
+
+/// What a [`Generator`] regenerated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegeneratedOutputs {
+    /// The tokens whose output was regenerated, with the name of their set.
+    pub tokens: Vec<(String, TokenPath)>,
+    /// The outputs whose contents changed, by emitter name.
+    pub outputs: IndexMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    css: HashMap<TokenPath, String>,
+    /// The constant name and item, `None` for tokens left out for a missing reference.
+    rust: HashMap<TokenPath, (String, Option<String>)>,
+}
+
+/// Keeps the output of a [`CssEmitter`] and a [`RustEmitter`] up to date, regenerating only
+/// the tokens a change affects through references, e.g. when watching large token files.
+#[derive(Debug)]
+pub struct Generator {
+    css: CssEmitter,
+    rust: RustEmitter,
+    sets: Vec<DesignTokens>,
+    caches: Vec<Cache>,
+    outputs: IndexMap<String, String>,
+}
+impl Generator {
+    /// Panics where [`Generator::replace`] fails.
+    pub fn new(sets: Vec<DesignTokens>, css: CssEmitter, rust: RustEmitter) -> Self {
+        let mut out = Self {
+            css,
+            rust,
+            sets: Vec::new(),
+            caches: Vec::new(),
+            outputs: IndexMap::new(),
+        };
+        out.replace(sets).unwrap_or_else(|err| panic!("{err}"));
+        out
+    }
+    pub fn sets(&self) -> &[DesignTokens] {
+        &self.sets
+    }
+    /// For editing tokens in place, followed by [`Generator::update`] with their paths.
+    pub fn sets_mut(&mut self) -> &mut [DesignTokens] {
+        &mut self.sets
+    }
+    /// The current output of each emitter, by name.
+    pub fn outputs(&self) -> &IndexMap<String, String> {
+        &self.outputs
+    }
+    /// Regenerates the tokens at `changed_paths`, or under them for groups, in every set,
+    /// along with the tokens referencing them. Fails, leaving the outputs as they are, when a
+    /// token to regenerate doesn't pass [`DesignTokens::check`].
+    pub fn update(&mut self, changed_paths: &[TokenPath]) -> Result<RegeneratedOutputs, String> {
+        let affected = self
+            .sets
+            .iter()
+            .map(|set| affected(set, changed_paths))
+            .collect_vec();
+        self.regenerate(|i, path| affected[i].contains(path))
+    }
+    /// Swaps in new sets, e.g. reloaded from disk, regenerating the tokens that differ from the
+    /// current ones and those referencing them. Everything is regenerated when the sets
+    /// themselves changed. Options aren't compared; make a new generator when they change.
+    /// Fails like [`Generator::update`], keeping the current sets.
+    pub fn replace(&mut self, sets: Vec<DesignTokens>) -> Result<RegeneratedOutputs, String> {
+        let same_sets = self.sets.len() == sets.len()
+            && self
+                .sets
+                .iter()
+                .zip(&sets)
+                .all(|(a, b)| a.file_name == b.file_name);
+        let old = std::mem::replace(&mut self.sets, sets);
+        let result = if same_sets {
+            let mut changed = Vec::new();
+            for (a, b) in old.iter().zip(&self.sets) {
+                let a = a.iter().collect::<HashMap<_, _>>();
+                let b = b.iter().collect::<HashMap<_, _>>();
+                for (path, token) in &b {
+                    if a.get(path) != Some(token) {
+                        changed.push(path.clone());
+                    }
+                }
+                changed.extend(a.into_keys().filter(|x| !b.contains_key(x)));
+            }
+            let changed = changed.into_iter().unique().collect_vec();
+            self.update(&changed)
+        } else {
+            let caches = self.sets.iter().map(|_| Cache::default()).collect();
+            let caches = std::mem::replace(&mut self.caches, caches);
+            let result = self.regenerate(|_, _| true);
+            if result.is_err() {
+                self.caches = caches;
+            }
+            result
+        };
+        if result.is_err() {
+            self.sets = old;
+        }
+        result
+    }
+    fn regenerate(
+        &mut self,
+        affected: impl Fn(usize, &TokenPath) -> bool,
+    ) -> Result<RegeneratedOutputs, String> {
+        // Everything is checked before the caches are touched, as emitting panics on errors.
+        let mut stale = Vec::new();
+        for (i, (set, cache)) in self.sets.iter().zip(&self.caches).enumerate() {
+            set.css_collision_error(&self.css.options)?;
+            let mut tokens = Vec::new();
+            for ((path, token), name) in set.tokens().into_iter().zip(set.rust_names()) {
+                if affected(i, &path)
+                    || !cache.css.contains_key(&path)
+                    || cache.rust.get(&path).is_none_or(|(x, _)| *x != name)
+                {
+                    set.check_token(&path, token)?;
+                    tokens.push((path, token, name));
+                }
+            }
+            stale.push(tokens);
+        }
+
+        let mut tokens = Vec::new();
+        for ((set, cache), stale) in self.sets.iter().zip(&mut self.caches).zip(stale) {
+            let selector = format!(".{}", set.get_name_css());
+            for (path, token, name) in stale {
+                let css = token.to_css(set, &self.css.options, &selector, &path);
+                let rust = (!set.unresolvable(token))
+                    .then(|| token.to_rust(set, &self.rust.options, &path, &name));
+                cache.css.insert(path.clone(), css);
+                cache.rust.insert(path.clone(), (name, rust));
+                tokens.push((set.get_name().to_string(), path));
+            }
+            let paths = set.iter().map(|(x, _)| x).collect::<HashSet<_>>();
+            cache.css.retain(|x, _| paths.contains(x));
+            cache.rust.retain(|x, _| paths.contains(x));
+        }
+
+        let resolved = ResolvedTokens::new(&self.sets);
+        let css = self
+            .sets
+            .iter()
+            .zip(&self.caches)
+            .map(|(set, cache)| {
+                join_tokens(&set.body, &mut Vec::new(), &|path| {
+                    cache.css[&TokenPath::from(path)].clone()
+                })
+            })
+            .join("\n");
+        let css = with_property_rules(&resolved, &self.css.options, css);
+        let rust = self.rust.emit_with(&resolved, |i, options| {
+            let cache = &self.caches[i];
+            self.sets[i]
+                .iter()
+                .filter_map(|(path, _)| cache.rust[&path].1.clone())
+                .chain(self.sets[i].rust_extras(options))
+                .collect()
+        });
+
+        let mut outputs = IndexMap::new();
+        for (name, output) in [(self.css.name(), css), (self.rust.name(), rust)] {
+            if self.outputs.get(name) != Some(&output) {
+                self.outputs.insert(name.to_string(), output.clone());
+                outputs.insert(name.to_string(), output);
+            }
+        }
+        Ok(RegeneratedOutputs { tokens, outputs })
+    }
+}
+
+/// The tokens at or under `changed`, and every token referencing those, directly or not.
+fn affected(set: &DesignTokens, changed: &[TokenPath]) -> HashSet<TokenPath> {
+    let mut referenced_by = HashMap::<_, Vec<_>>::new();
+    for (from, to) in set.reference_graph().edges {
+        referenced_by.entry(to).or_default().push(from);
+    }
+    let mut out = set
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| changed.iter().any(|x| path.0.starts_with(&x.0)))
+        .collect::<HashSet<_>>();
+    // References to removed tokens point at paths no longer in the set.
+    out.extend(changed.iter().cloned());
+    let mut queue = out.iter().cloned().collect_vec();
+    while let Some(path) = queue.pop() {
+        for from in referenced_by.get(&path).into_iter().flatten() {
+            if out.insert(from.clone()) {
+                queue.push(from.clone());
+            }
+        }
+    }
+    out
+}
+
+/// The output of every token under `node`, laid out like [`TokenOrGroup::to_css`].
+fn join_tokens(
+    node: &TokenOrGroup,
+    path: &mut Vec<String>,
+    token: &impl Fn(&[String]) -> String,
+) -> String {
+    match node {
+        TokenOrGroup::Token { .. } => token(path),
+        TokenOrGroup::Group(group) => group
+            .iter()
+            .map(|(key, value)| {
+                path.push(key.clone());
+                let out = join_tokens(value, path, token);
+                path.pop();
+                out
+            })
+            .join("\n"),
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = |primary: &str| {
+        crate::parse_design_tokens(
+            &format!(
+                r##"{{
+                    "base": {{ "red": {{ "$value": "#ff0000" }}, "blue": {{ "$value": "{primary}" }} }},
+                    "brand": {{ "primary": {{ "$value": "{{base.blue}}" }} }},
+                    "gap": {{ "$value": "4px" }}
+                }}"##
+            ),
+            None,
+        )
+        .unwrap()
+    };
+    let mut generator = Generator::new(
+        tokens("#0000ff"),
+        CssEmitter::default(),
+        RustEmitter::default(),
+    );
+    let full = |sets: &[DesignTokens]| {
+        let resolved = ResolvedTokens::new(sets);
+        [
+            CssEmitter::default().emit(&resolved),
+            RustEmitter::default().emit(&resolved),
+        ]
+    };
+    assert_eq!(
+        generator.outputs().values().collect_vec(),
+        full(&tokens("#0000ff")).iter().collect_vec()
+    );
+
+    let regenerated = generator.replace(tokens("#0000aa")).unwrap();
+    let paths = regenerated
+        .tokens
+        .iter()
+        .map(|(_, path)| path.to_string())
+        .collect_vec();
+    assert_eq!(paths, ["base/blue", "brand/primary"]);
+    assert_eq!(regenerated.outputs.len(), 2);
+    assert_eq!(
+        generator.outputs().values().collect_vec(),
+        full(&tokens("#0000aa")).iter().collect_vec()
+    );
+
+    assert!(generator
+        .replace(tokens("#0000aa"))
+        .unwrap()
+        .tokens
+        .is_empty());
+
+    // A broken reference fails without touching the outputs, and the next good set regenerates
+    // from the last good one.
+    let err = generator.replace(tokens("{base.green}")).unwrap_err();
+    assert_eq!(err, "Unresolved reference {base.green} in base/blue");
+    assert_eq!(
+        generator.outputs().values().collect_vec(),
+        full(&tokens("#0000aa")).iter().collect_vec()
+    );
+    let regenerated = generator.replace(tokens("#00ff00")).unwrap();
+    assert_eq!(regenerated.tokens.len(), 2);
+    assert_eq!(
+        generator.outputs().values().collect_vec(),
+        full(&tokens("#00ff00")).iter().collect_vec()
+    );
+
+    #[cfg(feature = "ambient-tokens")]
+    {
+        let sets = crate::design_tokens();
+        let generator =
+            Generator::new(sets.to_vec(), CssEmitter::default(), RustEmitter::default());
+        assert_eq!(
+            generator.outputs().values().collect_vec(),
+            full(sets).iter().collect_vec()
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use convert_case::{Case, Casing};
+use itertools::Itertools;
+
+use super::{dimension::NativeNumber, fmt_float, ident, sibling_idents};
+use crate::{
+    expression::Value, naming::Naming, resolve_single, DesignTokens, TokenOrGroup, TokenValue,
+};
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+/// Keywords only come up when [`Naming::case`] overrides the `UPPER_FLAT` and `UpperCamel`
+/// defaults.
+fn escape(x: String) -> String {
+    if KEYWORDS.contains(&x.as_str()) {
+        format!("`{x}`")
+    } else {
+        x
+    }
+}
+
+/// Members use the same `UPPER_FLAT` slugs as the generated Rust constants and objects are
+/// `UpperCamel`, numbered where siblings collide.
+fn names<'a>(naming: &Naming, keys: impl IntoIterator<Item = (&'a str, Case)>) -> Vec<String> {
+    sibling_idents(naming, keys)
+        .into_iter()
+        .map(escape)
+        .collect()
+}
+
+/// `None` for values Compose has no equivalent for.
+fn value(tokens: &DesignTokens, key: &str, value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Color(c) => {
+            let [r, g, b, a] = c.to_rgba8();
+            format!("Color(0x{a:02X}{r:02X}{g:02X}{b:02X})")
+        }
+        Value::Number(v, unit) => match NativeNumber::new(tokens, *v, unit) {
+            NativeNumber::Fraction(v) => format!("{}f", fmt_float(v)),
+            NativeNumber::Seconds(v) => format!("{}.milliseconds", fmt_float(v * 1000.)),
+            NativeNumber::Length(v) => match key.to_case(Case::Kebab).as_str() {
+                "font-weight" => fmt_float(v),
+                "font-size" | "line-height" | "letter-spacing" => {
+                    format!("{}.sp", fmt_float(v))
+                }
+                _ => format!("{}.dp", fmt_float(v)),
+            },
+            NativeNumber::Unsupported => return None,
+        },
+        Value::CubicBezier(p) => format!(
+            "CubicBezierEasing({})",
+            p.iter().map(|x| format!("{}f", fmt_float(*x))).join(", ")
+        ),
+        Value::Bool(v) => v.to_string(),
+        Value::Any(v) => format!("{:?}", v).replace('$', "\\$"),
+    })
+}
+
+fn member(tokens: &DesignTokens, indent: &str, name: &str, key: &str, v: &Value) -> String {
+    match value(tokens, key, v) {
+        Some(v) => format!("{indent}val {name} = {v}"),
+        None => format!(
+            "{indent}// {name} = {} has no Kotlin equivalent",
+            v.to_css()
+        ),
+    }
+}
+
+fn emit(
+    tokens: &DesignTokens,
+    key: &str,
+    name: &str,
+    token: &TokenOrGroup,
+    depth: usize,
+) -> String {
+    let indent = "    ".repeat(depth);
+    let naming = &tokens.options.naming;
+    match token {
+        TokenOrGroup::Token {
+            value: TokenValue::Single(expr),
+            extensions,
+            ..
+        } => member(
+            tokens,
+            &indent,
+            name,
+            key,
+            &resolve_single(tokens, expr, extensions),
+        ),
+        TokenOrGroup::Token {
+            value: TokenValue::Dict(dict),
+            ..
+        } => {
+            let names = names(naming, dict.keys().map(|k| (k.as_str(), Case::UpperFlat)));
+            let members = dict
+                .iter()
+                .zip(names)
+                .map(|((k, v), name)| {
+                    member(
+                        tokens,
+                        &format!("{indent}    "),
+                        &name,
+                        k,
+                        &v.get_value(tokens),
+                    )
+                })
+                .join("\n");
+            format!("{indent}object {name} {{\n{members}\n{indent}}}")
+        }
+        TokenOrGroup::Group(group) => {
+            let names = names(
+                naming,
+                group.iter().map(|(k, v)| match v {
+                    TokenOrGroup::Token {
+                        value: TokenValue::Single(_),
+                        ..
+                    } => (k.as_str(), Case::UpperFlat),
+                    _ => (k.as_str(), Case::UpperCamel),
+                }),
+            );
+            let members = group
+                .iter()
+                .zip(names)
+                .map(|((k, v), name)| emit(tokens, k, &name, v, depth + 1))
+                .join("\n");
+            format!("{indent}object {name} {{\n{members}\n{indent}}}")
+        }
+    }
+}
+
+/// The object name of a set.
+fn set_name(tokens: &DesignTokens) -> String {
+    escape(ident(
+        &tokens.options.naming,
+        tokens.get_name(),
+        Case::UpperCamel,
+    ))
+}
+
+impl DesignTokens {
+    /// A Kotlin object named after this set, with a nested object per group for Jetpack Compose.
+    pub fn to_kotlin(&self) -> String {
+        emit(self, self.get_name(), &set_name(self), &self.body, 0)
+    }
+}
+
+/// A complete Kotlin source file with every set nested under `object DesignTokens`.
+pub fn to_kotlin(tokens: &[DesignTokens], package: &str) -> String {
+    let sets = tokens
+        .iter()
+        .map(|x| emit(x, x.get_name(), &set_name(x), &x.body, 1))
+        .join("\n");
+    format!(
+        "package {package}\n\nimport androidx.compose.animation.core.CubicBezierEasing\nimport androidx.compose.ui.graphics.Color\nimport androidx.compose.ui.unit.dp\nimport androidx.compose.ui.unit.sp\nimport kotlin.time.Duration.Companion.milliseconds\n\nobject DesignTokens {{\n{sets}\n}}\n"
+    )
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#2871f980" }, "in": { "$value": 4 } },
+            "font": { "$type": "typography", "$value": { "fontSize": 20, "fontWeight": 400 } }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].to_kotlin(),
+        r#"object Tokens {
+    object Brand {
+        val PRIMARY = Color(0x802871F9)
+        val IN = 4.dp
+    }
+    object Font {
+        val FONTSIZE = 20.sp
+        val FONTWEIGHT = 400
+    }
+}"#
+    );
+
+    let mut units = crate::parse_design_tokens(
+        r#"{
+            "gap": { "$value": "1.5rem" },
+            "full": { "$value": "100vw" },
+            "fast": { "$value": "150ms", "$type": "duration" }
+        }"#,
+        Some("units.json"),
+    )
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        units.to_kotlin(),
+        "object Units {\n    val GAP = 24.dp\n    // FULL = 100vw has no Kotlin equivalent\n    val FAST = 150.milliseconds\n}"
+    );
+    units.options.rem_base = Some(10.);
+    assert!(units.to_kotlin().contains("val GAP = 15.dp"));
+
+    let mut names = crate::parse_design_tokens(
+        r#"{
+            "a%b": { "$value": 1 },
+            "Size 2": { "$value": 2 },
+            "size2": { "$value": 3 },
+            "in": { "$value": 4 }
+        }"#,
+        Some("names.json"),
+    )
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        names.to_kotlin(),
+        "object Names {\n    val A_B = 1.dp\n    val SIZE2 = 2.dp\n    val SIZE2_2 = 3.dp\n    val IN = 4.dp\n}"
+    );
+    names.options.naming.case = Some(crate::naming::NameCase::Flat);
+    assert!(names.to_kotlin().contains("    val `in` = 4.dp"));
+}
@@ -0,0 +1,99 @@
+use itertools::Itertools;
+
+use super::dimension::rust_unit;
+use crate::{
+    composite,
+    expression::{NumberType, Value},
+    resolve_single, DesignTokens, RustColor, RustOptions, TokenOrGroup, TokenValue,
+};
+
+/// The `TokenValue` enum the values of `TOKENS` are wrapped in.
+pub(crate) fn rust_token_value(options: &RustOptions) -> String {
+    let color = match options.color {
+        RustColor::Hex => "&'static str",
+        RustColor::LinearRgba => "[f32; 4]",
+        RustColor::GlamVec4 => "glam::Vec4",
+    };
+    let dimension = match options.dimensions {
+        true => " Dimension(Dimension),",
+        false => "",
+    };
+    format!(
+        "#[derive(Debug, Clone, Copy, PartialEq)]\npub enum TokenValue {{ Color({color}), Number(f32), Duration(std::time::Duration), CubicBezier([f32; 4]), Bool(bool), String(&'static str),{dimension} Transition(Transition), Fields(&'static [(&'static str, &'static str)]) }}"
+    )
+}
+
+impl DesignTokens {
+    /// The `TokenValue` variant the constant generated for `token` goes in.
+    fn rust_variant(&self, token: &TokenOrGroup, options: &RustOptions) -> &'static str {
+        match token {
+            TokenOrGroup::Token {
+                value: TokenValue::Single(expr),
+                extensions,
+                ..
+            } => match resolve_single(self, expr, extensions) {
+                Value::Color(_) => "Color",
+                Value::Number(_, NumberType::Seconds | NumberType::Milliseconds) => "Duration",
+                Value::Number(_, unit) if rust_unit(options, &unit).is_some() => "Dimension",
+                Value::Number(_, _) => "Number",
+                Value::CubicBezier(_) => "CubicBezier",
+                Value::Bool(_) => "Bool",
+                Value::Any(_) => "String",
+            },
+            TokenOrGroup::Token {
+                value: TokenValue::Dict(dict),
+                type_,
+                ..
+            } => match composite::rust_composite(self, type_, dict) {
+                Some(Ok(_)) => "Transition",
+                _ => "Fields",
+            },
+            TokenOrGroup::Group(_) => unreachable!(),
+        }
+    }
+    /// `pub static TOKENS`, mapping each `/`-separated token path to its constant.
+    pub(crate) fn rust_lookup(&self, options: &RustOptions) -> String {
+        let entries = self
+            .tokens()
+            .into_iter()
+            .zip(self.rust_names())
+            .filter(|((_, token), _)| !self.unresolvable(token))
+            .map(|((path, token), name)| {
+                format!(
+                    "{:?} => super::TokenValue::{}({name})",
+                    path.join("/"),
+                    self.rust_variant(token, options)
+                )
+            })
+            .join(", ");
+        format!("/// Every token by path, for lookups at runtime.\n#[allow(deprecated)]\npub static TOKENS: phf::Map<&'static str, super::TokenValue> = phf::phf_map! {{ {entries} }};")
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#ff0000" }, "Gap": { "$value": "4px" } },
+            "fade": { "$type": "transition", "$value": { "duration": "200ms" } },
+            "old": { "$value": "1s", "$deprecated": true }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let options = RustOptions {
+        lookup: true,
+        ..Default::default()
+    };
+    assert!(tokens[0].to_rust_with(&options).ends_with(
+        "\
+#[allow(deprecated)]
+pub static TOKENS: phf::Map<&'static str, super::TokenValue> = phf::phf_map! {
+    \"Brand/Primary\" => super::TokenValue::Color(BRAND_PRIMARY),
+    \"Brand/Gap\" => super::TokenValue::Number(BRAND_GAP),
+    \"fade\" => super::TokenValue::Transition(FADE),
+    \"old\" => super::TokenValue::Duration(OLD),
+};"
+    ));
+    assert!(!tokens[0].to_rust().contains("TOKENS"));
+}
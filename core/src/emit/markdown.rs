@@ -0,0 +1,102 @@
+use itertools::Itertools;
+
+use super::raw_value;
+use crate::{DesignTokens, TokenOrGroup, TokenType};
+
+fn cell(x: &str) -> String {
+    x.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn group(tokens: &DesignTokens, path: &mut Vec<String>, node: &TokenOrGroup, out: &mut String) {
+    let TokenOrGroup::Group(group) = node else {
+        unreachable!()
+    };
+    let rows = group
+        .iter()
+        .filter_map(|(key, token)| {
+            let TokenOrGroup::Token {
+                value,
+                type_,
+                description,
+                ..
+            } = token
+            else {
+                return None;
+            };
+            let resolved = match tokens.missing_ref(token) {
+                Some(missing) => format!("unresolved reference {{{}}}", missing.join(".")),
+                None => token.resolve_css(tokens),
+            };
+            Some(format!(
+                "| `{}` | {} | `{}` | `{}` | {} |",
+                cell(&path.iter().chain([key]).join("/")),
+                match type_ {
+                    TokenType::None => "",
+                    type_ => type_.as_str(),
+                },
+                cell(&raw_value(value)),
+                cell(&resolved),
+                cell(description.as_deref().unwrap_or_default())
+            ))
+        })
+        .join("\n");
+    if !rows.is_empty() {
+        let title = match path.is_empty() {
+            true => tokens.get_name().to_string(),
+            false => path.join("/"),
+        };
+        *out += &format!(
+            "\n## {title}\n\n| Token | Type | Value | Resolved | Description |\n| --- | --- | --- | --- | --- |\n{rows}\n"
+        );
+    }
+    for (key, node) in group {
+        if let TokenOrGroup::Group(_) = node {
+            path.push(key.clone());
+            self::group(tokens, path, node, out);
+            path.pop();
+        }
+    }
+}
+
+impl DesignTokens {
+    /// A table per group listing each token's path, type, raw and resolved value and description.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n", self.get_name());
+        group(self, &mut Vec::new(), &self.body, &mut out);
+        out
+    }
+}
+
+pub fn to_markdown(tokens: &[DesignTokens]) -> String {
+    tokens.iter().map(|x| x.to_markdown()).join("\n")
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "color": { "red": { "$value": "#ff0000", "$type": "color", "$description": "Errors | alerts" } },
+            "space": { "s": { "$value": "4px" }, "m": { "$value": "{space.s} * 2" } }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].to_markdown(),
+        r#"# tokens
+
+## color
+
+| Token | Type | Value | Resolved | Description |
+| --- | --- | --- | --- | --- |
+| `color/red` | color | `#ff0000` | `#ff0000` | Errors \| alerts |
+
+## space
+
+| Token | Type | Value | Resolved | Description |
+| --- | --- | --- | --- | --- |
+| `space/s` |  | `4px` | `4px` |  |
+| `space/m` |  | `{space.s} * 2` | `8px` |  |
+"#
+    );
+}
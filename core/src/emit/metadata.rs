@@ -0,0 +1,71 @@
+use itertools::Itertools;
+
+use super::raw_value;
+use crate::{DesignTokens, TokenOrGroup};
+
+pub(crate) const TOKEN_META: &str = "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub struct TokenMeta { pub name: &'static str, pub group: &'static [&'static str], pub type_: &'static str, pub expression: &'static str, pub description: Option<&'static str> }";
+
+impl DesignTokens {
+    /// `pub static TOKEN_METADATA`, describing each generated constant in order.
+    pub(crate) fn rust_metadata(&self) -> String {
+        let entries = self
+            .tokens()
+            .into_iter()
+            .filter(|(_, token)| !self.unresolvable(token))
+            .map(|(path, token)| {
+                let TokenOrGroup::Token {
+                    value,
+                    type_,
+                    description,
+                    ..
+                } = token
+                else {
+                    unreachable!()
+                };
+                let (name, group) = path.split_last().unwrap();
+                format!(
+                    "super::TokenMeta {{ name: {name:?}, group: &[{}], type_: {:?}, expression: {:?}, description: {:?} }}",
+                    group.iter().map(|x| format!("{x:?}")).join(", "),
+                    type_.as_str(),
+                    raw_value(value),
+                    description
+                )
+            })
+            .join(", ");
+        format!("/// Where each token comes from, in the order of the constants.\npub static TOKEN_METADATA: &[super::TokenMeta] = &[{entries}];")
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "Space": { "base": { "$type": "dimension", "$value": "4px", "$description": "Grid unit" }, "m": { "$value": "{Space.base} * 2" } }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let options = crate::RustOptions {
+        metadata: true,
+        ..Default::default()
+    };
+    assert!(tokens[0].to_rust_with(&options).ends_with(
+        "\
+pub static TOKEN_METADATA: &[super::TokenMeta] = &[
+    super::TokenMeta {
+        name: \"base\",
+        group: &[\"Space\"],
+        type_: \"dimension\",
+        expression: \"4px\",
+        description: Some(\"Grid unit\"),
+    },
+    super::TokenMeta {
+        name: \"m\",
+        group: &[\"Space\"],
+        type_: \"none\",
+        expression: \"{Space.base} * 2\",
+        description: None,
+    },
+];"
+    ));
+}
@@ -0,0 +1,452 @@
+use convert_case::Case;
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::{
+    composite, naming::Naming, sanitize_ident, to_css_property_rules, to_css_themed, unique_idents,
+    CssOptions, DesignTokens, RustOptions, ThemeSelector, TokenOrGroup, TokenType, TokenValue,
+};
+
+pub mod android;
+pub mod breakpoints;
+pub mod css_files;
+pub mod css_format;
+pub mod dart;
+pub mod dimension;
+pub mod fonts;
+pub mod incremental;
+pub mod kotlin;
+pub mod lookup;
+pub mod markdown;
+pub mod metadata;
+pub mod rust_format;
+pub mod rust_tests;
+pub mod styleguide;
+pub mod svg;
+pub mod swift;
+#[cfg(feature = "template")]
+pub mod template;
+pub mod theme;
+pub mod utilities;
+
+/// The token sets handed to an emitter. Values are resolved against the set they belong to.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedTokens<'a> {
+    pub sets: &'a [DesignTokens],
+}
+impl<'a> ResolvedTokens<'a> {
+    pub fn new(sets: &'a [DesignTokens]) -> Self {
+        Self { sets }
+    }
+}
+
+/// An output format. Implement this to add formats without modifying this crate.
+pub trait Emitter: Send + Sync {
+    /// Key the emitter is registered under, e.g. `css`.
+    fn name(&self) -> &str;
+    fn emit(&self, tokens: &ResolvedTokens) -> String;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CssEmitter {
+    pub options: CssOptions,
+}
+impl Emitter for CssEmitter {
+    fn name(&self) -> &str {
+        "css"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        let css = tokens
+            .sets
+            .iter()
+            .map(|x| x.to_css_with(&format!(".{}", x.get_name_css()), &self.options))
+            .join("\n");
+        with_property_rules(tokens, &self.options, css)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThemedCssEmitter {
+    pub default: String,
+    pub selector: ThemeSelector,
+    pub options: CssOptions,
+}
+impl Emitter for ThemedCssEmitter {
+    fn name(&self) -> &str {
+        "css-themed"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        let css = to_css_themed(tokens.sets, &self.default, self.selector, &self.options);
+        with_property_rules(tokens, &self.options, css)
+    }
+}
+
+/// Prepends `@property` rules when registering properties, and lays out the result.
+fn with_property_rules(tokens: &ResolvedTokens, options: &CssOptions, css: String) -> String {
+    let css = match options.register_properties {
+        true => format!("{}\n{css}", to_css_property_rules(tokens.sets, options)),
+        false => css,
+    };
+    css_format::format_css(&css, options)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RustEmitter {
+    pub options: RustOptions,
+}
+impl Emitter for RustEmitter {
+    fn name(&self) -> &str {
+        "rust"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        self.emit_with(tokens, |i, options| tokens.sets[i].rust_items(options))
+    }
+}
+impl RustEmitter {
+    /// Lays out the output around the items of each set, given by index.
+    pub(crate) fn emit_with(
+        &self,
+        tokens: &ResolvedTokens,
+        items: impl Fn(usize, &RustOptions) -> Vec<String>,
+    ) -> String {
+        let types = tokens
+            .sets
+            .iter()
+            .flat_map(|x| x.iter())
+            .filter_map(|(_, token)| match token {
+                TokenOrGroup::Token { type_, .. } => Some(type_.clone()),
+                TokenOrGroup::Group(_) => None,
+            })
+            // `TokenValue::Transition` needs the struct even without transition tokens.
+            .chain(self.options.lookup.then_some(TokenType::Transition))
+            .collect_vec();
+        let themes = match self.options.theme {
+            true => theme::mode_themes(tokens.sets, &self.options),
+            false => Default::default(),
+        };
+        let prelude = std::iter::once(composite::rust_prelude(&types))
+            .chain(
+                self.options
+                    .lookup
+                    .then(|| lookup::rust_token_value(&self.options)),
+            )
+            .chain(
+                self.options
+                    .metadata
+                    .then(|| metadata::TOKEN_META.to_string()),
+            )
+            .chain(
+                self.options
+                    .dimensions
+                    .then(|| dimension::DIMENSION.to_string()),
+            )
+            .filter(|x| !x.is_empty())
+            .collect_vec();
+        let mut out = String::new();
+        rust_format::write_items(&prelude, 0, &mut out);
+        for (i, x) in tokens.sets.iter().enumerate() {
+            let items = match themes.structs.get(&i) {
+                // Modes share a top-level struct instead of each having their own.
+                Some(name) => {
+                    let options = RustOptions {
+                        theme: false,
+                        ..self.options.clone()
+                    };
+                    let mut items = items(i, &options);
+                    items.push(x.rust_theme_const(&options, name));
+                    items
+                }
+                None => items(i, &self.options),
+            };
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str("#[allow(non_snake_case)]\npub mod ");
+            out.push_str(&x.get_name_rust());
+            out.push_str(" {\n");
+            rust_format::write_items(&items, 4, &mut out);
+            out.push_str("\n}");
+        }
+        if !themes.items.is_empty() && !out.is_empty() {
+            out.push('\n');
+            rust_format::write_items(&themes.items, 0, &mut out);
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SwiftEmitter;
+impl Emitter for SwiftEmitter {
+    fn name(&self) -> &str {
+        "swift"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        swift::to_swift(tokens.sets)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KotlinEmitter {
+    pub package: String,
+}
+impl Emitter for KotlinEmitter {
+    fn name(&self) -> &str {
+        "kotlin"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        kotlin::to_kotlin(tokens.sets, &self.package)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DartEmitter;
+impl Emitter for DartEmitter {
+    fn name(&self) -> &str {
+        "dart"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        dart::to_dart(tokens.sets)
+    }
+}
+
+/// Writes the tokens back out as DTCG JSON: a single tree, or a list of `{ fileName, body }` sets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonEmitter;
+impl Emitter for JsonEmitter {
+    fn name(&self) -> &str {
+        "json"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        match tokens.sets {
+            [set] => set.to_json(),
+            sets => serde_json::to_string_pretty(sets).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownEmitter;
+impl Emitter for MarkdownEmitter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        markdown::to_markdown(tokens.sets)
+    }
+}
+
+/// A standalone HTML style guide, see [`styleguide::to_styleguide`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StyleguideEmitter;
+impl Emitter for StyleguideEmitter {
+    fn name(&self) -> &str {
+        "styleguide"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        styleguide::to_styleguide(tokens.sets)
+    }
+}
+
+/// Color swatches as an SVG image, see [`svg::to_svg_swatches`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SvgEmitter;
+impl Emitter for SvgEmitter {
+    fn name(&self) -> &str {
+        "svg"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        svg::to_svg_swatches(tokens.sets)
+    }
+}
+
+/// Utility classes, see [`utilities::to_utilities`].
+#[derive(Debug, Clone)]
+pub struct UtilityEmitter {
+    /// Template with `{utility}` and `{name}` placeholders, `{utility}-{name}` by default.
+    pub class_name: String,
+    pub options: CssOptions,
+}
+impl Default for UtilityEmitter {
+    fn default() -> Self {
+        Self {
+            class_name: "{utility}-{name}".to_string(),
+            options: Default::default(),
+        }
+    }
+}
+impl Emitter for UtilityEmitter {
+    fn name(&self) -> &str {
+        "utilities"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        let css = utilities::to_utilities(tokens.sets, &self.class_name, &self.options);
+        css_format::format_css(&css, &self.options)
+    }
+}
+
+/// `@font-face` rules, see [`fonts::to_font_faces`].
+#[derive(Debug, Clone, Default)]
+pub struct FontFaceEmitter {
+    pub files: fonts::FontFiles,
+}
+impl Emitter for FontFaceEmitter {
+    fn name(&self) -> &str {
+        "font-face"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        fonts::to_font_faces(tokens.sets, &self.files)
+    }
+}
+
+/// The font files to ship, see [`fonts::to_font_manifest`].
+#[derive(Debug, Clone, Default)]
+pub struct FontManifestEmitter {
+    pub files: fonts::FontFiles,
+}
+impl Emitter for FontManifestEmitter {
+    fn name(&self) -> &str {
+        "font-manifest"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        fonts::to_font_manifest(tokens.sets, &self.files)
+    }
+}
+
+/// `@custom-media` queries, see [`breakpoints::to_custom_media`].
+#[derive(Debug, Clone, Default)]
+pub struct CustomMediaEmitter {
+    /// Group holding the breakpoints, found by name when `None`.
+    pub path: Option<String>,
+}
+impl Emitter for CustomMediaEmitter {
+    fn name(&self) -> &str {
+        "custom-media"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        breakpoints::to_custom_media(tokens.sets, self.path.as_deref())
+    }
+}
+
+/// A SCSS breakpoint map and mixin, see [`breakpoints::to_scss_breakpoints`].
+#[derive(Debug, Clone, Default)]
+pub struct ScssBreakpointsEmitter {
+    pub path: Option<String>,
+}
+impl Emitter for ScssBreakpointsEmitter {
+    fn name(&self) -> &str {
+        "scss-breakpoints"
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        breakpoints::to_scss_breakpoints(tokens.sets, self.path.as_deref())
+    }
+}
+
+/// Emitters keyed by [`Emitter::name`]. Registering a name twice replaces the earlier emitter.
+#[derive(Default)]
+pub struct EmitterRegistry {
+    emitters: IndexMap<String, Box<dyn Emitter>>,
+}
+impl EmitterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// All emitters built into this crate, with default options.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(CssEmitter::default());
+        registry.register(ThemedCssEmitter {
+            default: "light".to_string(),
+            ..Default::default()
+        });
+        registry.register(RustEmitter::default());
+        registry.register(SwiftEmitter);
+        registry.register(KotlinEmitter {
+            package: "design.tokens".to_string(),
+        });
+        registry.register(DartEmitter);
+        registry.register(JsonEmitter);
+        registry.register(MarkdownEmitter);
+        registry.register(StyleguideEmitter);
+        registry.register(SvgEmitter);
+        registry.register(UtilityEmitter::default());
+        registry.register(FontFaceEmitter::default());
+        registry.register(FontManifestEmitter::default());
+        registry.register(CustomMediaEmitter::default());
+        registry.register(ScssBreakpointsEmitter::default());
+        registry
+    }
+    pub fn register(&mut self, emitter: impl Emitter + 'static) {
+        self.emitters
+            .insert(emitter.name().to_string(), Box::new(emitter));
+    }
+    pub fn get(&self, name: &str) -> Option<&dyn Emitter> {
+        self.emitters.get(name).map(|x| x.as_ref())
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.emitters.keys().map(|x| x.as_str())
+    }
+    pub fn emit(&self, name: &str, tokens: &ResolvedTokens) -> Option<String> {
+        Some(self.get(name)?.emit(tokens))
+    }
+}
+
+/// Formats a float with at most four decimals and no trailing zeros.
+pub(crate) fn fmt_float(x: f64) -> String {
+    let x = (x * 10000.).round() / 10000.;
+    if x == 0. {
+        "0".to_string()
+    } else {
+        format!("{}", x)
+    }
+}
+
+/// The value as written in the source.
+pub fn raw_value(value: &TokenValue) -> String {
+    match value {
+        TokenValue::Single(expr) => expr.to_source(),
+        TokenValue::Dict(dict) => dict
+            .iter()
+            .map(|(k, v)| format!("{k}: {}", v.to_source()))
+            .join("; "),
+    }
+}
+
+/// Turns a token key into an identifier in `case`, see [`sanitize_ident`].
+pub(crate) fn ident(naming: &Naming, key: &str, case: Case) -> String {
+    sanitize_ident(&naming.segment(key, Some(case), "_"))
+}
+
+/// Identifiers for sibling keys, each in its own case, numbered where they collide as with the
+/// Rust constants, e.g. `SIZE2_2`.
+pub(crate) fn sibling_idents<'a>(
+    naming: &Naming,
+    keys: impl IntoIterator<Item = (&'a str, Case)>,
+) -> Vec<String> {
+    let raw = keys
+        .into_iter()
+        .map(|(key, case)| naming.segment(key, Some(case), "_"))
+        .collect_vec();
+    unique_idents(&raw, sanitize_ident)
+}
+
+#[test]
+#[cfg(feature = "ambient-tokens")]
+fn test_registry() {
+    struct Count;
+    impl Emitter for Count {
+        fn name(&self) -> &str {
+            "count"
+        }
+        fn emit(&self, tokens: &ResolvedTokens) -> String {
+            tokens.sets.len().to_string()
+        }
+    }
+    let data = crate::design_tokens();
+    let tokens = ResolvedTokens::new(data);
+    let mut registry = EmitterRegistry::with_builtins();
+    registry.register(Count);
+    assert_eq!(registry.emit("count", &tokens).unwrap(), "5");
+    assert_eq!(registry.emit("css", &tokens).unwrap(), crate::to_css(data));
+    assert!(registry.emit("unknown", &tokens).is_none());
+}
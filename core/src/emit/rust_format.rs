@@ -0,0 +1,205 @@
+//! Line breaking for generated Rust, so long constants read like rustfmt output without
+//! depending on it.
+
+use itertools::Itertools;
+
+const MAX_WIDTH: usize = 100;
+
+#[derive(Debug)]
+enum Doc {
+    Text(String),
+    /// A bracketed, comma-separated list.
+    Group(char, Vec<Vec<Doc>>, char),
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Splits a line of code into text and bracketed groups, keeping string literals whole.
+fn parse(chars: &mut std::iter::Peekable<std::str::Chars>, close: Option<char>) -> Vec<Vec<Doc>> {
+    let mut items = vec![Vec::new()];
+    let mut text = String::new();
+    let flush = |text: &mut String, items: &mut Vec<Vec<Doc>>| {
+        if !text.trim().is_empty() {
+            items
+                .last_mut()
+                .unwrap()
+                .push(Doc::Text(std::mem::take(text)));
+        }
+        text.clear();
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                text.push(c);
+                while let Some(x) = chars.next() {
+                    text.push(x);
+                    match x {
+                        '\\' => text.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' | '[' | '{' => {
+                flush(&mut text, &mut items);
+                let group = parse(chars, Some(closing(c)));
+                items
+                    .last_mut()
+                    .unwrap()
+                    .push(Doc::Group(c, group, closing(c)));
+            }
+            c if Some(c) == close => break,
+            ',' if close.is_some() => {
+                flush(&mut text, &mut items);
+                items.push(Vec::new());
+            }
+            c => text.push(c),
+        }
+    }
+    flush(&mut text, &mut items);
+    // A trailing comma leaves an empty last item.
+    if items.len() > 1 && items.last().is_some_and(|x| x.is_empty()) {
+        items.pop();
+    }
+    items
+}
+
+fn flat(seq: &[Doc]) -> String {
+    let mut out = String::new();
+    write_flat(seq, &mut out);
+    out
+}
+
+/// Appends `seq` on one line to `out`.
+fn write_flat(seq: &[Doc], out: &mut String) {
+    for doc in seq {
+        match doc {
+            Doc::Text(text) => out.push_str(text),
+            Doc::Group(open, items, close) => {
+                out.push(*open);
+                let start = out.len();
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_trimmed(item, out);
+                }
+                if *open == '{' && out.len() > start {
+                    out.insert(start, ' ');
+                    out.push(' ');
+                }
+                out.push(*close);
+            }
+        }
+    }
+}
+
+/// Appends `seq` on one line to `out` without surrounding whitespace.
+fn write_trimmed(seq: &[Doc], out: &mut String) {
+    let start = out.len();
+    write_flat(seq, out);
+    out.truncate(out.trim_end().len());
+    let leading = out[start..].len() - out[start..].trim_start().len();
+    out.drain(start..start + leading);
+}
+
+/// Writes `seq` on one line if it fits, otherwise breaks its last group with an item per line.
+fn print(seq: &[Doc], indent: usize) -> String {
+    let line = flat(seq);
+    let last_group = seq.iter().rposition(|x| matches!(x, Doc::Group(..)));
+    let (Some(i), true) = (last_group, indent + line.trim().len() > MAX_WIDTH) else {
+        return line;
+    };
+    let Doc::Group(open, items, close) = &seq[i] else {
+        unreachable!()
+    };
+    let pad = " ".repeat(indent + 4);
+    let items = items
+        .iter()
+        .map(|x| format!("{pad}{},\n", print(x, indent + 4).trim()))
+        .join("");
+    let prefix = flat(&seq[..i]);
+    let prefix = match open {
+        '{' => format!("{} ", prefix.trim_end()),
+        _ => prefix,
+    };
+    format!(
+        "{prefix}{open}\n{items}{}{close}{}",
+        " ".repeat(indent),
+        flat(&seq[i + 1..])
+    )
+}
+
+/// Appends each generated item to `out`, separated by newlines. Items are indented by `indent`
+/// spaces, breaking their code lines when they are too long. Doc comments, attributes and the
+/// lines opening or closing a multi-line block are kept as they are.
+pub(crate) fn write_items(items: &[String], indent: usize, out: &mut String) {
+    out.reserve(items.iter().map(|x| x.len() + indent + 1).sum());
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_item(item, indent, out);
+    }
+}
+
+fn write_item(item: &str, indent: usize, out: &mut String) {
+    for (i, line) in item.lines().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let code = line.trim_start();
+        let indent = indent + line.len() - code.len();
+        out.extend(std::iter::repeat_n(' ', indent));
+        if code.starts_with("//")
+            || code.starts_with("#[")
+            || code.ends_with('{')
+            || code.starts_with('}')
+        {
+            out.push_str(code);
+        } else {
+            let seq = parse(&mut code.chars().peekable(), None).remove(0);
+            out.push_str(print(&seq, indent).trim());
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let format_item = |item: &str, indent| {
+        let mut out = String::new();
+        write_items(&[item.to_string()], indent, &mut out);
+        out
+    };
+    assert_eq!(
+        format_item("pub const A: f32 = 4.;", 4),
+        "    pub const A: f32 = 4.;"
+    );
+    assert_eq!(
+        format_item(
+            "/// `Heading`\npub const HEADING: &[(&str, &str)] = &[(\"fontFamily\", \"Inter, \\\"Open Sans\\\"\"), (\"fontSize\", \"32px\"), (\"lineHeight\", \"40px\")];",
+            0
+        ),
+        "\
+/// `Heading`
+pub const HEADING: &[(&str, &str)] = &[
+    (\"fontFamily\", \"Inter, \\\"Open Sans\\\"\"),
+    (\"fontSize\", \"32px\"),
+    (\"lineHeight\", \"40px\"),
+];"
+    );
+    assert_eq!(
+        format_item("pub const FADE: super::Transition = super::Transition { duration: std::time::Duration::from_millis(200), delay: std::time::Duration::ZERO };", 0),
+        "\
+pub const FADE: super::Transition = super::Transition {
+    duration: std::time::Duration::from_millis(200),
+    delay: std::time::Duration::ZERO,
+};"
+    );
+}
@@ -0,0 +1,62 @@
+use itertools::Itertools;
+
+use crate::{DesignTokens, RustOptions};
+
+impl DesignTokens {
+    /// A `#[cfg(test)]` module that loads `source` when the tests run and checks that every
+    /// constant still resolves to the value it was generated with.
+    pub(crate) fn rust_tests(&self, source: &str, options: &RustOptions) -> String {
+        let asserts = self
+            .rust_values(options)
+            .into_iter()
+            .map(|(name, value)| format!("        assert_eq!(values[{name:?}], {value:?});"))
+            .join("\n");
+        format!(
+            "\
+#[cfg(test)]
+mod tests {{
+    #[test]
+    fn resolved_values() {{
+        let sets = ambient_design_tokens_core::load_design_tokens({source:?}).unwrap();
+        let set = sets.iter().find(|x| x.get_name() == {:?}).unwrap();
+        let options = ambient_design_tokens_core::RustOptions {{
+            color: ambient_design_tokens_core::RustColor::{:?},
+            ..Default::default()
+        }};
+        let values = set.rust_values(&options);
+{asserts}
+    }}
+}}",
+            self.get_name(),
+            options.color
+        )
+    }
+}
+
+#[test]
+fn test() {
+    let path = std::env::temp_dir().join("rust_tests.tokens.json");
+    std::fs::write(&path, r#"{ "gap": { "$value": "4px" } }"#).unwrap();
+    let tokens = crate::load_design_tokens(&path).unwrap();
+    let options = RustOptions {
+        tests: Some("tokens.json".to_string()),
+        ..Default::default()
+    };
+    assert!(tokens[0].to_rust_with(&options).ends_with(
+        "\
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn resolved_values() {
+        let sets = ambient_design_tokens_core::load_design_tokens(\"tokens.json\").unwrap();
+        let set = sets.iter().find(|x| x.get_name() == \"tokens\").unwrap();
+        let options = ambient_design_tokens_core::RustOptions {
+            color: ambient_design_tokens_core::RustColor::Hex,
+            ..Default::default()
+        };
+        let values = set.rust_values(&options);
+        assert_eq!(values[\"GAP\"], \"4.\");
+    }
+}"
+    ));
+}
@@ -0,0 +1,180 @@
+use itertools::Itertools;
+
+use super::raw_value;
+use crate::{
+    css_property,
+    expression::{NumberType, Value},
+    resolve_single, DesignTokens, TokenOrGroup, TokenType, TokenValue,
+};
+
+const STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+section { margin-left: 1rem; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1rem; }
+td { border-top: 1px solid #ddd; padding: 0.5rem; vertical-align: middle; }
+code { font-size: 0.85rem; }
+.swatch { width: 3rem; height: 3rem; border-radius: 4px; border: 1px solid #0002; }
+.bar { height: 0.75rem; background: #4b7bec; }
+.specimen { margin: 0; }";
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+enum Resolved {
+    Single(Value),
+    Dict(Vec<(String, Value)>),
+    Unresolved(Vec<String>),
+}
+
+/// A visual sample of the resolved value: a swatch, a bar for sizes, or a text specimen.
+fn preview(type_: &TokenType, value: &Resolved) -> String {
+    match value {
+        Resolved::Single(Value::Color(c)) => format!(
+            "<div class=\"swatch\" style=\"background: {}\"></div>",
+            c.to_hex_string()
+        ),
+        Resolved::Single(
+            value @ Value::Number(
+                v,
+                NumberType::Pixels | NumberType::Rem | NumberType::Em | NumberType::Percentage,
+            ),
+        ) if *v > 0. => format!(
+            "<div class=\"bar\" style=\"width: {}\"></div>",
+            escape(&value.to_css())
+        ),
+        Resolved::Dict(fields) if *type_ == TokenType::Typography => format!(
+            "<p class=\"specimen\" style=\"{}\">The quick brown fox jumps over the lazy dog</p>",
+            escape(
+                &fields
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", css_property(type_, key), value.to_css()))
+                    .join("; ")
+            )
+        ),
+        _ => String::new(),
+    }
+}
+
+fn row(tokens: &DesignTokens, path: &[String], token: &TokenOrGroup) -> String {
+    let TokenOrGroup::Token {
+        value,
+        type_,
+        extensions,
+        description,
+        ..
+    } = token
+    else {
+        unreachable!()
+    };
+    let resolved = match (tokens.missing_ref(token), value) {
+        (Some(missing), _) => Resolved::Unresolved(missing),
+        (None, TokenValue::Single(expr)) => {
+            Resolved::Single(resolve_single(tokens, expr, extensions))
+        }
+        (None, TokenValue::Dict(dict)) => Resolved::Dict(
+            dict.iter()
+                .map(|(k, v)| (k.clone(), v.get_value(tokens)))
+                .collect(),
+        ),
+    };
+    let resolved_text = match &resolved {
+        Resolved::Single(value) => value.to_css(),
+        Resolved::Dict(fields) => fields
+            .iter()
+            .map(|(k, v)| format!("{k}: {}", v.to_css()))
+            .join("; "),
+        Resolved::Unresolved(missing) => {
+            format!("unresolved reference {{{}}}", missing.join("."))
+        }
+    };
+    format!(
+        "<tr><td>{}</td><td><strong>{}</strong><br><small>{}</small></td><td><code>{}</code></td><td><code>{}</code></td><td>{}</td></tr>",
+        preview(type_, &resolved),
+        escape(&path.join("/")),
+        escape(description.as_deref().unwrap_or_default()),
+        escape(&raw_value(value)),
+        escape(&resolved_text),
+        escape(match type_ {
+            TokenType::None => "",
+            type_ => type_.as_str(),
+        })
+    )
+}
+
+/// Tokens of a group in a table, followed by a nested section per subgroup.
+fn section(tokens: &DesignTokens, path: &mut Vec<String>, group: &TokenOrGroup) -> String {
+    let TokenOrGroup::Group(group) = group else {
+        unreachable!()
+    };
+    let rows = group
+        .iter()
+        .filter(|(_, x)| matches!(x, TokenOrGroup::Token { .. }))
+        .map(|(key, token)| {
+            path.push(key.clone());
+            let row = row(tokens, path, token);
+            path.pop();
+            row
+        })
+        .join("\n");
+    let mut out = String::new();
+    if !rows.is_empty() {
+        out += &format!("<table>\n{rows}\n</table>\n");
+    }
+    for (key, x) in group {
+        if let TokenOrGroup::Group(_) = x {
+            path.push(key.clone());
+            let level = (path.len() + 1).min(6);
+            out += &format!(
+                "<section>\n<h{level}>{}</h{level}>\n{}</section>\n",
+                escape(key),
+                section(tokens, path, x)
+            );
+            path.pop();
+        }
+    }
+    out
+}
+
+/// A standalone HTML page documenting every set, with swatches, type specimens and size bars
+/// next to the raw and resolved value of each token, grouped by the token hierarchy.
+pub fn to_styleguide(tokens: &[DesignTokens]) -> String {
+    let sets = tokens
+        .iter()
+        .map(|x| {
+            format!(
+                "<section>\n<h1>{}</h1>\n{}</section>",
+                escape(x.get_name()),
+                section(x, &mut Vec::new(), &x.body)
+            )
+        })
+        .join("\n");
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Design tokens</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n{sets}\n</body>\n</html>\n"
+    )
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "color": { "red": { "$value": "#ff0000", "$type": "color", "$description": "<Danger>" } },
+            "space": { "m": { "$value": "{space.s} * 2" }, "s": { "$value": "4px" } },
+            "type": {
+                "body": { "$type": "typography", "$value": { "fontSize": "16px", "fontFamily": "Inter" } }
+            }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    let html = to_styleguide(&tokens);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2>color</h2>"));
+    assert!(html.contains("<div class=\"swatch\" style=\"background: #ff0000\"></div>"));
+    assert!(html.contains("<small>&lt;Danger&gt;</small>"));
+    assert!(html.contains("<code>{space.s} * 2</code></td><td><code>8px</code>"));
+    assert!(html.contains("<div class=\"bar\" style=\"width: 8px\"></div>"));
+    assert!(html.contains("style=\"font-family: Inter; font-size: 16px\""));
+}
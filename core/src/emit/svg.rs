@@ -0,0 +1,96 @@
+use csscolorparser::Color;
+use itertools::Itertools;
+
+use crate::{expression::Value, resolve_single, DesignTokens, TokenOrGroup, TokenValue};
+
+const COLUMNS: usize = 6;
+const CELL_WIDTH: usize = 160;
+const CELL_HEIGHT: usize = 130;
+const HEADING_HEIGHT: usize = 40;
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Every resolvable single-valued color token of `tokens`, with its path.
+fn colors(tokens: &DesignTokens) -> Vec<(String, Color)> {
+    tokens
+        .iter()
+        .filter(|(_, token)| tokens.missing_ref(token).is_none())
+        .filter_map(|(path, token)| match token {
+            TokenOrGroup::Token {
+                value: TokenValue::Single(expr),
+                extensions,
+                ..
+            } => match resolve_single(tokens, expr, extensions) {
+                Value::Color(color) => Some((path.to_string(), color)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// An SVG grid with a labelled swatch per color token, one block per set. Being plain text, it
+/// shows up as an image diff when a token drop changes colors.
+pub fn to_svg_swatches(tokens: &[DesignTokens]) -> String {
+    let mut y = 0;
+    let mut out = Vec::new();
+    for set in tokens {
+        let colors = colors(set);
+        if colors.is_empty() {
+            continue;
+        }
+        out.push(format!(
+            "<text x=\"10\" y=\"{}\" font-size=\"18\" font-weight=\"bold\">{}</text>",
+            y + 28,
+            escape(set.get_name())
+        ));
+        y += HEADING_HEIGHT;
+        for (i, (path, c)) in colors.iter().enumerate() {
+            let x = (i % COLUMNS) * CELL_WIDTH + 10;
+            let top = y + (i / COLUMNS) * CELL_HEIGHT;
+            out.push(format!(
+                "<g><rect x=\"{x}\" y=\"{top}\" width=\"{}\" height=\"80\" rx=\"6\" fill=\"{}\" stroke=\"#0003\"/><text x=\"{x}\" y=\"{}\" font-size=\"12\">{}</text><text x=\"{x}\" y=\"{}\" font-size=\"11\" fill=\"#666\">{}</text></g>",
+                CELL_WIDTH - 20,
+                c.to_hex_string(),
+                top + 98,
+                escape(path),
+                top + 114,
+                c.to_hex_string()
+            ));
+        }
+        y += colors.len().div_ceil(COLUMNS) * CELL_HEIGHT;
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{y}\" font-family=\"sans-serif\">\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n{}\n</svg>\n",
+        COLUMNS * CELL_WIDTH,
+        out.iter().join("\n")
+    )
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "red": { "$value": "#ff0000" },
+            "faded": { "$value": "{red}", "$extensions": { "studio.tokens": { "modify": { "type": "alpha", "value": "0.5", "space": "srgb" } } } },
+            "gap": { "$value": "4px" }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    let svg = to_svg_swatches(&tokens);
+    assert!(
+        svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"960\" height=\"170\"")
+    );
+    assert!(svg.contains(">tokens</text>"));
+    assert!(svg.contains("fill=\"#ff0000\""));
+    assert!(svg.contains(
+        ">faded</text><text x=\"170\" y=\"154\" font-size=\"11\" fill=\"#666\">#ff000080</text>"
+    ));
+    assert!(!svg.contains(">gap<"));
+}
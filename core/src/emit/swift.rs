@@ -0,0 +1,240 @@
+use convert_case::Case;
+use itertools::Itertools;
+
+use super::{dimension::NativeNumber, fmt_float, ident, sibling_idents};
+use crate::{
+    expression::Value, naming::Naming, resolve_single, DesignTokens, TokenOrGroup, TokenValue,
+};
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "associatedtype",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "continue",
+    "default",
+    "defer",
+    "deinit",
+    "do",
+    "else",
+    "enum",
+    "extension",
+    "fallthrough",
+    "false",
+    "fileprivate",
+    "for",
+    "func",
+    "guard",
+    "if",
+    "import",
+    "in",
+    "init",
+    "inout",
+    "internal",
+    "is",
+    "let",
+    "nil",
+    "open",
+    "operator",
+    "private",
+    "protocol",
+    "public",
+    "repeat",
+    "rethrows",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "subscript",
+    "super",
+    "switch",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "Type",
+    "typealias",
+    "var",
+    "where",
+    "while",
+];
+
+fn escape(x: String) -> String {
+    if KEYWORDS.contains(&x.as_str()) {
+        format!("`{x}`")
+    } else {
+        x
+    }
+}
+
+/// Members are `camelCase` and enums `UpperCamel`, numbered where siblings collide.
+fn names<'a>(naming: &Naming, keys: impl IntoIterator<Item = (&'a str, Case)>) -> Vec<String> {
+    sibling_idents(naming, keys)
+        .into_iter()
+        .map(escape)
+        .collect()
+}
+
+/// `None` for values Swift has no equivalent for.
+fn value(tokens: &DesignTokens, value: &Value) -> Option<(&'static str, String)> {
+    Some(match value {
+        Value::Color(c) => (
+            "UIColor",
+            format!(
+                "UIColor(red: {}, green: {}, blue: {}, alpha: {})",
+                fmt_float(c.r),
+                fmt_float(c.g),
+                fmt_float(c.b),
+                fmt_float(c.a)
+            ),
+        ),
+        Value::Number(v, unit) => match NativeNumber::new(tokens, *v, unit) {
+            NativeNumber::Length(v) | NativeNumber::Fraction(v) => ("CGFloat", fmt_float(v)),
+            NativeNumber::Seconds(v) => ("TimeInterval", fmt_float(v)),
+            NativeNumber::Unsupported => return None,
+        },
+        Value::CubicBezier(p) => (
+            "[CGFloat]",
+            format!("[{}]", p.iter().map(|x| fmt_float(*x)).join(", ")),
+        ),
+        Value::Bool(v) => ("Bool", v.to_string()),
+        Value::Any(v) => ("String", format!("{:?}", v)),
+    })
+}
+
+fn member(tokens: &DesignTokens, indent: &str, name: &str, v: &Value) -> String {
+    match value(tokens, v) {
+        Some((type_, v)) => format!("{indent}public static let {name}: {type_} = {v}"),
+        None => format!("{indent}// {name} = {} has no Swift equivalent", v.to_css()),
+    }
+}
+
+fn emit(tokens: &DesignTokens, name: &str, token: &TokenOrGroup, depth: usize) -> String {
+    let indent = "    ".repeat(depth);
+    let naming = &tokens.options.naming;
+    match token {
+        TokenOrGroup::Token {
+            value: TokenValue::Single(expr),
+            extensions,
+            ..
+        } => member(
+            tokens,
+            &indent,
+            name,
+            &resolve_single(tokens, expr, extensions),
+        ),
+        TokenOrGroup::Token {
+            value: TokenValue::Dict(dict),
+            ..
+        } => {
+            let names = names(naming, dict.keys().map(|k| (k.as_str(), Case::Camel)));
+            let members = dict
+                .values()
+                .zip(names)
+                .map(|(v, name)| {
+                    member(
+                        tokens,
+                        &format!("{indent}    "),
+                        &name,
+                        &v.get_value(tokens),
+                    )
+                })
+                .join("\n");
+            format!("{indent}public enum {name} {{\n{members}\n{indent}}}")
+        }
+        TokenOrGroup::Group(group) => {
+            let names = names(
+                naming,
+                group.iter().map(|(k, v)| match v {
+                    TokenOrGroup::Token {
+                        value: TokenValue::Single(_),
+                        ..
+                    } => (k.as_str(), Case::Camel),
+                    _ => (k.as_str(), Case::UpperCamel),
+                }),
+            );
+            let members = group
+                .values()
+                .zip(names)
+                .map(|(v, name)| emit(tokens, &name, v, depth + 1))
+                .join("\n");
+            format!("{indent}public enum {name} {{\n{members}\n{indent}}}")
+        }
+    }
+}
+
+/// The enum name of a set.
+fn set_name(tokens: &DesignTokens) -> String {
+    escape(ident(
+        &tokens.options.naming,
+        tokens.get_name(),
+        Case::UpperCamel,
+    ))
+}
+
+impl DesignTokens {
+    /// A Swift enum named after this set, with a nested enum per group and a static member per token.
+    pub fn to_swift(&self) -> String {
+        emit(self, &set_name(self), &self.body, 0)
+    }
+}
+
+/// A complete Swift source file with every set nested under `public enum DesignTokens`.
+pub fn to_swift(tokens: &[DesignTokens]) -> String {
+    let sets = tokens
+        .iter()
+        .map(|x| emit(x, &set_name(x), &x.body, 1))
+        .join("\n");
+    format!("import UIKit\n\npublic enum DesignTokens {{\n{sets}\n}}\n")
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#ff0000" }, "default": { "$value": "50%" } },
+            "90-talsbarn": { "Size 2": { "$value": 8 } }
+        }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].to_swift(),
+        r#"public enum Tokens {
+    public enum Brand {
+        public static let primary: UIColor = UIColor(red: 1, green: 0, blue: 0, alpha: 1)
+        public static let `default`: CGFloat = 0.5
+    }
+    public enum _90Talsbarn {
+        public static let size2: CGFloat = 8
+    }
+}"#
+    );
+
+    let units = crate::parse_design_tokens(
+        r#"{
+            "gap": { "$value": "1.5rem" },
+            "full": { "$value": "100vh" },
+            "fast": { "$value": "150ms", "$type": "duration" }
+        }"#,
+        Some("units.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        units[0].to_swift(),
+        "public enum Units {\n    public static let gap: CGFloat = 24\n    // full = 100vh has no Swift equivalent\n    public static let fast: TimeInterval = 0.15\n}"
+    );
+
+    let names = crate::parse_design_tokens(
+        r#"{ "a%b": { "$value": 1 }, "size2": { "$value": 2 }, "Size 2": { "$value": 3 } }"#,
+        Some("names.json"),
+    )
+    .unwrap();
+    assert_eq!(
+        names[0].to_swift(),
+        "public enum Names {\n    public static let a_b: CGFloat = 1\n    public static let size2: CGFloat = 2\n    public static let size2_2: CGFloat = 3\n}"
+    );
+}
@@ -0,0 +1,115 @@
+use handlebars::Handlebars;
+use itertools::Itertools;
+use serde_json::{json, Value as Json};
+
+use super::{Emitter, ResolvedTokens};
+use crate::{resolve_single, CssOptions, TokenOrGroup, TokenValue};
+
+pub use handlebars::TemplateError;
+
+/// The data templates render against:
+///
+/// ```json
+/// { "sets": [{ "name": "light", "tokens": [{
+///     "path": ["Border", "M"], "name": "Border/M", "type": "number",
+///     "raw": "4", "value": "4", "css": "--border-m", "rust": "BORDER_M"
+/// }] }] }
+/// ```
+///
+/// Composite tokens have objects keyed by field for `raw` and `value`.
+pub fn context(tokens: &ResolvedTokens) -> Json {
+    let sets = tokens
+        .sets
+        .iter()
+        .map(|set| {
+            let tokens = set
+                .tokens()
+                .into_iter()
+                .zip(set.rust_names())
+                .filter_map(|((path, token), rust)| {
+                    let TokenOrGroup::Token {
+                        value,
+                        type_,
+                        extensions,
+                        ..
+                    } = token
+                    else {
+                        return None;
+                    };
+                    let (raw, value) = match value {
+                        TokenValue::Single(expr) => (
+                            json!(expr.to_css()),
+                            json!(resolve_single(set, expr, extensions).to_css()),
+                        ),
+                        TokenValue::Dict(dict) => {
+                            let dict = dict.iter();
+                            (
+                                dict.clone()
+                                    .map(|(k, v)| (k.clone(), json!(v.to_css())))
+                                    .collect(),
+                                dict.map(|(k, v)| (k.clone(), json!(v.get_value(set).to_css())))
+                                    .collect(),
+                            )
+                        }
+                    };
+                    Some(json!({
+                        "path": &path[..],
+                        "name": path.join("/"),
+                        "type": type_.as_str(),
+                        "raw": raw,
+                        "value": value,
+                        "css": CssOptions::default().property_name(&set.options.naming, &path),
+                        "rust": rust,
+                    }))
+                })
+                .collect_vec();
+            json!({ "name": set.get_name(), "tokens": tokens })
+        })
+        .collect_vec();
+    json!({ "sets": sets })
+}
+
+/// Renders a user-supplied Handlebars template against [`context`].
+pub struct TemplateEmitter {
+    name: String,
+    registry: Handlebars<'static>,
+}
+impl TemplateEmitter {
+    pub fn new(name: &str, template: &str) -> Result<Self, TemplateError> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        registry.register_template_string(name, template)?;
+        Ok(Self {
+            name: name.to_string(),
+            registry,
+        })
+    }
+}
+impl Emitter for TemplateEmitter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn emit(&self, tokens: &ResolvedTokens) -> String {
+        self.registry
+            .render(&self.name, &context(tokens))
+            .unwrap_or_else(|err| panic!("Failed to render template {}: {}", self.name, err))
+    }
+}
+
+#[test]
+fn test() {
+    let data = crate::parse_design_tokens(
+        r##"{ "Brand": { "Primary": { "$type": "color", "$value": "#ff0000" } } }"##,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    let emitter = TemplateEmitter::new(
+        "ini",
+        "{{#each sets}}[{{name}}]\n{{#each tokens}}{{rust}}={{value}}\n{{/each}}{{/each}}",
+    )
+    .unwrap();
+    assert_eq!(
+        emitter.emit(&ResolvedTokens::new(&data)),
+        "[tokens]\nBRAND_PRIMARY=#ff0000\n"
+    );
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use convert_case::{Case, Casing};
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::{rust_ident, DesignTokens, RustOptions};
+
+#[derive(Debug, Clone, PartialEq)]
+struct ThemeField {
+    name: String,
+    constant: String,
+    type_: String,
+}
+
+impl DesignTokens {
+    fn theme_fields(&self, options: &RustOptions) -> Vec<ThemeField> {
+        self.tokens()
+            .into_iter()
+            .zip(self.rust_names())
+            .filter(|((_, token), _)| !self.unresolvable(token))
+            .map(|((_, token), constant)| ThemeField {
+                name: rust_ident(&constant.to_lowercase()),
+                type_: token.rust_value(self, options).0.replace('&', "&'static "),
+                constant,
+            })
+            .collect()
+    }
+    /// A `Theme` struct with a field per token, defaulting to the constants, and a setter per
+    /// field for building variants at runtime.
+    pub(crate) fn rust_theme(&self, options: &RustOptions) -> String {
+        let fields = self.theme_fields(options);
+        theme_struct("Theme", &fields, &theme_literal("Self", &fields))
+    }
+}
+
+fn theme_literal(type_: &str, fields: &[ThemeField]) -> String {
+    format!(
+        "{type_} {{ {} }}",
+        fields
+            .iter()
+            .map(|x| format!("{}: {}", x.name, x.constant))
+            .join(", ")
+    )
+}
+
+fn theme_struct(name: &str, fields: &[ThemeField], default: &str) -> String {
+    let mut lines = vec![
+        "/// The tokens as values that can be changed at runtime.".to_string(),
+        "#[derive(Debug, Clone, PartialEq)]".to_string(),
+        format!("pub struct {name} {{"),
+    ];
+    lines.extend(
+        fields
+            .iter()
+            .map(|x| format!("    pub {}: {},", x.name, x.type_)),
+    );
+    lines.extend([
+        "}".to_string(),
+        "#[allow(deprecated)]".to_string(),
+        format!("impl Default for {name} {{"),
+        "    fn default() -> Self {".to_string(),
+        format!("        {default}"),
+        "    }".to_string(),
+        "}".to_string(),
+        format!("impl {name} {{"),
+    ]);
+    for x in fields {
+        lines.extend([
+            format!(
+                "    pub fn {}(mut self, value: {}) -> Self {{",
+                x.name, x.type_
+            ),
+            format!("        self.{} = value;", x.name),
+            "        self".to_string(),
+            "    }".to_string(),
+        ]);
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// Themes shared by the modes of a Figma collection, as generated by [`mode_themes`].
+#[derive(Debug, Default)]
+pub(crate) struct ModeThemes {
+    /// Top-level structs and selector functions.
+    pub items: Vec<String>,
+    /// The struct used by each set that is a mode, by index. These sets get a `THEME` constant
+    /// instead of a struct of their own.
+    pub structs: HashMap<usize, String>,
+}
+
+/// Finds collections with several modes, e.g. `Ambient.light.tokens.json` and
+/// `Ambient.dark.tokens.json`, whose modes have the same tokens. Each gets one `Theme` struct
+/// and a `fn theme(mode: &str) -> Option<&'static Theme>` selector; when several collections
+/// do, they are named after the collection, e.g. `AmbientTheme` and `ambient_theme`.
+pub(crate) fn mode_themes(sets: &[DesignTokens], options: &RustOptions) -> ModeThemes {
+    let mut collections: IndexMap<&str, Vec<usize>> = IndexMap::new();
+    for (i, set) in sets.iter().enumerate() {
+        let parts = set
+            .file_name
+            .as_deref()
+            .unwrap_or_default()
+            .split('.')
+            .collect_vec();
+        if parts.len() > 2 {
+            collections.entry(parts[0]).or_default().push(i);
+        }
+    }
+    let shared = collections
+        .into_iter()
+        .filter(|(_, modes)| modes.len() > 1)
+        .filter_map(|(name, modes)| {
+            let fields = modes
+                .iter()
+                .map(|&i| sets[i].theme_fields(options))
+                .dedup()
+                .exactly_one()
+                .ok()?;
+            Some((name, modes, fields))
+        })
+        .collect_vec();
+    let mut out = ModeThemes::default();
+    let single = shared.len() == 1;
+    for (name, modes, mut fields) in shared {
+        let (struct_name, fn_name) = match single {
+            true => ("Theme".to_string(), "theme".to_string()),
+            false => (
+                rust_ident(&format!("{}Theme", name.to_case(Case::Pascal))),
+                rust_ident(&format!("{}_theme", name.to_case(Case::Snake))),
+            ),
+        };
+        for x in &mut fields {
+            x.type_ = x.type_.replace("super::", "");
+        }
+        let module = |i: usize| sets[i].get_name_rust();
+        out.items.push(theme_struct(
+            &struct_name,
+            &fields,
+            &format!("{}::THEME", module(modes[0])),
+        ));
+        let arms = modes
+            .iter()
+            .map(|&i| {
+                format!(
+                    "        {:?} => Some(&{}::THEME),",
+                    sets[i].get_name(),
+                    module(i)
+                )
+            })
+            .join("\n");
+        out.items.push(format!(
+            "/// The `{name}` theme of each mode.\npub fn {fn_name}(mode: &str) -> Option<&'static {struct_name}> {{\n    match mode {{\n{arms}\n        _ => None,\n    }}\n}}"
+        ));
+        out.structs
+            .extend(modes.into_iter().map(|i| (i, struct_name.clone())));
+    }
+    out
+}
+
+impl DesignTokens {
+    /// `THEME`, the values of this mode as the shared `struct_name`.
+    pub(crate) fn rust_theme_const(&self, options: &RustOptions, struct_name: &str) -> String {
+        let type_ = format!("super::{struct_name}");
+        format!(
+            "#[allow(deprecated)]\npub const THEME: {type_} = {};",
+            theme_literal(&type_, &self.theme_fields(options))
+        )
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#ff0000" } },
+            "type": { "$value": "4px" }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let options = RustOptions {
+        theme: true,
+        ..Default::default()
+    };
+    assert!(tokens[0].to_rust_with(&options).ends_with(
+        "\
+/// The tokens as values that can be changed at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub brand_primary: &'static str,
+    pub r#type: f32,
+}
+#[allow(deprecated)]
+impl Default for Theme {
+    fn default() -> Self {
+        Self { brand_primary: BRAND_PRIMARY, r#type: TYPE }
+    }
+}
+impl Theme {
+    pub fn brand_primary(mut self, value: &'static str) -> Self {
+        self.brand_primary = value;
+        self
+    }
+    pub fn r#type(mut self, value: f32) -> Self {
+        self.r#type = value;
+        self
+    }
+}"
+    ));
+}
+
+#[test]
+fn test_modes() {
+    let tokens = crate::parse_design_tokens(
+        r##"[
+            { "fileName": "App.light.tokens.json", "body": { "bg": { "$value": "#ffffff" } } },
+            { "fileName": "App.dark.tokens.json", "body": { "bg": { "$value": "#000000" } } },
+            { "fileName": "Other.tokens.json", "body": { "gap": { "$value": "4px" } } }
+        ]"##,
+        None,
+    )
+    .unwrap();
+    let options = RustOptions {
+        theme: true,
+        ..Default::default()
+    };
+    let themes = mode_themes(&tokens, &options);
+    assert_eq!(themes.structs.len(), 2);
+    assert_eq!(themes.items[0].lines().nth(2), Some("pub struct Theme {"));
+    assert_eq!(
+        themes.items[1],
+        "\
+/// The `App` theme of each mode.
+pub fn theme(mode: &str) -> Option<&'static Theme> {
+    match mode {
+        \"light\" => Some(&LIGHT::THEME),
+        \"dark\" => Some(&DARK::THEME),
+        _ => None,
+    }
+}"
+    );
+    assert_eq!(
+        tokens[1].rust_theme_const(&options, "Theme"),
+        "#[allow(deprecated)]\npub const THEME: super::Theme = super::Theme { bg: BG };"
+    );
+}
@@ -0,0 +1,121 @@
+use itertools::Itertools;
+
+use crate::{
+    css_entry,
+    expression::{NumberType, Value},
+    resolve_single, CssOptions, DesignTokens, TokenOrGroup, TokenType, TokenValue,
+};
+
+/// Utilities generated for color tokens, as `{utility}` name and property.
+const COLOR_UTILITIES: &[(&str, &str)] = &[
+    ("bg", "background-color"),
+    ("text", "color"),
+    ("border", "border-color"),
+];
+/// Utilities generated for lengths.
+const SPACE_UTILITIES: &[(&str, &str)] = &[("p", "padding"), ("m", "margin"), ("gap", "gap")];
+
+/// Utility classes applying tokens to elements, e.g. `.bg-brand-primary` for a color or
+/// `.p-space-4` for a length, so markup can use tokens without a CSS framework.
+///
+/// `class_name` is a template with `{utility}` and `{name}` placeholders, e.g.
+/// `u-{utility}-{name}`. Single-valued tokens are applied through their custom properties, so
+/// the CSS output has to be loaded as well; typography tokens become a `text` class setting
+/// every field.
+pub fn to_utilities(tokens: &[DesignTokens], class_name: &str, options: &CssOptions) -> String {
+    let class = |set: &DesignTokens, utility: &str, path: &[String]| {
+        let unprefixed = CssOptions {
+            prefix: None,
+            ..options.clone()
+        };
+        let name = unprefixed.property_name(&set.options.naming, path);
+        class_name
+            .replace("{utility}", utility)
+            .replace("{name}", name.trim_start_matches('-'))
+    };
+    tokens
+        .iter()
+        .flat_map(|set| {
+            set.iter()
+                .filter(|(_, token)| set.missing_ref(token).is_none())
+                .flat_map(|(path, token)| {
+                    let TokenOrGroup::Token {
+                        value,
+                        type_,
+                        extensions,
+                        ..
+                    } = token
+                    else {
+                        return Vec::new();
+                    };
+                    let utilities = match value {
+                        TokenValue::Dict(dict) if *type_ == TokenType::Typography => {
+                            return vec![format!(
+                                ".{} {{ {} }}",
+                                class(set, "text", &path),
+                                dict.iter()
+                                    .map(|(key, value)| css_entry(set, options, type_, key, value))
+                                    .join(" ")
+                            )];
+                        }
+                        TokenValue::Dict(_) => return Vec::new(),
+                        TokenValue::Single(expr) => match resolve_single(set, expr, extensions) {
+                            Value::Color(_) => COLOR_UTILITIES,
+                            Value::Number(
+                                _,
+                                NumberType::Pixels
+                                | NumberType::Rem
+                                | NumberType::Em
+                                | NumberType::Percentage,
+                            ) => SPACE_UTILITIES,
+                            _ => return Vec::new(),
+                        },
+                    };
+                    let property = options.property_name(&set.options.naming, &path);
+                    utilities
+                        .iter()
+                        .map(|(utility, css)| {
+                            format!(
+                                ".{} {{ {css}: var({property}); }}",
+                                class(set, utility, &path)
+                            )
+                        })
+                        .collect()
+                })
+                .collect_vec()
+        })
+        .unique()
+        .join("\n")
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#e11d48" } },
+            "Space": { "4": { "$value": "16px" } },
+            "Weight": { "$value": 600 },
+            "Heading 1": { "$type": "typography", "$value": { "fontFamily": "Inter", "fontSize": 32, "fontWeight": "{Weight}" } }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let css = to_utilities(&tokens, "{utility}-{name}", &CssOptions::default());
+    assert_eq!(
+        css,
+        "\
+.bg-brand-primary { background-color: var(--brand-primary); }
+.text-brand-primary { color: var(--brand-primary); }
+.border-brand-primary { border-color: var(--brand-primary); }
+.p-space-4 { padding: var(--space-4); }
+.m-space-4 { margin: var(--space-4); }
+.gap-space-4 { gap: var(--space-4); }
+.text-heading-1 { font-family: Inter; font-size: 32px; font-weight: var(--weight); }"
+    );
+    let options = CssOptions {
+        prefix: Some("ds".to_string()),
+        ..Default::default()
+    };
+    assert!(to_utilities(&tokens, "u-{utility}-{name}", &options)
+        .contains(".u-p-space-4 { padding: var(--ds-space-4); }"));
+}
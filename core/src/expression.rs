@@ -3,36 +3,149 @@ use std::fmt;
 use csscolorparser::Color;
 use itertools::Itertools;
 use serde::{
-    de::{self, Visitor},
-    Deserialize, Deserializer,
+    de::{self, DeserializeSeed, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::{slugify_css, DesignTokens};
+use crate::{
+    emit::{dimension::rust_unit, fmt_float},
+    extensions::to_oklch,
+    naming::Naming,
+    CssOptions, CssReferences, DesignTokens, ResolutionMode, ResolveStrategy, RustColor,
+    RustOptions, TokenValue,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum NumberType {
     None,
     Pixels,
     Percentage,
+    Rem,
+    Em,
+    Vh,
+    Vw,
+    Seconds,
+    Milliseconds,
 }
 impl NumberType {
-    fn to_css(&self, value: f32) -> String {
+    pub fn unit(&self) -> &'static str {
+        match self {
+            NumberType::None => "",
+            NumberType::Pixels => "px",
+            NumberType::Percentage => "%",
+            NumberType::Rem => "rem",
+            NumberType::Em => "em",
+            NumberType::Vh => "vh",
+            NumberType::Vw => "vw",
+            NumberType::Seconds => "s",
+            NumberType::Milliseconds => "ms",
+        }
+    }
+    fn to_css(&self, value: f64) -> String {
+        format!("{}{}", value, self.unit())
+    }
+    /// The unit of `a op b`: unitless operands take on the unit of the other side.
+    fn combine(a: &NumberType, b: &NumberType) -> NumberType {
+        match a {
+            NumberType::None => b.clone(),
+            a => a.clone(),
+        }
+    }
+    fn describe(&self) -> &'static str {
         match self {
-            NumberType::None => format!("{}", value),
-            NumberType::Pixels => format!("{}px", value),
-            NumberType::Percentage => format!("{}%", value),
+            NumberType::None => "a number",
+            x => x.unit(),
         }
     }
-    fn to_rust(&self, value: f32) -> String {
+    fn to_rust(&self, value: f64) -> String {
         let value = match self {
-            NumberType::Percentage => value * 0.01,
+            // Dividing keeps e.g. 29% at 0.29, where multiplying by 0.01 picks up a rounding error.
+            NumberType::Percentage => value / 100.,
             _ => value,
         };
-        let x = format!("{}", value);
-        if x.contains(".") {
-            x
+        // The constants are generated as f32, so they're written at its precision.
+        rust_float(value as f32)
+    }
+}
+
+/// A float literal with the shortest digits that round-trip, e.g. `232.83` or `4.`.
+fn rust_float(value: impl fmt::Display) -> String {
+    let x = format!("{}", value);
+    if x.contains(".") {
+        x
+    } else {
+        format!("{}.", x)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorFormat {
+    /// `#rrggbb`, or `#rrggbbaa` for translucent colors.
+    #[default]
+    Hex,
+    /// `#rrggbb`, or `rgba(r, g, b, a)` for translucent colors, for targets that don't accept
+    /// 8-digit hex.
+    HexRgba,
+    Rgb,
+    Hsl,
+    Oklch,
+}
+impl std::str::FromStr for ColorFormat {
+    type Err = String;
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        Ok(match x {
+            "hex" => ColorFormat::Hex,
+            "hex-rgba" => ColorFormat::HexRgba,
+            "rgb" => ColorFormat::Rgb,
+            "hsl" => ColorFormat::Hsl,
+            "oklch" => ColorFormat::Oklch,
+            _ => return Err(format!("unknown color format {x:?}")),
+        })
+    }
+}
+impl ColorFormat {
+    pub fn format(&self, color: &Color) -> String {
+        let alpha = if color.a < 1. {
+            format!(" / {}", fmt_float(color.a))
         } else {
-            format!("{}.", x)
+            String::new()
+        };
+        match self {
+            ColorFormat::Hex => color.to_hex_string(),
+            ColorFormat::HexRgba if color.a < 1. => {
+                let [r, g, b, _] = color.to_rgba8();
+                format!("rgba({r}, {g}, {b}, {})", fmt_float(color.a))
+            }
+            ColorFormat::HexRgba => color.to_hex_string(),
+            ColorFormat::Rgb => {
+                let [r, g, b, _] = color.to_rgba8();
+                format!("rgb({r} {g} {b}{alpha})")
+            }
+            ColorFormat::Hsl => {
+                let (h, s, l, _) = color.to_hsla();
+                let h = if h.is_nan() { 0. } else { h };
+                format!(
+                    "hsl({} {}% {}%{alpha})",
+                    fmt_float((h * 100.).round() / 100.),
+                    fmt_float((s * 10000.).round() / 100.),
+                    fmt_float((l * 10000.).round() / 100.)
+                )
+            }
+            ColorFormat::Oklch => {
+                let (l, c, h, _) = to_oklch(color);
+                // Greys carry a little float noise in chroma, which makes the hue meaningless.
+                let (c, h) = if c < 5e-4 {
+                    (0., 0.)
+                } else {
+                    (c, h.to_degrees().rem_euclid(360.))
+                };
+                format!(
+                    "oklch({}% {} {}{alpha})",
+                    fmt_float((l * 10000.).round() / 100.),
+                    fmt_float(c),
+                    fmt_float((h * 100.).round() / 100.)
+                )
+            }
         }
     }
 }
@@ -40,107 +153,580 @@ impl NumberType {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Color(Color),
-    Number(f32, NumberType),
+    Number(f64, NumberType),
+    /// Control points of a `cubic-bezier()` timing function.
+    CubicBezier([f64; 4]),
+    /// A JSON boolean, or `"true"` or `"false"` in a `boolean` token.
+    Bool(bool),
     Any(String),
 }
 impl Value {
     pub fn to_css(&self) -> String {
-        match self {
-            Value::Color(val) => val.to_hex_string(),
-            Value::Number(val, typ) => typ.to_css(*val),
-            Value::Any(val) => val.to_string(),
+        self.to_css_with(&CssOptions::default())
+    }
+    pub fn to_css_with(&self, options: &CssOptions) -> String {
+        match (self, options.rem_base) {
+            (Value::Color(val), _) => options.color_format.format(val),
+            (Value::Number(val, NumberType::Pixels), Some(base)) => format!("{}rem", val / base),
+            (Value::Number(val, typ), _) => typ.to_css(*val),
+            (Value::CubicBezier(points), _) => {
+                format!("cubic-bezier({})", points.iter().join(", "))
+            }
+            (Value::Bool(val), _) => val.to_string(),
+            (Value::Any(val), _) => val.to_string(),
         }
     }
     pub fn to_rust(&self) -> String {
+        self.to_rust_with(&RustOptions::default())
+    }
+    pub fn to_rust_with(&self, options: &RustOptions) -> String {
         match self {
-            Value::Color(val) => format!("\"{}\"", val.to_hex_string()),
-            Value::Number(val, typ) => typ.to_rust(*val),
-            Value::Any(val) => format!("\"{}\"", val.to_string()),
+            Value::Color(val) => {
+                let linear = || {
+                    [val.r, val.g, val.b]
+                        .map(srgb_to_linear)
+                        .into_iter()
+                        .chain([val.a])
+                        // The channels are generated as f32, so they're written at its precision.
+                        .map(|x| rust_float(x as f32))
+                        .join(", ")
+                };
+                match options.color {
+                    RustColor::Hex => format!("\"{}\"", val.to_hex_string()),
+                    RustColor::LinearRgba => format!("[{}]", linear()),
+                    RustColor::GlamVec4 => format!("glam::Vec4::new({})", linear()),
+                }
+            }
+            Value::Number(val, typ @ (NumberType::Seconds | NumberType::Milliseconds)) => {
+                let micros = (*val
+                    * if *typ == NumberType::Seconds {
+                        1e6
+                    } else {
+                        1e3
+                    })
+                .round() as u64;
+                if micros.is_multiple_of(1000) {
+                    format!("std::time::Duration::from_millis({})", micros / 1000)
+                } else {
+                    format!("std::time::Duration::from_micros({micros})")
+                }
+            }
+            Value::Number(val, typ) => match rust_unit(options, typ) {
+                Some(unit) => format!(
+                    "super::Dimension {{ value: {}, unit: super::Unit::{unit} }}",
+                    rust_float(val)
+                ),
+                None => typ.to_rust(*val),
+            },
+            Value::CubicBezier(points) => format!(
+                "[{}]",
+                points
+                    .iter()
+                    .map(|x| NumberType::None.to_rust(*x))
+                    .join(", ")
+            ),
+            Value::Bool(val) => val.to_string(),
+            Value::Any(val) => format!("{:?}", val),
         }
     }
     pub fn to_rust_type(&self) -> &'static str {
+        self.to_rust_type_with(&RustOptions::default())
+    }
+    pub fn to_rust_type_with(&self, options: &RustOptions) -> &'static str {
         match self {
+            Value::Color(_) => match options.color {
+                RustColor::Hex => "&str",
+                RustColor::LinearRgba => "[f32; 4]",
+                RustColor::GlamVec4 => "glam::Vec4",
+            },
+            Value::Number(_, NumberType::Seconds | NumberType::Milliseconds) => {
+                "std::time::Duration"
+            }
+            Value::Number(_, typ) if rust_unit(options, typ).is_some() => "super::Dimension",
             Value::Number(_, _) => "f32",
-            _ => "&'static str",
+            Value::CubicBezier(_) => "[f32; 4]",
+            Value::Bool(_) => "bool",
+            _ => "&str",
+        }
+    }
+    /// What kind of value this is, for errors, e.g. `a color` or `px`.
+    pub(crate) fn describe(&self) -> &'static str {
+        match self {
+            Value::Color(_) => "a color",
+            Value::Number(_, typ) => typ.describe(),
+            Value::CubicBezier(_) => "a cubic-bezier",
+            Value::Bool(_) => "a boolean",
+            Value::Any(_) => "text",
         }
     }
     pub fn to_rust_string(&self) -> String {
         match self {
-            Value::Number(_, _) => format!("\"{}\"", self.to_rust()),
+            Value::Number(_, NumberType::Seconds | NumberType::Milliseconds)
+            | Value::CubicBezier(_) => format!("\"{}\"", self.to_css()),
+            Value::Number(_, _) | Value::Bool(_) => format!("\"{}\"", self.to_rust()),
             _ => self.to_rust(),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Min,
+    Max,
+    Clamp,
+    Round,
+}
+impl Function {
+    /// The name it is called by, e.g. `clamp`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Clamp => "clamp",
+            Function::Round => "round",
+        }
+    }
+    fn apply(&self, args: Vec<Value>) -> Result<Value, String> {
+        let numbers = args
+            .iter()
+            .map(|x| match x {
+                Value::Number(v, _) => Ok(*v),
+                _ => Err(format!(
+                    "{}() expects numbers, got {}",
+                    self.name(),
+                    x.describe()
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let typ = args.iter().fold(NumberType::None, |typ, x| match x {
+            Value::Number(_, x) => NumberType::combine(&typ, x),
+            _ => unreachable!(),
+        });
+        let value = match (self, numbers.as_slice()) {
+            (Function::Min, [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.min(*b)),
+            (Function::Max, [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.max(*b)),
+            (Function::Clamp, [min, pref, max]) => pref.max(*min).min(*max),
+            (Function::Round, [x]) => x.round(),
+            _ => {
+                return Err(format!(
+                    "{}() does not take {} arguments",
+                    self.name(),
+                    numbers.len()
+                ))
+            }
+        };
+        Ok(Value::Number(value, typ))
+    }
+}
+
+/// A parsed token value, see [`parse_expression`].
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
+    /// A reference to another token by path, e.g. `{Brand.Primary}`.
     Ref(Vec<String>),
     Mul(Box<Expression>, Box<Expression>),
     Div(Box<Expression>, Box<Expression>),
+    /// E.g. `clamp({Space.s}, 2vw, {Space.l})`.
+    Call(Function, Vec<Expression>),
+    /// A string with embedded references, e.g. `{font.main}, sans-serif`.
+    Concat(Vec<Expression>),
+    /// A JSON array, written space-separated in CSS.
+    List(Vec<Expression>),
     Value(Value),
 }
 impl Expression {
-    pub fn to_css(&self, tokens: &DesignTokens) -> String {
+    pub fn to_css(&self) -> String {
+        self.to_css_with(&CssOptions::default(), &Naming::default())
+    }
+    pub fn to_css_with(&self, options: &CssOptions, naming: &Naming) -> String {
+        self.css(options, naming, None)
+    }
+    /// Like [`Expression::to_css_with`], but follows [`CssOptions::resolve`] and
+    /// [`CssOptions::references`], resolving references against `tokens`.
+    pub(crate) fn to_css_in(&self, tokens: &DesignTokens, options: &CssOptions) -> String {
+        if options.resolve == ResolveStrategy::Inline
+            && tokens.missing_in(self, &mut Vec::new()).is_none()
+        {
+            return self.get_value(tokens).to_css_with(options);
+        }
+        self.css(options, &tokens.options.naming, Some(tokens))
+    }
+    fn css(&self, options: &CssOptions, naming: &Naming, tokens: Option<&DesignTokens>) -> String {
         match self {
             Expression::Ref(path) => {
-                format!("var(--{})", path.iter().map(|x| slugify_css(x)).join("-"))
+                let property = options.property_name(naming, path);
+                let resolved = tokens
+                    .filter(|_| options.references != CssReferences::Var)
+                    .filter(|tokens| tokens.missing_in(self, &mut Vec::new()).is_none())
+                    .and_then(|tokens| tokens.resolve(path));
+                match (options.references, resolved) {
+                    (CssReferences::Inline, Some(value)) => value.to_css_with(options),
+                    (CssReferences::Fallback, Some(value)) => {
+                        format!("var({property}, {})", value.to_css_with(options))
+                    }
+                    _ => format!("var({property})"),
+                }
+            }
+            Expression::Mul(a, b) => format!(
+                "calc({} * {})",
+                a.css(options, naming, tokens),
+                b.css(options, naming, tokens)
+            ),
+            Expression::Div(a, b) => format!(
+                "calc({} / {})",
+                a.css(options, naming, tokens),
+                b.css(options, naming, tokens)
+            ),
+            Expression::Call(f, args) => {
+                let mut css = args
+                    .iter()
+                    .map(|x| x.css(options, naming, tokens))
+                    .collect_vec();
+                // CSS only defaults the rounding interval for plain numbers.
+                if let (Function::Round, [x]) = (f, args.as_slice()) {
+                    if let Some(typ) = x.known_unit(tokens).filter(|x| *x != NumberType::None) {
+                        css.push(Value::Number(1., typ).to_css_with(options));
+                    }
+                }
+                format!("{}({})", f.name(), css.join(", "))
             }
-            Expression::Mul(a, b) => format!("calc({} * {})", a.to_css(tokens), b.to_css(tokens)),
-            Expression::Div(a, b) => format!("calc({} / {})", a.to_css(tokens), b.to_css(tokens)),
-            Expression::Value(val) => val.to_css(),
+            Expression::Concat(parts) => parts
+                .iter()
+                .map(|x| x.css(options, naming, tokens))
+                .join(""),
+            Expression::List(items) => items
+                .iter()
+                .map(|x| x.css(options, naming, tokens))
+                .join(" "),
+            Expression::Value(val) => val.to_css_with(options),
         }
     }
-    pub fn get_value(&self, tokens: &DesignTokens) -> Value {
+    /// The unit of a number, if it can be told without following missing references.
+    fn known_unit(&self, tokens: Option<&DesignTokens>) -> Option<NumberType> {
+        let value = match (self, tokens) {
+            (Expression::Value(value), _) => value.clone(),
+            (_, Some(tokens)) if tokens.missing_in(self, &mut Vec::new()).is_none() => {
+                self.try_get_value(tokens).ok()?
+            }
+            _ => return None,
+        };
+        match value {
+            Value::Number(_, typ) => Some(typ),
+            _ => None,
+        }
+    }
+    /// The expression in the syntax it is parsed from, e.g. `{a.b} * 2`.
+    pub fn to_source(&self) -> String {
         match self {
-            Expression::Ref(path) => {
-                // let path = path.iter().map(|s| slugify_css(s)).collect_vec();
-                tokens
-                    .get_value(&path)
-                    .expect(&format!("No such path: {:?}", path))
-                    .get_value(tokens)
+            Expression::Ref(path) => format!("{{{}}}", path.join(".")),
+            Expression::Mul(a, b) => format!("{} * {}", a.to_source(), b.to_source()),
+            Expression::Div(a, b) => format!("{} / {}", a.to_source(), b.to_source()),
+            Expression::Call(f, args) => {
+                format!(
+                    "{}({})",
+                    f.name(),
+                    args.iter().map(|x| x.to_source()).join(", ")
+                )
             }
-            Expression::Mul(a, b) => match (a.get_value(tokens), b.get_value(tokens)) {
-                (Value::Color(a), Value::Color(b)) => Value::Color(Color {
-                    r: a.r * b.r,
-                    g: a.g * b.g,
-                    b: a.b * b.b,
-                    a: a.a * b.a,
-                }),
-                (Value::Number(a, typ), Value::Number(b, _)) => Value::Number(a * b, typ),
-                (a, b) => todo!("Not handled: {:?} {:?}", a, b),
-            },
-            Expression::Div(a, b) => match (a.get_value(tokens), b.get_value(tokens)) {
-                (Value::Color(a), Value::Color(b)) => Value::Color(Color {
-                    r: a.r / b.r,
-                    g: a.g / b.g,
-                    b: a.b / b.b,
-                    a: a.a / b.a,
-                }),
-                (Value::Number(a, typ), Value::Number(b, _)) => Value::Number(a / b, typ),
-                _ => todo!(),
+            Expression::Value(Value::Color(color)) => color.to_hex_string(),
+            Expression::Concat(parts) => parts.iter().map(|x| x.to_source()).join(""),
+            Expression::List(items) => {
+                format!("[{}]", items.iter().map(|x| x.to_source()).join(", "))
+            }
+            Expression::Value(value) => value.to_css(),
+        }
+    }
+    pub fn refs(&self) -> Vec<&[String]> {
+        match self {
+            Expression::Ref(path) => vec![path.as_slice()],
+            Expression::Mul(a, b) | Expression::Div(a, b) => {
+                a.refs().into_iter().chain(b.refs()).collect()
+            }
+            Expression::Call(_, args) | Expression::Concat(args) | Expression::List(args) => {
+                args.iter().flat_map(|x| x.refs()).collect()
+            }
+            Expression::Value(_) => Vec::new(),
+        }
+    }
+    /// Panics where [`Expression::try_get_value`] fails.
+    pub fn get_value(&self, tokens: &DesignTokens) -> Value {
+        self.try_get_value(tokens)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Resolves the expression, failing on paths that don't exist or name a composite token,
+    /// operands of the wrong kind and, in [`ResolutionMode::Strict`], units that don't combine.
+    /// References have to be free of cycles, see [`DesignTokens::missing_ref`].
+    pub(crate) fn try_get_value(&self, tokens: &DesignTokens) -> Result<Value, String> {
+        Ok(match self {
+            Expression::Ref(path) => match tokens.get_value(path) {
+                Some(TokenValue::Single(expr)) => expr.try_get_value(tokens)?,
+                Some(TokenValue::Dict(_)) => {
+                    return Err(format!(
+                        "{{{}}} is a composite token, reference one of its fields",
+                        path.join(".")
+                    ))
+                }
+                None => tokens
+                    .get_field(path)
+                    .ok_or_else(|| format!("No such path: {:?}", path))?
+                    .try_get_value(tokens)?,
             },
+            Expression::Mul(a, b) | Expression::Div(a, b) => {
+                let op = |a: f64, b: f64| match self {
+                    Expression::Div(..) => a / b,
+                    _ => a * b,
+                };
+                match (a.try_get_value(tokens)?, b.try_get_value(tokens)?) {
+                    (Value::Color(a), Value::Color(b)) => {
+                        Value::Color(color_math(tokens, &a, &b, op))
+                    }
+                    (Value::Number(a, x), Value::Number(b, y)) => {
+                        Value::Number(op(a, b), self.number_unit(tokens, &x, &y)?)
+                    }
+                    (a, b) => return Err(self.arithmetic_error(a.describe(), b.describe())),
+                }
+            }
+            Expression::Call(f, args) => {
+                let args = args
+                    .iter()
+                    .map(|x| x.try_get_value(tokens))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if tokens.options.mode == ResolutionMode::Strict {
+                    if let Some(err) = self.mixed_units(&args) {
+                        return Err(err);
+                    }
+                }
+                f.apply(args)?
+            }
+            Expression::List(items) => Value::Any(
+                items
+                    .iter()
+                    .map(|x| Ok(x.try_get_value(tokens)?.to_css()))
+                    .collect::<Result<Vec<_>, String>>()?
+                    .join(" "),
+            ),
+            Expression::Concat(parts) => Value::Any(
+                parts
+                    .iter()
+                    .map(|x| {
+                        Ok(match x.try_get_value(tokens)? {
+                            Value::Any(x) => x,
+                            value => value.to_css(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+                    .join(""),
+            ),
             Expression::Value(value) => value.clone(),
+        })
+    }
+    /// The unit of a product or quotient: numbers scale a unit, and equal units cancel out
+    /// when divided. Anything else, e.g. `px * px` or `% / px`, has no CSS unit.
+    fn arithmetic_unit(&self, x: &NumberType, y: &NumberType) -> Result<NumberType, String> {
+        match (self, x, y) {
+            (Expression::Mul(..), NumberType::None, y) => Ok(y.clone()),
+            (_, x, NumberType::None) => Ok(x.clone()),
+            (Expression::Div(..), x, y) if x == y => Ok(NumberType::None),
+            _ => Err(self.arithmetic_error(x.describe(), y.describe())),
+        }
+    }
+    fn arithmetic_error(&self, x: &str, y: &str) -> String {
+        format!(
+            "`{}`: cannot {} {x} by {y}",
+            self.to_source(),
+            match self {
+                Expression::Div(..) => "divide",
+                _ => "multiply",
+            },
+        )
+    }
+    /// An error if the arguments of a function call have different units, which can only be
+    /// compared once laid out, e.g. `px` and `vw`. Unitless arguments go with any unit.
+    fn mixed_units(&self, args: &[Value]) -> Option<String> {
+        let mut units = args.iter().filter_map(|x| match x {
+            Value::Number(_, typ) if *typ != NumberType::None => Some(typ),
+            _ => None,
+        });
+        let first = units.next()?;
+        let other = units.find(|x| *x != first)?;
+        Some(format!(
+            "`{}`: cannot compare {} with {}",
+            self.to_source(),
+            first.describe(),
+            other.describe()
+        ))
+    }
+    /// Fails on units that don't combine, unless resolving leniently, where the unit of the
+    /// first operand is kept.
+    fn number_unit(
+        &self,
+        tokens: &DesignTokens,
+        x: &NumberType,
+        y: &NumberType,
+    ) -> Result<NumberType, String> {
+        self.arithmetic_unit(x, y)
+            .or_else(|err| match tokens.options.mode {
+                ResolutionMode::Strict => Err(err),
+                ResolutionMode::Lenient => Ok(NumberType::combine(x, y)),
+            })
+    }
+    /// Every product, quotient or function call in the expression whose units don't combine.
+    pub(crate) fn unit_errors(&self, tokens: &DesignTokens) -> Vec<String> {
+        match self {
+            Expression::Mul(a, b) | Expression::Div(a, b) => {
+                let mut errors = a.unit_errors(tokens);
+                errors.extend(b.unit_errors(tokens));
+                if errors.is_empty() {
+                    if let (Ok(Value::Number(_, x)), Ok(Value::Number(_, y))) =
+                        (a.try_get_value(tokens), b.try_get_value(tokens))
+                    {
+                        errors.extend(self.arithmetic_unit(&x, &y).err());
+                    }
+                }
+                errors
+            }
+            Expression::Call(_, args) => {
+                let mut errors = args
+                    .iter()
+                    .flat_map(|x| x.unit_errors(tokens))
+                    .collect_vec();
+                if errors.is_empty() {
+                    if let Ok(values) = args
+                        .iter()
+                        .map(|x| x.try_get_value(tokens))
+                        .collect::<Result<Vec<_>, _>>()
+                    {
+                        errors.extend(self.mixed_units(&values));
+                    }
+                }
+                errors
+            }
+            Expression::Concat(args) | Expression::List(args) => {
+                args.iter().flat_map(|x| x.unit_errors(tokens)).collect()
+            }
+            Expression::Ref(_) | Expression::Value(_) => Vec::new(),
         }
     }
 }
 
+/// Applies `f` channel by channel, on linear light unless [`ColorMath::Srgb`] is configured.
+/// Alpha is always combined as is.
+fn color_math(tokens: &DesignTokens, a: &Color, b: &Color, f: impl Fn(f64, f64) -> f64) -> Color {
+    let channel = |x: f64, y: f64| match tokens.options.color_math {
+        ColorMath::Linear => linear_to_srgb(f(srgb_to_linear(x), srgb_to_linear(y))),
+        ColorMath::Srgb => f(x, y),
+    };
+    Color::new(
+        channel(a.r, b.r),
+        channel(a.g, b.g),
+        channel(a.b, b.b),
+        f(a.a, b.a),
+    )
+}
+
+/// How `*` and `/` combine two colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMath {
+    /// On linear light, which matches how colors blend physically.
+    #[default]
+    Linear,
+    /// On the gamma-encoded sRGB channels, as earlier versions did.
+    Srgb,
+}
+
+pub(crate) fn srgb_to_linear(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+pub(crate) fn linear_to_srgb(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Parses a token value such as `{Space.base} * 2` the way token files are read.
+pub fn parse_expression(source: &str) -> Result<Expression, String> {
+    expr_parser::expr(source).map_err(|err| {
+        format!(
+            "Invalid expression {source:?} at column {}: expected {}",
+            err.location.column, err.expected
+        )
+    })
+}
+
 peg::parser! {
-  grammar expr_parser() for str {
+  pub(crate) grammar expr_parser() for str {
     rule _ = quiet!{[' ' | '\n' | '\t']*}
 
-    rule number() -> f32
-        = n:$("-"? ['0'..='9']+ "."? ['0'..='9']*) {? n.parse().or(Err("f32")) }
+    rule number() -> f64
+        = n:$("-"? ['0'..='9']+ "."? ['0'..='9']*) {? n.parse().or(Err("f64")) }
+
+    rule unit() -> NumberType
+        = "%" { NumberType::Percentage }
+        / "px" { NumberType::Pixels }
+        / "rem" { NumberType::Rem }
+        / "em" { NumberType::Em }
+        / "vh" { NumberType::Vh }
+        / "vw" { NumberType::Vw }
+        / "ms" { NumberType::Milliseconds }
+        / "s" { NumberType::Seconds }
+
+    rule cubic_bezier() -> Expression
+        = "cubic-bezier(" _ p:(number() ** (_ "," _)) _ ")" {? match p.as_slice() {
+            [a, b, c, d] => Ok(Expression::Value(Value::CubicBezier([*a, *b, *c, *d]))),
+            _ => Err("4 control points"),
+        } }
 
-    pub(crate) rule expr() -> Expression = precedence!{
+    rule color() -> Expression
+        = "#" v:$(['a'..='z' | 'A'..='Z' | '0'..='9']*) {? csscolorparser::parse(v).map(|x| Expression::Value(Value::Color(x))).or(Err("a hex color")) }
+
+    rule function() -> Function
+        = "min" { Function::Min }
+        / "max" { Function::Max }
+        / "clamp" { Function::Clamp }
+        / "round" { Function::Round }
+
+    rule reference() -> Expression
+        = "{" v:($((!"}" !"." [_])*) ** ".") "}" { Expression::Ref(v.iter().flat_map(|x| x.split('/').map(|x| x.to_string()).collect_vec()).collect()) }
+
+    rule literal() -> Expression
+        = v:$((!"{" [_])+) { Expression::Value(Value::Any(v.to_string())) }
+
+    /// Text around references; plain text without references stays a single `Any` value.
+    rule interpolation() -> Expression
+        = parts:(reference() / literal())+ {
+            match parts.as_slice() {
+                [Expression::Value(_)] => parts.into_iter().next().unwrap(),
+                _ => Expression::Concat(parts),
+            }
+        }
+
+    /// Values that fail to parse rather than being read as text: a lone `#` word is a hex
+    /// color, and functions take arguments.
+    rule malformed()
+        = "#" ['a'..='z' | 'A'..='Z' | '0'..='9']* ![_]
+        / function() _ "(" _ ")"
+
+    pub(crate) rule expr() -> Expression
+        = x:arith() ![_] { x }
+        / !malformed() x:interpolation() { x }
+
+    rule arith() -> Expression = precedence!{
         x:(@) _ "*" _ y:@ { Expression::Mul(Box::new(x), Box::new(y)) }
         x:(@) _ "/" _ y:@ { Expression::Div(Box::new(x), Box::new(y)) }
         --
-        "{" v:($((!"}" !"." [_])*) ** ".") "}" { Expression::Ref(v.iter().flat_map(|x| x.to_string().split("/").map(|x| x.to_string()).collect_vec()).collect()) }
-        "#" v:$(['a'..='z' | 'A'..='Z' | '0'..='9']*) { Expression::Value(Value::Color(csscolorparser::parse(v).unwrap())) }
-        v:number() "%" { Expression::Value(Value::Number(v, NumberType::Percentage)) }
-        v:number() "px" { Expression::Value(Value::Number(v, NumberType::Pixels)) }
+        f:function() _ "(" _ args:((!("," / ")") x:arith() { x }) ++ (_ "," _)) _ ")" { Expression::Call(f, args) }
+        x:reference() { x }
+        x:color() { x }
+        x:cubic_bezier() { x }
+        v:number() u:unit() { Expression::Value(Value::Number(v, u)) }
         v:number() { Expression::Value(Value::Number(v, NumberType::None)) }
-        v:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '#' | '%' | '-' | '.' | ' ']*) { Expression::Value(Value::Any(v.to_string())) }
+        v:$(!"#" ['a'..='z' | 'A'..='Z' | '0'..='9' | '#' | '%' | '-' | '.' | ' ']*) { Expression::Value(Value::Any(v.to_string())) }
     }
   }
 }
@@ -155,6 +741,11 @@ fn test() {
         expr_parser::expr("#ff00ff").unwrap(),
         Expression::Value(Value::Color(csscolorparser::parse("#ff00ff").unwrap()))
     );
+    assert!(expr_parser::expr("#zz").is_err());
+    assert_eq!(
+        expr_parser::expr("#1 in sales").unwrap(),
+        Expression::Value(Value::Any("#1 in sales".to_string()))
+    );
     assert_eq!(
         expr_parser::expr("90%").unwrap(),
         Expression::Value(Value::Number(90., NumberType::Percentage))
@@ -190,58 +781,117 @@ fn test() {
             Box::new(Expression::Value(Value::Number(5., NumberType::None))),
         )
     );
+    assert_eq!(
+        expr_parser::expr("clamp({min}, 2px, {max})").unwrap(),
+        Expression::Call(
+            Function::Clamp,
+            vec![
+                Expression::Ref(vec!["min".to_string()]),
+                Expression::Value(Value::Number(2., NumberType::Pixels)),
+                Expression::Ref(vec!["max".to_string()]),
+            ]
+        )
+    );
+    assert!(expr_parser::expr("min()").is_err());
+    let round = expr_parser::expr("round({x} * 1.5)").unwrap();
+    assert_eq!(round.to_css(), "round(calc(var(--x) * 1.5))");
+    let clamp = expr_parser::expr("clamp(4px, 12px * 2, 20px)").unwrap();
+    assert_eq!(clamp.to_css(), "clamp(4px, calc(12px * 2), 20px)");
+    assert_eq!(
+        Function::Clamp.apply(vec![
+            Value::Number(4., NumberType::Pixels),
+            Value::Number(24., NumberType::Pixels),
+            Value::Number(20., NumberType::Pixels),
+        ]),
+        Ok(Value::Number(20., NumberType::Pixels))
+    );
+    assert_eq!(
+        Function::Round.apply(vec![Value::Number(2.6, NumberType::None)]),
+        Ok(Value::Number(3., NumberType::None))
+    );
 }
 
-struct ExpressionVisitor;
+/// Deserializes an expression of the token at the given path, which prefixes parse errors.
+pub(crate) struct ExpressionVisitor<'a>(pub(crate) &'a [String]);
+
+impl<'de> DeserializeSeed<'de> for ExpressionVisitor<'_> {
+    type Value = Expression;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Expression, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
 
-impl<'de> Visitor<'de> for ExpressionVisitor {
+impl<'de> Visitor<'de> for ExpressionVisitor<'_> {
     type Value = Expression;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("an integer between -2^31 and 2^31")
+        formatter.write_str("an expression, number, boolean or list")
     }
 
     fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        match expr_parser::expr(value) {
-            Ok(expr) => Ok(expr),
-            Err(err) => Err(E::custom(format!("Invalid expression: {}", err))),
+        parse_expression(value).map_err(|err| E::custom(crate::de::located(self.0, err)))
+    }
+
+    /// A list such as a `dashArray`, or `[x1, y1, x2, y2]`, which `cubicBezier` tokens read as
+    /// [`Value::CubicBezier`].
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut items = Vec::new();
+        while let Some(x) = seq.next_element_seed(ExpressionVisitor(self.0))? {
+            items.push(x);
         }
+        Ok(Expression::List(items))
     }
 
-    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(Expression::Value(Value::Number(v as f32, NumberType::None)))
+        Ok(Expression::Value(Value::Bool(v)))
     }
 
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(Expression::Value(Value::Number(v as f32, NumberType::None)))
+        Ok(Expression::Value(Value::Number(v, NumberType::None)))
     }
 
     fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(Expression::Value(Value::Number(v as f32, NumberType::None)))
+        Ok(Expression::Value(Value::Number(v as f64, NumberType::None)))
     }
     fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(Expression::Value(Value::Number(v as f32, NumberType::None)))
+        Ok(Expression::Value(Value::Number(v as f64, NumberType::None)))
     }
     fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Ok(Expression::Value(Value::Number(v as f32, NumberType::None)))
+        Ok(Expression::Value(Value::Number(v as f64, NumberType::None)))
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Expression::Value(Value::Number(v, NumberType::None)) => serializer.serialize_f64(*v),
+            Expression::Value(Value::Bool(v)) => serializer.serialize_bool(*v),
+            Expression::Value(Value::CubicBezier(points)) => points.serialize(serializer),
+            Expression::List(items) => items.serialize(serializer),
+            _ => serializer.serialize_str(&self.to_source()),
+        }
     }
 }
 
@@ -250,13 +900,228 @@ impl<'de> Deserialize<'de> for Expression {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(ExpressionVisitor)
+        deserializer.deserialize_any(ExpressionVisitor(&[]))
     }
 }
 
+#[test]
+fn test_color_format() {
+    let color = csscolorparser::parse("#2871f980").unwrap();
+    assert_eq!(ColorFormat::Hex.format(&color), "#2871f980");
+    assert_eq!(ColorFormat::Rgb.format(&color), "rgb(40 113 249 / 0.502)");
+    assert_eq!(
+        ColorFormat::HexRgba.format(&color),
+        "rgba(40, 113, 249, 0.502)"
+    );
+    assert_eq!(
+        ColorFormat::HexRgba.format(&csscolorparser::parse("#2871f9").unwrap()),
+        "#2871f9"
+    );
+    assert_eq!(
+        ColorFormat::Hsl.format(&csscolorparser::parse("#ff0000").unwrap()),
+        "hsl(0 100% 50%)"
+    );
+    assert_eq!(
+        ColorFormat::Oklch.format(&csscolorparser::parse("#ffffff").unwrap()),
+        "oklch(100% 0 0)"
+    );
+}
+
+#[test]
+fn test_color_math() {
+    let json = r##"{
+        "grey": { "$value": "#808080" },
+        "tint": { "$value": "#ff8080" },
+        "multiplied": { "$value": "{grey} * {tint}" },
+        "divided": { "$value": "{grey} / {tint}" }
+    }"##;
+    let mut tokens = crate::parse_design_tokens(json, None).unwrap().remove(0);
+    let resolve =
+        |tokens: &DesignTokens, path: &str| tokens.resolve(&[path.to_string()]).unwrap().to_css();
+    assert_eq!(resolve(&tokens, "multiplied"), "#803d3d");
+    assert_eq!(resolve(&tokens, "divided"), "#80ffff");
+    tokens.options.color_math = ColorMath::Srgb;
+    assert_eq!(resolve(&tokens, "multiplied"), "#804040");
+}
+
 #[test]
 fn test_expr() {
     let _expr: Expression = serde_json::from_str("5.5").unwrap();
     let _expr: Expression = serde_json::from_str("55").unwrap();
     let _expr: Expression = serde_json::from_str("\"55\"").unwrap();
+    assert_eq!(
+        parse_expression("{Space.base} * 2"),
+        Ok(Expression::Mul(
+            Box::new(Expression::Ref(vec!["Space".into(), "base".into()])),
+            Box::new(Expression::Value(Value::Number(2., NumberType::None)))
+        ))
+    );
+    assert_eq!(
+        parse_expression("round(2.5)").unwrap().to_source(),
+        "round(2.5)"
+    );
+}
+
+#[test]
+fn test_rust_strings() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "quote": { "$value": "say \"hi\" C:\\x" },
+            "icon": { "$type": "asset", "$value": "icons\\close.svg" },
+            "shadow": { "$value": { "say \"x\"": "1px" } }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains(r#"pub const QUOTE: &str = "say \"hi\" C:\\x";"#));
+    assert!(rust.contains(r#"pub const ICON: &str = "icons\\close.svg";"#));
+    assert!(rust.contains(r#"&[("say \"x\"", "1.")]"#));
+}
+
+#[test]
+fn test_precision() {
+    let tokens = crate::parse_design_tokens(
+        r#"{ "width": { "$value": 232.8300018310547 }, "half": { "$value": "29%" } }"#,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css_with_selector(":root");
+    assert!(css.contains("--width: 232.8300018310547;"));
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const WIDTH: f32 = 232.83;"));
+    assert!(rust.contains("pub const HALF: f32 = 0.29;"));
+    let json = serde_json::to_string(&tokens[0]).unwrap();
+    assert!(json.contains("232.8300018310547"));
+}
+
+#[test]
+fn test_units() {
+    for (source, typ) in [
+        ("1.5rem", NumberType::Rem),
+        ("2em", NumberType::Em),
+        ("100vh", NumberType::Vh),
+        ("50vw", NumberType::Vw),
+        ("0.3s", NumberType::Seconds),
+        ("200ms", NumberType::Milliseconds),
+    ] {
+        let expr = expr_parser::expr(source).unwrap();
+        assert_eq!(
+            expr,
+            Expression::Value(Value::Number(
+                source
+                    .trim_end_matches(char::is_alphabetic)
+                    .parse()
+                    .unwrap(),
+                typ
+            ))
+        );
+        assert_eq!(expr.to_css(), source);
+    }
+    let tokens = crate::parse_design_tokens(
+        r#"{ "base": { "$value": "1.5rem" }, "double": { "$value": "2 * {base}" }, "slow": { "$value": "{fast} * 2" }, "fast": { "$value": "150ms" } }"#,
+        None,
+    )
+    .unwrap();
+    let flat = tokens[0].flatten();
+    assert_eq!(
+        flat[1].value,
+        crate::ResolvedValue::Single(Value::Number(3., NumberType::Rem))
+    );
+    assert_eq!(
+        flat[2].value,
+        crate::ResolvedValue::Single(Value::Number(300., NumberType::Milliseconds))
+    );
+    assert!(tokens[0].to_rust().contains("pub const DOUBLE: f32 = 3.;"));
+}
+
+#[test]
+fn test_motion() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "fast": { "$value": "150ms", "$type": "duration" },
+            "slow": { "$value": "0.5s", "$type": "duration" },
+            "odd": { "$value": "0.2505s", "$type": "duration" },
+            "ease": { "$value": [0.4, 0, 0.2, 1], "$type": "cubicBezier" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css_with_selector(":root");
+    assert!(css.contains("--fast: 150ms;"));
+    assert!(css.contains("--ease: cubic-bezier(0.4, 0, 0.2, 1);"));
+    let rust = tokens[0].to_rust();
+    assert!(rust
+        .contains("pub const FAST: std::time::Duration = std::time::Duration::from_millis(150);"));
+    assert!(rust
+        .contains("pub const SLOW: std::time::Duration = std::time::Duration::from_millis(500);"));
+    assert!(rust.contains("std::time::Duration::from_micros(250500);"));
+    assert!(rust.contains("pub const EASE: [f32; 4] = [0.4, 0., 0.2, 1.];"));
+    assert_eq!(
+        expr_parser::expr("cubic-bezier(0.4, 0, 0.2, 1)").unwrap(),
+        Expression::Value(Value::CubicBezier([0.4, 0., 0.2, 1.]))
+    );
+    assert!(tokens[0].to_json().contains("\"$value\": [\n      0.4,"));
+}
+
+#[test]
+fn test_interpolation() {
+    let expr = expr_parser::expr("{fontFamilies.main}, sans-serif").unwrap();
+    assert_eq!(expr.to_css(), "var(--fontfamilies-main), sans-serif");
+    assert_eq!(expr.to_source(), "{fontFamilies.main}, sans-serif");
+    assert_eq!(
+        expr_parser::expr("Inter, sans-serif").unwrap(),
+        Expression::Value(Value::Any("Inter, sans-serif".to_string()))
+    );
+    assert!(expr_parser::expr("{a").is_err());
+
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "fontFamilies": { "main": { "$value": "Inter" } },
+            "body": { "$value": "{fontFamilies.main}, sans-serif" },
+            "gap": { "$value": "{size} {size}" },
+            "size": { "$value": "4px" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const BODY: &str = \"Inter, sans-serif\";"));
+    assert!(rust.contains("pub const GAP: &str = \"4px 4px\";"));
+}
+
+#[test]
+fn test_functions() {
+    let mut tokens = crate::parse_design_tokens(
+        r#"{
+            "space": { "s": { "$value": "4px" }, "l": { "$value": "16px" } },
+            "capped": { "$value": "min({space.l}, 12px)" },
+            "rounded": { "$value": "round({space.s} * 1.3)" },
+            "ratio": { "$value": "round(2.6)" },
+            "fluid": { "$value": "clamp({space.s}, 2vw, {space.l})" }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        tokens.eval("{capped}"),
+        Ok(Value::Number(12., NumberType::Pixels))
+    );
+    assert_eq!(
+        tokens.eval("{rounded}"),
+        Ok(Value::Number(5., NumberType::Pixels))
+    );
+    let css = tokens.to_css_with_selector(":root");
+    assert!(css.contains("--rounded: round(calc(var(--space-s) * 1.3), 1px);"));
+    assert!(css.contains("--ratio: round(2.6);"));
+    let err = "`clamp({space.s}, 2vw, {space.l})`: cannot compare px with vw";
+    assert_eq!(tokens.eval("{fluid}"), Err(err.to_string()));
+    tokens.options.mode = ResolutionMode::Lenient;
+    let errors = tokens.unit_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        (errors[0].path.to_string(), &*errors[0].message),
+        ("fluid".to_string(), err)
+    );
 }
@@ -1,115 +1,596 @@
 use csscolorparser::Color;
-use serde::Deserialize;
+use indexmap::IndexMap;
+use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::expression::Value;
+use crate::{
+    expression::{parse_expression, Expression, NumberType, Value},
+    DesignTokens, TokenOrGroup, TokenPath, TokenValue,
+};
 
-#[derive(Debug, Deserialize)]
-pub enum Extensions {
-    #[serde(rename = "studio.tokens")]
-    StudioTokens(StudioTokensExtension),
-    #[serde(other)]
-    Other,
+const STUDIO_TOKENS: &str = "studio.tokens";
+
+/// Adjusts the resolved value of a token carrying the vendor's extension, given as written.
+/// Registered by vendor in [`crate::ResolveOptions::extensions`].
+pub type ExtensionHandler = fn(&DesignTokens, &serde_json::Value, Value) -> Value;
+
+/// The `$extensions` of a token. `studio.tokens` is understood natively; other vendors are kept
+/// as written, so they survive a round trip and can be handled by an [`ExtensionHandler`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Extensions {
+    pub studio_tokens: Option<Box<StudioTokensExtension>>,
+    pub other: IndexMap<String, serde_json::Value>,
+}
+impl Extensions {
+    /// Whether resolving with `tokens` changes the value of a token carrying these extensions.
+    pub(crate) fn modifies(ext: &Option<Extensions>, tokens: &DesignTokens) -> bool {
+        ext.as_ref().is_some_and(|ext| {
+            ext.studio_tokens.is_some()
+                || ext
+                    .other
+                    .keys()
+                    .any(|x| tokens.options.extensions.contains_key(x))
+        })
+    }
+    /// Applies the modifier and then the registered handlers, in the order they were written.
+    pub(crate) fn apply(&self, tokens: &DesignTokens, value: Value) -> Value {
+        let value = match &self.studio_tokens {
+            Some(ext) => ext.to_rust(tokens, &value),
+            None => value,
+        };
+        self.other.iter().fold(value, |value, (vendor, data)| {
+            match tokens.options.extensions.get(vendor) {
+                Some(handler) => handler(tokens, data, value),
+                None => value,
+            }
+        })
+    }
+}
+impl Serialize for Extensions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(ext) = &self.studio_tokens {
+            map.serialize_entry(STUDIO_TOKENS, ext)?;
+        }
+        for (vendor, data) in &self.other {
+            map.serialize_entry(vendor, data)?;
+        }
+        map.end()
+    }
+}
+impl<'de> Deserialize<'de> for Extensions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut other = IndexMap::<String, serde_json::Value>::deserialize(deserializer)?;
+        let studio_tokens = match other.shift_remove(STUDIO_TOKENS) {
+            Some(ext) => Some(serde_json::from_value(ext).map_err(serde::de::Error::custom)?),
+            None => None,
+        };
+        Ok(Extensions {
+            studio_tokens,
+            other,
+        })
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StudioTokensModify {
     #[serde(rename = "lighten")]
     Lighten,
     #[serde(rename = "darken")]
     Darken,
+    /// Sets the alpha to the value.
     #[serde(rename = "alpha")]
     Alpha,
-    #[serde(other)]
-    Other,
+    #[serde(rename = "mix")]
+    Mix,
+    #[serde(rename = "saturate")]
+    Saturate,
+    #[serde(rename = "desaturate")]
+    Desaturate,
+    /// Rotates the hue by the value in degrees.
+    #[serde(rename = "hue")]
+    Hue,
+    /// A type this crate doesn't implement, left unapplied.
+    #[serde(untagged)]
+    Other(String),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StudioTokensSpace {
+    #[serde(rename = "srgb")]
+    Srgb,
     #[serde(rename = "hsl")]
     Hsl,
     #[serde(rename = "lch")]
     Lch,
+    #[serde(rename = "oklch")]
+    Oklch,
+    /// A space this crate doesn't implement, whose modifiers are left unapplied.
     #[serde(other)]
     Other,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudioTokensModifier {
+    #[serde(rename = "type")]
+    pub type_: StudioTokensModify,
+    pub value: String,
+    pub space: StudioTokensSpace,
+    /// The color to mix towards, for `mix`. Usually a reference to another token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Expression>,
+}
+
+/// One modifier, or several applied in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StudioTokensModifiers {
+    One(StudioTokensModifier),
+    Chain(Vec<StudioTokensModifier>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StudioTokensExtension {
     #[serde(rename = "modify")]
-    Modify {
-        #[serde(rename = "type")]
-        type_: StudioTokensModify,
-        value: String,
-        space: StudioTokensSpace,
-    },
+    Modify(StudioTokensModifiers),
 }
 impl StudioTokensExtension {
-    pub fn to_css(&self, base_value: &Value) -> String {
+    pub fn modifiers(&self) -> &[StudioTokensModifier] {
         match self {
-            StudioTokensExtension::Modify {
-                type_,
-                value,
-                space,
-            } => {
-                let value: f64 = value.parse().unwrap();
-                match base_value {
-                    Value::Color(color) => match space {
-                        StudioTokensSpace::Hsl => {
-                            let (h, s, l, a) = color.to_hsla();
-                            let l2 = match type_ {
-                                StudioTokensModify::Lighten => l + l * value,
-                                StudioTokensModify::Darken => l - l * value,
-                                _ => panic!("Invalid type: {:?}", type_),
-                            };
-                            Color::from_hsla(h, s, l2, a).to_hex_string()
-                        }
-                        StudioTokensSpace::Lch => {
-                            let (l, c, h, a) = color.to_lch();
-                            let a2 = match type_ {
-                                StudioTokensModify::Alpha => a + a * value,
-                                _ => panic!("Invalid type: {:?}", type_),
-                            };
-                            Color::from_lch(l, c, h, a2).to_hex_string()
-                        }
-                        StudioTokensSpace::Other => todo!(),
-                    },
-                    _ => panic!("Unexpected base value: {:?}", base_value),
-                }
+            StudioTokensExtension::Modify(StudioTokensModifiers::One(x)) => std::slice::from_ref(x),
+            StudioTokensExtension::Modify(StudioTokensModifiers::Chain(x)) => x,
+        }
+    }
+    pub fn to_css(&self, tokens: &DesignTokens, base_value: &Value) -> String {
+        self.to_rust(tokens, base_value).to_css()
+    }
+    pub fn to_rust(&self, tokens: &DesignTokens, base_value: &Value) -> Value {
+        self.modifiers()
+            .iter()
+            .fold(base_value.clone(), |value, x| x.apply(tokens, &value))
+    }
+}
+impl StudioTokensModifier {
+    /// Modifies `base_value` the way Tokens Studio does. `oklch` is not a Tokens Studio space;
+    /// it works like `lch` on perceptual lightness and chroma.
+    /// Unsupported types and spaces leave the value as is, see
+    /// [`DesignTokens::unsupported_modifiers`].
+    pub fn apply(&self, tokens: &DesignTokens, base_value: &Value) -> Value {
+        self.try_apply(tokens, base_value)
+            .unwrap_or_else(|_| base_value.clone())
+    }
+    /// The value as a number. Tokens Studio allows references and math here, e.g.
+    /// `{opacity.low} * 2`, and percentages are read as fractions.
+    fn amount(&self, tokens: &DesignTokens) -> Option<f64> {
+        let expr = parse_expression(&self.value).ok()?;
+        if tokens.missing_in(&expr, &mut Vec::new()).is_some() {
+            return None;
+        }
+        match expr.try_get_value(tokens).ok()? {
+            Value::Number(x, NumberType::None) => Some(x),
+            Value::Number(x, NumberType::Percentage) => Some(x / 100.),
+            _ => None,
+        }
+    }
+    /// Fails with the reason the modifier is left unapplied, see
+    /// [`DesignTokens::unsupported_modifiers`].
+    fn try_apply(&self, tokens: &DesignTokens, base_value: &Value) -> Result<Value, String> {
+        let space = tokens.options.modifier_space.unwrap_or(self.space);
+        match (&self.type_, space) {
+            (StudioTokensModify::Other(type_), _) => {
+                return Err(format!(
+                    "unsupported modifier type {type_:?}, left unapplied"
+                ))
             }
+            (StudioTokensModify::Alpha, _) => {}
+            (type_, StudioTokensSpace::Other) => {
+                return Err(format!(
+                    "unsupported color space for {type_:?}, left unapplied"
+                ))
+            }
+            _ => {}
         }
+        let Value::Color(color) = base_value else {
+            return Err(format!(
+                "{:?} needs a color, not {}, left unapplied",
+                self.type_,
+                base_value.describe()
+            ));
+        };
+        let value = self.amount(tokens).ok_or_else(|| {
+            format!(
+                "unsupported modifier value {:?}, left unapplied",
+                self.value
+            )
+        })?;
+        let space = &space;
+        Ok(Value::Color(match self.type_ {
+            StudioTokensModify::Mix => {
+                let other = match self.color.as_ref().map(|x| x.try_get_value(tokens)) {
+                    Some(Ok(Value::Color(other))) => other,
+                    _ => return Err("Mix needs a color to mix with, left unapplied".to_string()),
+                };
+                mix(color, &other, value, space)
+            }
+            StudioTokensModify::Alpha => {
+                let mut color = color.clone();
+                color.a = value.clamp(0., 1.);
+                color
+            }
+            StudioTokensModify::Lighten => lighten(color, space, value),
+            StudioTokensModify::Darken => darken(color, space, value),
+            StudioTokensModify::Saturate => adjust(color, space, |l, c| (l, c + c * value)),
+            StudioTokensModify::Desaturate => adjust(color, space, |l, c| (l, c - c * value)),
+            StudioTokensModify::Hue => rotate_hue(color, space, value),
+            StudioTokensModify::Other(_) => unreachable!(),
+        }))
     }
-    pub fn to_rust(&self, base_value: &Value) -> Value {
-        match self {
-            StudioTokensExtension::Modify {
-                type_,
-                value,
-                space,
-            } => {
-                let value: f64 = value.parse().unwrap();
-                match base_value {
-                    Value::Color(color) => match space {
-                        StudioTokensSpace::Hsl => {
-                            let (h, s, l, a) = color.to_hsla();
-                            let l2 = match type_ {
-                                StudioTokensModify::Lighten => l + l * value,
-                                StudioTokensModify::Darken => l - l * value,
-                                _ => panic!("Invalid type: {:?}", type_),
-                            };
-                            Value::Color(Color::from_hsla(h, s, l2, a))
-                        }
-                        StudioTokensSpace::Lch => {
-                            let (l, c, h, a) = color.to_lch();
-                            let a2 = match type_ {
-                                StudioTokensModify::Alpha => a + a * value,
-                                _ => panic!("Invalid type: {:?}", type_),
-                            };
-                            Value::Color(Color::from_lch(l, c, h, a2))
+}
+
+/// A Tokens Studio modifier on the token at `path` that is left unapplied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedModifier {
+    pub path: TokenPath,
+    pub message: String,
+}
+
+impl DesignTokens {
+    /// Modifiers with a type or space this crate doesn't implement, or with values it can't
+    /// apply, such as a base value that isn't a color. They are skipped when resolving.
+    pub fn unsupported_modifiers(&self) -> Vec<UnsupportedModifier> {
+        self.iter()
+            .flat_map(|(path, token)| {
+                let TokenOrGroup::Token {
+                    value: TokenValue::Single(expr),
+                    extensions:
+                        Some(Extensions {
+                            studio_tokens: Some(ext),
+                            ..
+                        }),
+                    ..
+                } = token
+                else {
+                    return Vec::new();
+                };
+                // Tokens that don't resolve are reported elsewhere.
+                let Ok(mut value) = expr.try_get_value(self) else {
+                    return Vec::new();
+                };
+                ext.modifiers()
+                    .iter()
+                    .filter_map(|x| match x.try_apply(self, &value) {
+                        Ok(next) => {
+                            value = next;
+                            None
                         }
-                        StudioTokensSpace::Other => todo!(),
-                    },
-                    _ => panic!("Unexpected base value: {:?}", base_value),
-                }
-            }
+                        Err(message) => Some(message),
+                    })
+                    .map(|message| UnsupportedModifier {
+                        path: path.clone(),
+                        message,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Moves each sRGB channel, the HSL lightness or the LCH or OKLCH lightness `amount` of the way
+/// to white, reducing LCH and OKLCH chroma by the same fraction.
+fn lighten(color: &Color, space: &StudioTokensSpace, amount: f64) -> Color {
+    match space {
+        StudioTokensSpace::Srgb | StudioTokensSpace::Other => {
+            let [r, g, b] = [color.r, color.g, color.b].map(|x| x + (1. - x) * amount);
+            Color::new(r.clamp(0., 1.), g.clamp(0., 1.), b.clamp(0., 1.), color.a)
         }
+        StudioTokensSpace::Hsl => {
+            let (h, s, l, a) = color.to_hsla();
+            Color::from_hsla(h, s, (l + (1. - l) * amount).clamp(0., 1.), a)
+        }
+        StudioTokensSpace::Lch => {
+            let (l, c, h, a) = color.to_lch();
+            let l = (l + (100. - l) * amount).clamp(0., 100.);
+            Color::from_lch(l, (c - c * amount).max(0.), h, a)
+        }
+        StudioTokensSpace::Oklch => {
+            adjust(color, space, |l, c| (l + (1. - l) * amount, c - c * amount))
+        }
+    }
+}
+
+/// Scales each sRGB channel, the HSL lightness or the LCH lightness down by `amount`.
+fn darken(color: &Color, space: &StudioTokensSpace, amount: f64) -> Color {
+    match space {
+        StudioTokensSpace::Srgb | StudioTokensSpace::Other => {
+            let [r, g, b] = [color.r, color.g, color.b].map(|x| (x - x * amount).clamp(0., 1.));
+            Color::new(r, g, b, color.a)
+        }
+        _ => adjust(color, space, |l, c| (l - l * amount, c)),
+    }
+}
+
+fn rotate_hue(color: &Color, space: &StudioTokensSpace, degrees: f64) -> Color {
+    match space {
+        StudioTokensSpace::Lch => {
+            let (l, c, h, a) = color.to_lch();
+            Color::from_lch(l, c, h + degrees.to_radians(), a)
+        }
+        StudioTokensSpace::Oklch => {
+            let (l, c, h, a) = to_oklch(color);
+            from_oklch(l, c, h + degrees.to_radians(), a)
+        }
+        _ => {
+            let (h, s, l, a) = color.to_hsla();
+            Color::from_hsla((h + degrees).rem_euclid(360.), s, l, a)
+        }
+    }
+}
+
+pub(crate) fn to_oklch(color: &Color) -> (f64, f64, f64, f64) {
+    let (l, a, b, alpha) = color.to_oklaba();
+    (l, a.hypot(b), b.atan2(a), alpha)
+}
+pub(crate) fn from_oklch(l: f64, c: f64, h: f64, alpha: f64) -> Color {
+    Color::from_oklaba(l, c * h.cos(), c * h.sin(), alpha)
+}
+
+/// Applies `f` to the lightness and chroma (saturation for HSL) of `color` in `space`.
+fn adjust(color: &Color, space: &StudioTokensSpace, f: impl Fn(f64, f64) -> (f64, f64)) -> Color {
+    match space {
+        StudioTokensSpace::Hsl | StudioTokensSpace::Srgb => {
+            let (h, s, l, a) = color.to_hsla();
+            let (l, s) = f(l, s);
+            Color::from_hsla(h, s.clamp(0., 1.), l.clamp(0., 1.), a)
+        }
+        StudioTokensSpace::Lch => {
+            let (l, c, h, a) = color.to_lch();
+            let (l, c) = f(l, c);
+            Color::from_lch(l.clamp(0., 100.), c.max(0.), h, a)
+        }
+        StudioTokensSpace::Oklch => {
+            let (l, c, h, a) = to_oklch(color);
+            let (l, c) = f(l, c);
+            from_oklch(l.clamp(0., 1.), c.max(0.), h, a)
+        }
+        StudioTokensSpace::Other => unreachable!(),
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+/// Interpolates between two hues (in degrees) along the shorter arc.
+fn lerp_hue(a: f64, b: f64, t: f64) -> f64 {
+    let a = if a.is_nan() { b } else { a };
+    let b = if b.is_nan() { a } else { b };
+    let delta = ((b - a) % 360. + 540.) % 360. - 180.;
+    (a + delta * t).rem_euclid(360.)
+}
+
+/// Mixes `amount` (0..1) of `other` into `color`, interpolating in `space`.
+fn mix(color: &Color, other: &Color, amount: f64, space: &StudioTokensSpace) -> Color {
+    match space {
+        StudioTokensSpace::Hsl => {
+            let (h1, s1, l1, a1) = color.to_hsla();
+            let (h2, s2, l2, a2) = other.to_hsla();
+            Color::from_hsla(
+                lerp_hue(h1, h2, amount),
+                lerp(s1, s2, amount),
+                lerp(l1, l2, amount),
+                lerp(a1, a2, amount),
+            )
+        }
+        StudioTokensSpace::Lch => color.interpolate_lch(other, amount),
+        StudioTokensSpace::Oklch => {
+            let (l1, c1, h1, a1) = to_oklch(color);
+            let (l2, c2, h2, a2) = to_oklch(other);
+            from_oklch(
+                lerp(l1, l2, amount),
+                lerp(c1, c2, amount),
+                lerp_hue(h1.to_degrees(), h2.to_degrees(), amount).to_radians(),
+                lerp(a1, a2, amount),
+            )
+        }
+        StudioTokensSpace::Srgb | StudioTokensSpace::Other => color.interpolate_rgb(other, amount),
     }
 }
+
+#[test]
+fn test_mix() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "white": { "$value": "#ffffff" },
+            "black": { "$value": "#000000" },
+            "grey": {
+                "$value": "{black}",
+                "$extensions": {
+                    "studio.tokens": {
+                        "modify": { "type": "mix", "value": "0.5", "space": "srgb", "color": "{white}" }
+                    }
+                }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    assert!(tokens[0].to_css().contains("--grey: #808080;"));
+    assert!(tokens[0]
+        .to_rust()
+        .contains("pub const GREY: &str = \"#808080\";"));
+}
+
+#[test]
+fn test_spaces() {
+    let json = |space: &str| {
+        format!(
+            r##"{{
+                "base": {{ "$value": "#3366cc" }},
+                "light": {{
+                    "$value": "{{base}}",
+                    "$extensions": {{
+                        "studio.tokens": {{ "modify": {{ "type": "lighten", "value": "0.2", "space": "{space}" }} }}
+                    }}
+                }}
+            }}"##
+        )
+    };
+    let light = |tokens: &DesignTokens| {
+        let (_, token) = tokens.tokens().remove(1);
+        Value::Color(csscolorparser::parse(&token.resolve_css(tokens)).unwrap())
+    };
+    let hsl = crate::parse_design_tokens(&json("hsl"), None)
+        .unwrap()
+        .remove(0);
+    let oklch = crate::parse_design_tokens(&json("oklch"), None)
+        .unwrap()
+        .remove(0);
+    let Value::Color(a) = light(&hsl) else {
+        unreachable!()
+    };
+    let Value::Color(b) = light(&oklch) else {
+        unreachable!()
+    };
+    assert_ne!(a.to_hex_string(), b.to_hex_string());
+    // OKLCH lightening keeps the hue and moves perceived lightness 20% of the way to white.
+    let base = csscolorparser::parse("#3366cc").unwrap();
+    let l = to_oklch(&base).0;
+    assert!((to_oklch(&b).0 - (l + (1. - l) * 0.2)).abs() < 1e-2);
+    assert!((to_oklch(&b).2 - to_oklch(&base).2).abs() < 1e-2);
+
+    let mut forced = crate::parse_design_tokens(&json("hsl"), None)
+        .unwrap()
+        .remove(0);
+    forced.options.modifier_space = Some(StudioTokensSpace::Oklch);
+    let Value::Color(c) = light(&forced) else {
+        unreachable!()
+    };
+    assert_eq!(b.to_hex_string(), c.to_hex_string());
+}
+
+#[test]
+fn test_vendor() {
+    let mut tokens = crate::parse_design_tokens(
+        r#"{
+            "gap": {
+                "$value": "4px",
+                "$extensions": { "com.example": { "scale": 2 }, "org.other": [1, 2] }
+            }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert!(tokens.to_json().contains("\"org.other\": [\n"));
+    assert!(tokens.to_css().contains("--gap: 4px;"));
+    tokens
+        .options
+        .extensions
+        .insert("com.example".to_string(), |_, data, value| match value {
+            Value::Number(x, unit) => Value::Number(x * data["scale"].as_f64().unwrap(), unit),
+            value => value,
+        });
+    assert!(tokens.to_css().contains("--gap: 8px;"));
+}
+
+#[test]
+fn test_modifiers() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "black": { "$value": "#000000" },
+            "red": { "$value": "#ff0000" },
+            "smoke": {
+                "$value": "{black}",
+                "$extensions": { "studio.tokens": { "modify": [
+                    { "type": "lighten", "value": "0.2", "space": "srgb" },
+                    { "type": "alpha", "value": "0.5", "space": "srgb" }
+                ] } }
+            },
+            "maroon": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "darken", "value": "0.2", "space": "srgb" } } }
+            },
+            "step": { "$value": 0.1 },
+            "maroon2": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "darken", "value": "{step} * 2", "space": "srgb" } } }
+            },
+            "maroon3": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "darken", "value": "20%", "space": "srgb" } } }
+            },
+            "green": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "hue", "value": "120", "space": "hsl" } } }
+            },
+            "grey": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "desaturate", "value": "1", "space": "hsl" } } }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css();
+    assert!(css.contains("--smoke: #33333380;"));
+    assert!(css.contains("--maroon: #cc0000;"));
+    assert!(css.contains("--maroon2: #cc0000;"));
+    assert!(css.contains("--maroon3: #cc0000;"));
+    assert!(css.contains("--green: #00ff00;"));
+    let grey = csscolorparser::parse(&tokens[0].resolve_css("grey").unwrap()).unwrap();
+    assert!(grey.r == grey.g && grey.g == grey.b);
+}
+
+#[test]
+fn test_unsupported() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "red": { "$value": "#ff0000" },
+            "a": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "shade", "value": "0.2", "space": "srgb" } } }
+            },
+            "b": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "lighten", "value": "0.2", "space": "p3" } } }
+            },
+            "c": {
+                "$value": "4px",
+                "$extensions": { "studio.tokens": { "modify": { "type": "lighten", "value": "0.2", "space": "srgb" } } }
+            },
+            "d": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "darken", "value": "lots", "space": "srgb" } } }
+            },
+            "e": {
+                "$value": "{red}",
+                "$extensions": { "studio.tokens": { "modify": { "type": "mix", "value": "0.5", "space": "srgb", "color": "{c}" } } }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css();
+    assert!(css.contains("--a: #ff0000;"));
+    assert!(css.contains("--b: #ff0000;"));
+    assert!(css.contains("--c: 4px;"));
+    assert!(css.contains("--d: #ff0000;"));
+    assert!(css.contains("--e: #ff0000;"));
+    assert!(tokens[0]
+        .to_rust()
+        .contains("pub const E: &str = \"#ff0000\";"));
+    let found = tokens[0].unsupported_modifiers();
+    assert_eq!(found[0].path, TokenPath::parse("a"));
+    assert_eq!(
+        found[0].message,
+        "unsupported modifier type \"shade\", left unapplied"
+    );
+    assert_eq!(found[1].path, TokenPath::parse("b"));
+    assert_eq!(
+        found[2..].iter().map(|x| &x.message).collect::<Vec<_>>(),
+        [
+            "Lighten needs a color, not px, left unapplied",
+            "unsupported modifier value \"lots\", left unapplied",
+            "Mix needs a color to mix with, left unapplied",
+        ]
+    );
+    assert!(tokens[0].to_json().contains("\"type\": \"shade\""));
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{DesignTokens, TokenPath};
+
+/// Which tokens reference which, as returned by [`DesignTokens::reference_graph`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReferenceGraph {
+    /// `(token, referenced token)` pairs in document order. References into a composite token's
+    /// fields point at the composite token.
+    pub edges: Vec<(TokenPath, TokenPath)>,
+    /// Referenced paths that don't exist.
+    pub missing: Vec<TokenPath>,
+}
+impl ReferenceGraph {
+    pub fn references<'a>(&'a self, path: &'a TokenPath) -> impl Iterator<Item = &'a TokenPath> {
+        self.edges
+            .iter()
+            .filter(move |(from, _)| from == path)
+            .map(|(_, to)| to)
+    }
+    pub fn referenced_by<'a>(&'a self, path: &'a TokenPath) -> impl Iterator<Item = &'a TokenPath> {
+        self.edges
+            .iter()
+            .filter(move |(_, to)| to == path)
+            .map(|(from, _)| from)
+    }
+    /// Length of the longest alias chain starting at `path`; 0 for tokens without references.
+    pub fn depth(&self, path: &TokenPath) -> usize {
+        self.depth_from(path, &mut Vec::new())
+    }
+    fn depth_from<'a>(&'a self, path: &'a TokenPath, seen: &mut Vec<&'a TokenPath>) -> usize {
+        if seen.contains(&path) {
+            return 0;
+        }
+        seen.push(path);
+        let depth = self
+            .references(path)
+            .map(|x| self.depth_from(x, seen) + 1)
+            .max()
+            .unwrap_or(0);
+        seen.pop();
+        depth
+    }
+    /// Tokens whose alias chain is longer than `max`, deepest first.
+    pub fn deeper_than(&self, max: usize) -> Vec<(&TokenPath, usize)> {
+        self.edges
+            .iter()
+            .map(|(from, _)| from)
+            .unique()
+            .map(|x| (x, self.depth(x)))
+            .filter(|(_, depth)| *depth > max)
+            .sorted_by(|a, b| b.1.cmp(&a.1))
+            .collect()
+    }
+    /// The graph in Graphviz DOT syntax, with missing references drawn in red.
+    pub fn to_dot(&self) -> String {
+        let missing = self
+            .missing
+            .iter()
+            .map(|x| format!("  \"{}\" [color=red, fontcolor=red];\n", escape(x)))
+            .join("");
+        let edges = self
+            .edges
+            .iter()
+            .map(|(from, to)| format!("  \"{}\" -> \"{}\";\n", escape(from), escape(to)))
+            .join("");
+        format!("digraph tokens {{\n  rankdir=LR;\n  node [shape=box];\n{missing}{edges}}}\n")
+    }
+}
+
+/// Hands out one shared [`TokenPath`] per distinct path.
+#[derive(Debug, Clone, Default)]
+pub struct PathInterner(HashSet<TokenPath>);
+impl PathInterner {
+    pub fn intern(&mut self, path: &[String]) -> TokenPath {
+        if let Some(x) = self.0.get(path) {
+            return x.clone();
+        }
+        let path = TokenPath::from(path);
+        self.0.insert(path.clone());
+        path
+    }
+}
+
+fn escape(path: &TokenPath) -> String {
+    path.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl DesignTokens {
+    /// The alias dependency graph, e.g. to see how semantic tokens map onto core palettes.
+    pub fn reference_graph(&self) -> ReferenceGraph {
+        let mut graph = ReferenceGraph::default();
+        let mut paths = PathInterner::default();
+        for (path, token) in self.iter() {
+            let path = paths.intern(&path);
+            let targets = token
+                .exprs()
+                .into_iter()
+                .flat_map(|x| x.refs())
+                .map(|target| match self.get_value(target) {
+                    None if self.get_field(target).is_some() => {
+                        paths.intern(&target[..target.len() - 1])
+                    }
+                    _ => paths.intern(target),
+                })
+                .sorted()
+                .dedup();
+            for target in targets {
+                if self.get_value(&target).is_none() && !graph.missing.contains(&target) {
+                    graph.missing.push(target.clone());
+                }
+                graph.edges.push((path.clone(), target));
+            }
+        }
+        graph
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "base": { "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" } },
+            "brand": { "primary": { "$value": "{base.red}" } },
+            "button": {
+                "bg": { "$value": "{brand.primary}" },
+                "border": { "$type": "border", "$value": { "color": "{button.bg}", "width": "{base.gap}" } },
+                "ring": { "$value": "{button.border.color}" },
+                "broken": { "$value": "{nope}" }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let graph = tokens[0].reference_graph();
+    let path = TokenPath::parse;
+    assert_eq!(graph.depth(&path("button/ring")), 4);
+    assert_eq!(graph.depth(&path("brand/primary")), 1);
+    assert_eq!(graph.depth(&path("base/red")), 0);
+    assert_eq!(
+        graph.referenced_by(&path("base/red")).collect_vec(),
+        [&path("brand/primary")]
+    );
+    assert_eq!(graph.missing, [path("nope")]);
+    let (from, to) = (&graph.edges[0].0, &graph.edges[1].1);
+    assert_eq!((from, to), (&path("brand/primary"), &path("brand/primary")));
+    assert!(std::sync::Arc::ptr_eq(&from.0, &to.0));
+    assert_eq!(
+        graph.deeper_than(2),
+        [(&path("button/ring"), 4), (&path("button/border"), 3)]
+    );
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph tokens {\n"));
+    assert!(dot.contains("  \"nope\" [color=red, fontcolor=red];\n"));
+    assert!(dot
+        .contains("  \"button/border\" -> \"base/gap\";\n  \"button/border\" -> \"button/bg\";\n"));
+}
@@ -1,15 +1,63 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    sync::Arc,
+};
 
 use convert_case::{Case, Casing};
-use expression::{Expression, NumberType, Value};
-use extensions::Extensions;
+use emit::Emitter;
+pub use expression::{
+    parse_expression, ColorFormat, ColorMath, Expression, Function, NumberType, Value,
+};
+use extensions::{ExtensionHandler, Extensions, StudioTokensSpace};
 use indexmap::IndexMap;
 use itertools::Itertools;
-use serde::Deserialize;
+use naming::Naming;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+pub mod a11y;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod composite;
+mod de;
+pub mod diff;
+pub mod emit;
 mod expression;
 pub mod extensions;
+pub mod graph;
+pub mod lint;
+pub mod modes;
+pub mod naming;
+pub mod palette;
+mod parallel;
+pub mod scale;
+pub mod schema;
+pub mod token_set;
+pub mod transform;
+mod usage;
+pub mod validate;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use watch::watch;
 
+/// The embedded Ambient tokens, parsed on first use. See [`design_tokens`] to borrow them
+/// without cloning.
+#[cfg(feature = "ambient-tokens")]
 pub fn get_design_tokens() -> Vec<DesignTokens> {
+    design_tokens().to_vec()
+}
+
+/// The embedded Ambient tokens, parsed once and shared by every caller.
+#[cfg(feature = "ambient-tokens")]
+pub fn design_tokens() -> &'static [DesignTokens] {
+    static TOKENS: std::sync::OnceLock<Vec<DesignTokens>> = std::sync::OnceLock::new();
+    TOKENS.get_or_init(parse_embedded)
+}
+
+#[cfg(feature = "ambient-tokens")]
+fn parse_embedded() -> Vec<DesignTokens> {
     // I couldn't get one exporter to give me good, well-formatted data, so I had to use two.
     let mut data: Vec<DesignTokens> =
         serde_json::from_str(include_str!("./exportedVariables.json")).unwrap();
@@ -18,167 +66,1372 @@ pub fn get_design_tokens() -> Vec<DesignTokens> {
     data.push(DesignTokens {
         file_name: None,
         body: TokenOrGroup::Group(data2),
+        options: Default::default(),
+        origin: Default::default(),
     });
+    for set in &mut data {
+        set.body.apply_types();
+    }
+    data
+}
+
+/// Parses either a list of `{ fileName, body }` sets (Figma variables export) or a single token tree.
+pub fn parse_design_tokens(
+    json: &str,
+    file_name: Option<&str>,
+) -> serde_json::Result<Vec<DesignTokens>> {
+    let mut data: Vec<DesignTokens> = if json.trim_start().starts_with('[') {
+        list_bodies(serde_json::from_str(json)?)
+    } else {
+        vec![DesignTokens {
+            file_name: file_name.map(|x| x.to_string()),
+            body: serde_json::from_str::<de::Body>(json)?.0,
+            options: Default::default(),
+            origin: Default::default(),
+        }]
+    };
+    for set in &mut data {
+        set.body.apply_types();
+    }
+    Ok(data)
+}
+/// Like [`parse_design_tokens`], but streams from `reader` instead of holding all of the JSON
+/// in memory, for exports of tens of megabytes.
+pub fn read_design_tokens(
+    reader: impl Read,
+    file_name: Option<&str>,
+) -> serde_json::Result<Vec<DesignTokens>> {
+    let mut reader = BufReader::new(reader);
+    let list = loop {
+        let buf = reader.fill_buf().map_err(serde_json::Error::io)?;
+        match buf.iter().position(|x| !x.is_ascii_whitespace()) {
+            Some(i) => break buf[i] == b'[',
+            None if buf.is_empty() => break false,
+            None => {
+                let n = buf.len();
+                reader.consume(n);
+            }
+        }
+    };
+    let mut data: Vec<DesignTokens> = if list {
+        list_bodies(serde_json::from_reader(reader)?)
+    } else {
+        vec![DesignTokens {
+            file_name: file_name.map(|x| x.to_string()),
+            body: serde_json::from_reader::<_, de::Body>(reader)?.0,
+            options: Default::default(),
+            origin: Default::default(),
+        }]
+    };
+    for set in &mut data {
+        set.body.apply_types();
+    }
+    Ok(data)
+}
+/// Points each set of a `[{ fileName, body }]` file at its body.
+fn list_bodies(mut data: Vec<DesignTokens>) -> Vec<DesignTokens> {
+    for (i, set) in data.iter_mut().enumerate() {
+        set.origin.body = format!("/{i}/body");
+    }
     data
 }
+pub fn load_design_tokens(path: impl AsRef<Path>) -> std::io::Result<Vec<DesignTokens>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)?;
+    let file_name = path.file_name().and_then(|x| x.to_str());
+    Ok(read_design_tokens(file, file_name)?)
+}
+
+pub fn to_css(tokens: &[DesignTokens]) -> String {
+    emit::CssEmitter::default().emit(&emit::ResolvedTokens::new(tokens))
+}
+pub fn to_rust(tokens: &[DesignTokens]) -> String {
+    emit::RustEmitter::default().emit(&emit::ResolvedTokens::new(tokens))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CssOptions {
+    pub color_format: ColorFormat,
+    /// Writes pixel values as `rem` relative to this root font size.
+    pub rem_base: Option<f64>,
+    /// Precedes each declaration with a comment naming the source file, token path and
+    /// unresolved expression.
+    pub provenance: bool,
+    /// Prepended to every custom property, e.g. `ds` for `--ds-brand-primary`.
+    pub prefix: Option<String>,
+    pub naming: CssNaming,
+    /// Precedes the output with `@property` rules typing every color, length, percentage,
+    /// number and time token, so they can be animated. See [`to_css_property_rules`].
+    pub register_properties: bool,
+    /// Strips comments and whitespace from emitted CSS.
+    pub minify: bool,
+    /// Writes every rule over multiple lines, indenting its contents by this many spaces per
+    /// level. Without it, rules are written as generated: single-valued tokens on one line,
+    /// composite ones over several. See [`emit::css_format::format_css`].
+    pub indent: Option<usize>,
+    /// How token references are written, e.g. for pages that don't load every set.
+    pub references: CssReferences,
+    pub resolve: ResolveStrategy,
+}
+impl CssOptions {
+    /// The custom property a token path is written to, e.g. `--brand-primary`.
+    pub fn property_name(&self, naming: &Naming, path: &[String]) -> String {
+        let name = match self.naming {
+            CssNaming::Escaped if !naming.transforms.contains_key("css") => naming
+                .prefix
+                .iter()
+                .chain(path)
+                .map(|x| css_escape(x))
+                .join("-"),
+            _ => naming.name("css", path, None, "-"),
+        };
+        format!("--{}", self.prefix.iter().cloned().chain([name]).join("-"))
+    }
+}
+
+/// Whether an emitter keeps references and arithmetic or writes resolved values. Rust output is
+/// always resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolveStrategy {
+    /// Keeps references and arithmetic as `var()` and `calc()`, so values follow theme switches
+    /// at runtime. Color modifiers and references to composite fields, which CSS can't
+    /// express, are still resolved.
+    #[default]
+    Preserve,
+    /// Writes every value fully resolved, the same as the Rust constants.
+    Inline,
+}
+
+/// How a reference to another token is written in CSS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CssReferences {
+    /// `var(--brand-primary)`, following the referenced custom property at runtime.
+    #[default]
+    Var,
+    /// `var(--brand-primary, #2871f9)`, falling back to the resolved value when the referenced
+    /// property isn't defined on the page.
+    Fallback,
+    /// `#2871f9`, the resolved value without a `var()`.
+    Inline,
+}
+
+/// How token paths become CSS custom property names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CssNaming {
+    /// Lowercase with punctuation spelled out, e.g. `Space/1.5` as `--space-1d5`. Distinct
+    /// paths can end up with the same name, see [`DesignTokens::css_collisions`].
+    #[default]
+    Slug,
+    /// Keeps the case and characters of the path, backslash-escaping those that aren't valid
+    /// in a CSS identifier, e.g. `--Space-1\.5`.
+    Escaped,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RustOptions {
+    pub color: RustColor,
+    /// Group generated as `BREAKPOINTS`, see [`DesignTokens::breakpoints`].
+    pub breakpoints: Option<String>,
+    /// Also generates `TOKENS`, a `phf::Map` from each token's `/`-separated path to a
+    /// `TokenValue`, for looking tokens up by name at runtime. The generated code needs `phf`
+    /// with the `macros` feature.
+    pub lookup: bool,
+    /// Also generates a `Theme` struct holding every token, defaulting to the constants, with
+    /// builder-style setters for overriding them at runtime.
+    pub theme: bool,
+    /// Also generates `TOKEN_METADATA`, the name, group, type, source expression and
+    /// description of each token, for listing them at runtime.
+    pub metadata: bool,
+    /// Path of the token file the constants come from, relative to the package running the
+    /// tests. When set, each module gets a `#[cfg(test)]` module checking the constants against
+    /// [`DesignTokens::rust_values`] of that file, which needs `ambient_design_tokens_core` as a
+    /// dev-dependency.
+    pub tests: Option<String>,
+    /// Generates numbers with a CSS unit as a `Dimension` holding the value as written and its
+    /// `Unit`, e.g. `16px` as `Dimension { value: 16., unit: Unit::Px }`, instead of a bare
+    /// `f32`. Percentages stay out of 100.
+    pub dimensions: bool,
+    /// Group generated as `SPACING`, see [`DesignTokens::spacing`].
+    pub spacing: Option<String>,
+}
+
+/// How color constants are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RustColor {
+    /// `&str` in `#rrggbb` or `#rrggbbaa` form.
+    #[default]
+    Hex,
+    /// Linear-light `[f32; 4]` RGBA, ready to upload to the GPU without conversion.
+    LinearRgba,
+    /// Linear-light `glam::Vec4`. The generated code needs `glam` in scope.
+    GlamVec4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThemeSelector {
+    MediaQuery,
+    DataAttribute,
+    #[default]
+    Both,
+}
+
+/// An `@property` rule per custom property with a typed value, e.g.
+/// `@property --gap { syntax: '<length>'; inherits: true; initial-value: 4px; }`. The first set
+/// defining a property provides its initial value.
+pub fn to_css_property_rules(tokens: &[DesignTokens], options: &CssOptions) -> String {
+    // Initial values have to be computationally independent, so pixels aren't converted to rem
+    // and relative lengths start out at zero.
+    let absolute = CssOptions {
+        rem_base: None,
+        ..options.clone()
+    };
+    tokens
+        .iter()
+        .flat_map(|set| {
+            set.iter()
+                .filter(|(_, token)| set.missing_ref(token).is_none())
+                .filter_map(|(path, token)| {
+                    let TokenOrGroup::Token {
+                        value: TokenValue::Single(expr),
+                        extensions,
+                        ..
+                    } = token
+                    else {
+                        return None;
+                    };
+                    let value = resolve_single(set, expr, extensions);
+                    let syntax = match &value {
+                        Value::Color(_) => "<color>",
+                        Value::Number(_, NumberType::None) => "<number>",
+                        Value::Number(_, NumberType::Percentage) => "<percentage>",
+                        Value::Number(_, NumberType::Seconds | NumberType::Milliseconds) => "<time>",
+                        Value::Number(_, _) => "<length>",
+                        _ => return None,
+                    };
+                    let initial = match &value {
+                        Value::Number(_, NumberType::Rem | NumberType::Em | NumberType::Vh | NumberType::Vw) => {
+                            "0px".to_string()
+                        }
+                        value => value.to_css_with(&absolute),
+                    };
+                    Some((
+                        options.property_name(&set.options.naming, &path),
+                        syntax,
+                        initial,
+                    ))
+                })
+                .collect_vec()
+        })
+        .unique_by(|(name, _, _)| name.clone())
+        .map(|(name, syntax, initial)| {
+            format!("@property {name} {{ syntax: '{syntax}'; inherits: true; initial-value: {initial}; }}")
+        })
+        .join("\n")
+}
+
+/// Emits the `default` theme under `:root` and every other theme as an override block.
+/// `light`/`dark` themes are additionally bound to `prefers-color-scheme`.
+pub fn to_css_themed(
+    themes: &[DesignTokens],
+    default: &str,
+    selector: ThemeSelector,
+    options: &CssOptions,
+) -> String {
+    let (defaults, overrides): (Vec<_>, Vec<_>) =
+        themes.iter().partition(|x| x.get_name() == default);
+    let mut out = defaults
+        .iter()
+        .map(|x| x.to_css_with(":root", options))
+        .collect_vec();
+    for tokens in overrides {
+        let name = tokens.get_name();
+        if selector != ThemeSelector::DataAttribute && matches!(name, "light" | "dark") {
+            let root = match selector {
+                ThemeSelector::Both => ":root:not([data-theme])",
+                _ => ":root",
+            };
+            out.push(format!(
+                "@media (prefers-color-scheme: {name}) {{\n{}\n}}",
+                tokens.to_css_with(root, options)
+            ));
+        }
+        if selector != ThemeSelector::MediaQuery {
+            out.push(tokens.to_css_with(
+                &format!("[data-theme=\"{}\"]", tokens.get_name_css()),
+                options,
+            ));
+        }
+    }
+    out.join("\n")
+}
+
+/// Settings that change how token values are resolved and named.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// How token paths are named in every emitter.
+    pub naming: Naming,
+    /// Runs every color modifier in this space instead of the one declared on the token.
+    pub modifier_space: Option<StudioTokensSpace>,
+    pub mode: ResolutionMode,
+    pub color_math: ColorMath,
+    /// Handlers for vendor `$extensions` other than `studio.tokens`, by vendor, e.g.
+    /// `com.example`.
+    pub extensions: IndexMap<String, ExtensionHandler>,
+    /// Looked up for references this set doesn't define, see [`token_set::TokenSet`].
+    pub fallback: Option<Arc<TokenOrGroup>>,
+    /// The root font size `rem` and `em` are converted with in native outputs such as Swift,
+    /// 16 if unset.
+    pub rem_base: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionMode {
+    /// [`DesignTokens::check`] fails on any reference that doesn't resolve and on custom
+    /// properties shared by several tokens, and arithmetic whose units don't combine is an error.
+    /// The emitters leave the check to the caller and write broken references as in
+    /// [`ResolutionMode::Lenient`].
+    #[default]
+    Strict,
+    /// Writes tokens with broken references as `var()` references in CSS and leaves them out of
+    /// the Rust output. [`DesignTokens::unresolved`] lists the affected tokens. Arithmetic with
+    /// mismatched units keeps the unit of the first operand, see [`DesignTokens::unit_errors`].
+    Lenient,
+}
+
+/// The order tokens, groups and composite fields are written in by every emitter, see
+/// [`DesignTokens::reorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputOrder {
+    /// As in the input files.
+    #[default]
+    Source,
+    /// Sorted by key at every level.
+    Alphabetical,
+}
+
+/// A token that can't be resolved because `reference`, or a token it aliases, is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unresolved {
+    pub path: TokenPath,
+    pub reference: TokenPath,
+}
+
+/// Arithmetic in the token at `path` whose units don't combine, e.g. `{size} * {size}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitError {
+    pub path: TokenPath,
+    pub message: String,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DesignTokens {
     #[serde(rename = "fileName")]
     pub file_name: Option<String>,
     pub body: TokenOrGroup,
+    #[serde(skip)]
+    pub options: ResolveOptions,
+    #[serde(skip)]
+    pub origin: Origin,
 }
+
+/// Where a set's tokens were read from, see [`DesignTokens::source`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Origin {
+    /// JSON pointer to the set's body in its file, e.g. `/0/body` in a list of sets.
+    pub body: String,
+    /// Tokens merged in from other sets by [`DesignTokens::merge`].
+    pub merged: HashMap<TokenPath, Source>,
+}
+
+/// The file and JSON pointer a token was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub file_name: Option<String>,
+    /// E.g. `/Brand/Primary`, with `~` and `/` in keys escaped as `~0` and `~1`.
+    pub pointer: String,
+}
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}#{}",
+            self.file_name.as_deref().unwrap_or_default(),
+            self.pointer
+        )
+    }
+}
+
 impl DesignTokens {
+    /// Merges `layers` into one set, later layers overriding earlier ones token by token.
+    /// Groups are merged recursively; the file name and options come from the first layer.
+    pub fn merge(layers: Vec<DesignTokens>) -> DesignTokens {
+        let mut layers = layers.into_iter();
+        let Some(mut out) = layers.next() else {
+            return DesignTokens {
+                file_name: None,
+                body: TokenOrGroup::Group(IndexMap::new()),
+                options: Default::default(),
+                origin: Default::default(),
+            };
+        };
+        for layer in layers {
+            for (path, _) in layer.iter() {
+                let source = layer.source(&path);
+                out.origin.merged.insert(path, source);
+            }
+            out.body.merge(layer.body);
+        }
+        out
+    }
+    /// Rearranges the tree in `order`, so that output doesn't depend on how the input was
+    /// exported.
+    pub fn reorder(&mut self, order: OutputOrder) {
+        if order == OutputOrder::Alphabetical {
+            self.body.sort_keys();
+        }
+    }
+    /// Where the token at `path` was read from, following it through [`DesignTokens::merge`].
+    pub fn source(&self, path: &[String]) -> Source {
+        if let Some(source) = self.origin.merged.get(path) {
+            return source.clone();
+        }
+        let mut pointer = self.origin.body.clone();
+        for key in path.iter() {
+            pointer += "/";
+            pointer += &key.replace('~', "~0").replace('/', "~1");
+        }
+        Source {
+            file_name: self.file_name.clone(),
+            pointer,
+        }
+    }
+    /// Just the file of [`DesignTokens::source`].
+    pub fn source_file(&self, path: &[String]) -> Option<&str> {
+        match self.origin.merged.get(path) {
+            Some(source) => source.file_name.as_deref(),
+            None => self.file_name.as_deref(),
+        }
+    }
+    /// The token tree as DTCG JSON with `$value`/`$type` keys.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.body).unwrap()
+    }
     pub fn get_name(&self) -> &str {
         if let Some(name) = &self.file_name {
-            name.split(".").nth(1).unwrap()
+            // `Ambient.light.tokens.json` -> `light`, `tokens.json` -> `tokens`
+            let parts = name.split('.').collect_vec();
+            if parts.len() > 2 {
+                parts[1]
+            } else {
+                parts[0]
+            }
         } else {
             "ambient"
         }
     }
     pub fn get_name_rust(&self) -> String {
-        slugify_rs(self.get_name()).to_case(Case::UpperFlat)
+        rust_ident(
+            &self
+                .options
+                .naming
+                .segment(self.get_name(), Some(Case::UpperFlat), "_"),
+        )
+    }
+    /// The name of this set as a CSS class or file name, e.g. `brand-light`.
+    pub fn get_name_css(&self) -> String {
+        self.options.naming.slug(self.get_name(), "-")
     }
     pub fn to_css(&self) -> String {
-        self.body.to_css(self, &slugify_css(self.get_name()), "")
+        self.to_css_with_selector(&format!(".{}", self.get_name_css()))
+    }
+    pub fn to_css_with_selector(&self, selector: &str) -> String {
+        self.to_css_with(selector, &CssOptions::default())
+    }
+    /// Tokens sharing a custom property are all written, see [`DesignTokens::check`].
+    pub fn to_css_with(&self, selector: &str, options: &CssOptions) -> String {
+        match &self.body {
+            // Top-level groups are written in parallel with the `rayon` feature.
+            TokenOrGroup::Group(group) => {
+                parallel::par_map(&group.iter().collect_vec(), |(key, value)| {
+                    value.to_css(self, options, selector, std::slice::from_ref(key))
+                })
+                .join("\n")
+            }
+            body => body.to_css(self, options, selector, &[]),
+        }
+    }
+    fn css_collision_error(&self, options: &CssOptions) -> Result<(), String> {
+        if self.options.mode == ResolutionMode::Strict {
+            if let Some((name, paths)) = self.css_collisions(options).first() {
+                return Err(format!(
+                    "CSS property {name} is shared by {}; rename the tokens or use CssNaming::Escaped",
+                    paths.iter().join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+    /// Custom property names that more than one token is written to, with the tokens.
+    pub fn css_collisions(&self, options: &CssOptions) -> Vec<(String, Vec<TokenPath>)> {
+        self.iter()
+            .filter(|(_, token)| matches!(token, TokenOrGroup::Token { .. }))
+            .map(|(path, _)| (options.property_name(&self.options.naming, &path), path))
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .sorted()
+            .collect()
     }
     pub fn to_rust(&self) -> String {
-        self.body.to_rust(self, "")
+        self.to_rust_with(&RustOptions::default())
+    }
+    pub fn to_rust_with(&self, options: &RustOptions) -> String {
+        let mut out = String::new();
+        emit::rust_format::write_items(&self.rust_items(options), 0, &mut out);
+        out
+    }
+    /// The generated constants, each on a single line.
+    pub(crate) fn rust_items(&self, options: &RustOptions) -> Vec<String> {
+        let tokens = self
+            .tokens()
+            .into_iter()
+            .zip(self.rust_names())
+            .collect_vec();
+        parallel::par_map(&tokens, |((path, token), name)| {
+            (!self.unresolvable(token)).then(|| token.to_rust(self, options, path, name))
+        })
+        .into_iter()
+        .flatten()
+        .chain(self.rust_extras(options))
+        .collect()
+    }
+    /// The items following the constants, which depend on the whole set.
+    pub(crate) fn rust_extras(&self, options: &RustOptions) -> Vec<String> {
+        self.rust_breakpoints(options.breakpoints.as_deref())
+            .into_iter()
+            .chain(self.rust_spacing(options.spacing.as_deref()))
+            .chain(options.lookup.then(|| self.rust_lookup(options)))
+            .chain(options.theme.then(|| self.rust_theme(options)))
+            .chain(options.metadata.then(|| self.rust_metadata()))
+            .chain(options.tests.as_ref().map(|x| self.rust_tests(x, options)))
+            .collect()
+    }
+    /// The value of each generated constant, as Rust source, by constant name.
+    pub fn rust_values(&self, options: &RustOptions) -> IndexMap<String, String> {
+        self.tokens()
+            .into_iter()
+            .zip(self.rust_names())
+            .filter(|((_, token), _)| !self.unresolvable(token))
+            .map(|((_, token), name)| (name, token.rust_value(self, options).1))
+            .collect()
+    }
+    /// Tokens whose value depends on a missing reference, directly or through aliases.
+    pub fn unresolved(&self) -> Vec<Unresolved> {
+        self.iter()
+            .filter_map(|(path, token)| {
+                Some(Unresolved {
+                    reference: self.missing_ref(token)?.into(),
+                    path,
+                })
+            })
+            .collect()
+    }
+    /// Products, quotients and function calls with units that don't combine. These panic when resolved in
+    /// [`ResolutionMode::Strict`]; tokens with missing references are skipped.
+    pub fn unit_errors(&self) -> Vec<UnitError> {
+        self.iter()
+            .filter(|(_, token)| self.missing_ref(token).is_none())
+            .flat_map(|(path, token)| {
+                token
+                    .exprs()
+                    .into_iter()
+                    .flat_map(|x| x.unit_errors(self))
+                    .map(move |message| UnitError {
+                        path: path.clone(),
+                        message,
+                    })
+            })
+            .collect()
+    }
+    /// Whether `token` has to be written unresolved, see [`DesignTokens::unresolved`].
+    fn unresolvable(&self, token: &TokenOrGroup) -> bool {
+        self.missing_ref(token).is_some()
+    }
+    /// The first problem with the set in its [`ResolutionMode`]: a missing reference or a CSS
+    /// property collision in [`ResolutionMode::Strict`], or a value that can't be computed,
+    /// see [`DesignTokens::eval`]. Run this before generating output.
+    pub fn check(&self, options: &CssOptions) -> Result<(), String> {
+        self.css_collision_error(options)?;
+        self.iter()
+            .try_for_each(|(path, token)| self.check_token(&path, token))
+    }
+    /// Like [`DesignTokens::check`], for a single token.
+    pub(crate) fn check_token(&self, path: &[String], token: &TokenOrGroup) -> Result<(), String> {
+        if let Some(missing) = self.missing_ref(token) {
+            return match self.options.mode {
+                ResolutionMode::Strict => Err(format!(
+                    "Unresolved reference {{{}}} in {}",
+                    missing.join("."),
+                    path.join("/")
+                )),
+                ResolutionMode::Lenient => Ok(()),
+            };
+        }
+        token.exprs().into_iter().try_for_each(|x| {
+            x.try_get_value(self)
+                .map(|_| ())
+                .map_err(|err| format!("{}: {err}", path.join("/")))
+        })?;
+        match self.composite_error(token) {
+            Some(err) if self.options.mode == ResolutionMode::Strict => {
+                Err(format!("{}: {err}", path.join("/")))
+            }
+            _ => Ok(()),
+        }
+    }
+    /// The first reference needed by `token` that doesn't resolve.
+    pub(crate) fn missing_ref(&self, token: &TokenOrGroup) -> Option<Vec<String>> {
+        token
+            .exprs()
+            .into_iter()
+            .find_map(|x| self.missing_in(x, &mut Vec::new()))
+    }
+    /// Follows the references of `expr`, treating cycles as missing.
+    fn missing_in<'a>(
+        &'a self,
+        expr: &'a Expression,
+        seen: &mut Vec<&'a [String]>,
+    ) -> Option<Vec<String>> {
+        expr.refs().into_iter().find_map(|path| {
+            let target = match self.get_value(path) {
+                _ if seen.contains(&path) => return Some(path.to_vec()),
+                Some(TokenValue::Single(expr)) => expr,
+                Some(TokenValue::Dict(_)) => return None,
+                None => match self.get_field(path) {
+                    Some(expr) => expr,
+                    None => return Some(path.to_vec()),
+                },
+            };
+            seen.push(path);
+            let out = self.missing_in(target, seen);
+            seen.pop();
+            out
+        })
+    }
+    /// Rust constant names for [`Self::tokens`], in the same order. Names that are not valid
+    /// identifiers are sanitized with [`rust_ident`]. When names collide, see
+    /// [`Self::rust_collisions`], the first token that needed no sanitizing keeps the name and
+    /// the others are numbered in order, e.g. `SIZE2_2`.
+    pub(crate) fn rust_names(&self) -> Vec<String> {
+        let raw = self
+            .tokens()
+            .into_iter()
+            .map(|(path, _)| rust_path(&self.options.naming, &path))
+            .collect_vec();
+        unique_idents(&raw, rust_ident)
+    }
+    /// Rust constant names that more than one token maps to, with the tokens. [`Self::to_rust`]
+    /// tells them apart with a numeric suffix.
+    pub fn rust_collisions(&self) -> Vec<(String, Vec<TokenPath>)> {
+        self.iter()
+            .filter(|(_, token)| matches!(token, TokenOrGroup::Token { .. }))
+            .map(|(path, _)| (rust_ident(&rust_path(&self.options.naming, &path.0)), path))
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .sorted()
+            .collect()
     }
     fn get_value(&self, path: &[String]) -> Option<&TokenValue> {
-        self.body.get_value(self, path)
+        self.body
+            .get_value(path)
+            .or_else(|| self.options.fallback.as_ref()?.get_value(path))
+    }
+    /// A single field of a composite token, e.g. `typography/body/fontSize`.
+    pub(crate) fn get_field(&self, path: &[String]) -> Option<&Expression> {
+        let (field, path) = path.split_last()?;
+        match self.get_value(path)? {
+            TokenValue::Dict(dict) => dict.get(field),
+            TokenValue::Single(_) => None,
+        }
+    }
+    /// Whether `expr` reaches into composite token fields, which have no CSS property of their
+    /// own and so have to be written out resolved.
+    fn refs_fields(&self, expr: &Expression) -> bool {
+        expr.refs()
+            .iter()
+            .any(|x| self.get_value(x).is_none() && self.get_field(x).is_some())
+    }
+    pub(crate) fn get_token(&self, path: &[String]) -> Option<&TokenOrGroup> {
+        path.iter().try_fold(&self.body, |node, key| match node {
+            TokenOrGroup::Group(group) => group.get(key),
+            TokenOrGroup::Token { .. } => None,
+        })
+    }
+    /// Resolves the single-valued token at `path`, including its modifiers.
+    pub(crate) fn resolve(&self, path: &[String]) -> Option<Value> {
+        match self.get_token(path)? {
+            TokenOrGroup::Token {
+                value: TokenValue::Single(expr),
+                extensions,
+                ..
+            } => Some(resolve_single(self, expr, extensions)),
+            _ => None,
+        }
+    }
+    /// Every token with its value resolved, in document order.
+    pub fn flatten(&self) -> Vec<ResolvedToken<'_>> {
+        self.iter()
+            .filter_map(|(path, token)| {
+                let TokenOrGroup::Token {
+                    value,
+                    type_,
+                    extensions,
+                    description,
+                    deprecated,
+                } = token
+                else {
+                    return None;
+                };
+                let resolved = match value {
+                    TokenValue::Single(expr) => {
+                        ResolvedValue::Single(resolve_single(self, expr, extensions))
+                    }
+                    TokenValue::Dict(dict) => ResolvedValue::Dict(
+                        dict.iter()
+                            .map(|(k, v)| (k.clone(), v.get_value(self)))
+                            .collect(),
+                    ),
+                };
+                Some(ResolvedToken {
+                    source: self.source(&path),
+                    path,
+                    raw: value,
+                    value: resolved,
+                    type_: type_.clone(),
+                    description: description.as_deref(),
+                    deprecated: deprecated.as_ref(),
+                })
+            })
+            .collect()
+    }
+    /// All tokens in document order.
+    pub fn iter(&self) -> impl Iterator<Item = (TokenPath, &TokenOrGroup)> {
+        self.tokens().into_iter()
+    }
+    /// Parses and resolves an expression against this set, e.g. `{Space.base} * 2`.
+    /// Fails on syntax errors, missing references, values that can't be combined and, in
+    /// [`ResolutionMode::Strict`], units that don't combine.
+    pub fn eval(&self, expr: &str) -> Result<Value, String> {
+        let expr = parse_expression(expr)?;
+        if let Some(missing) = self.missing_in(&expr, &mut Vec::new()) {
+            return Err(format!("Unresolved reference {{{}}}", missing.join(".")));
+        }
+        expr.try_get_value(self)
+    }
+    /// Looks up a token or group by its `/`-separated path, e.g. `Brand/Primary`.
+    pub fn get(&self, path: &str) -> Option<&TokenOrGroup> {
+        self.get_token(&TokenPath::parse(path))
+    }
+    /// The resolved value of the token at a `/`-separated path as CSS, with composite fields
+    /// written as `key: value; ...`.
+    pub fn resolve_css(&self, path: &str) -> Option<String> {
+        match self.get(path)? {
+            token @ TokenOrGroup::Token { .. } => Some(token.resolve_css(self)),
+            TokenOrGroup::Group(_) => None,
+        }
+    }
+    pub fn filter_by_type(
+        &self,
+        type_: TokenType,
+    ) -> impl Iterator<Item = (TokenPath, &TokenOrGroup)> {
+        self.iter().filter(
+            move |(_, token)| matches!(token, TokenOrGroup::Token { type_: x, .. } if *x == type_),
+        )
+    }
+    pub(crate) fn tokens(&self) -> Vec<(TokenPath, &TokenOrGroup)> {
+        let mut out = Vec::new();
+        self.body.collect_tokens(&mut Vec::new(), &mut out);
+        out
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// `$deprecated`: either a flag or the reason/replacement to show.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Deprecated {
+    Flag(bool),
+    Message(String),
+}
+impl Deprecated {
+    /// `None` when not deprecated, otherwise the optional message.
+    pub fn note(&self) -> Option<Option<&str>> {
+        match self {
+            Deprecated::Flag(false) => None,
+            Deprecated::Flag(true) => Some(None),
+            Deprecated::Message(x) => Some(Some(x)),
+        }
+    }
+    fn to_rust(deprecated: &Option<Deprecated>) -> String {
+        match deprecated.as_ref().and_then(|x| x.note()) {
+            None => String::new(),
+            Some(None) => "#[deprecated]\n".to_string(),
+            Some(Some(note)) => format!("#[deprecated(note = {note:?})]\n"),
+        }
+    }
+    fn to_css(deprecated: &Option<Deprecated>) -> String {
+        match deprecated.as_ref().and_then(|x| x.note()) {
+            None => String::new(),
+            Some(None) => " /* deprecated */".to_string(),
+            Some(Some(note)) => format!(" /* deprecated: {} */", note.replace("*/", "* /")),
+        }
+    }
+}
+
+/// A token with its value resolved, see [`DesignTokens::flatten`].
+#[derive(Debug)]
+pub struct ResolvedToken<'a> {
+    pub path: TokenPath,
+    /// The value as written, before resolving references and modifiers.
+    pub raw: &'a TokenValue,
+    pub value: ResolvedValue,
+    pub type_: TokenType,
+    pub description: Option<&'a str>,
+    pub deprecated: Option<&'a Deprecated>,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedValue {
+    Single(Value),
+    /// Composite tokens, sorted by field.
+    Dict(std::collections::BTreeMap<String, Value>),
+}
+
+/// The group keys leading to a token, displayed `/`-separated. Clones share the keys, see
+/// [`graph::PathInterner`] for sharing equal paths built separately.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenPath(pub Arc<[String]>);
+impl TokenPath {
+    pub fn parse(path: &str) -> Self {
+        TokenPath(path.split('/').map(|x| x.to_string()).collect())
+    }
+    /// Whether this path is `prefix` or lies under it, e.g. `Brand/Primary` under `Brand`.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.0.starts_with(&TokenPath::parse(prefix).0)
+    }
+    /// Whether this path matches a `/`-separated glob, where `*` matches any part of a key and
+    /// `**` any number of keys, e.g. `Brand/*` or `**/Primary`.
+    pub fn matches(&self, pattern: &str) -> bool {
+        fn keys(pattern: &[&str], path: &[String]) -> bool {
+            match pattern.split_first() {
+                None => path.is_empty(),
+                Some((&"**", rest)) => (0..=path.len()).any(|i| keys(rest, &path[i..])),
+                Some((first, rest)) => path
+                    .split_first()
+                    .is_some_and(|(key, tail)| key_matches(first, key) && keys(rest, tail)),
+            }
+        }
+        fn key_matches(pattern: &str, key: &str) -> bool {
+            match pattern.split_once('*') {
+                None => pattern == key,
+                Some((head, tail)) => key.strip_prefix(head).is_some_and(|key| {
+                    (0..=key.len())
+                        .filter(|&i| key.is_char_boundary(i))
+                        .any(|i| key_matches(tail, &key[i..]))
+                }),
+            }
+        }
+        keys(&pattern.split('/').collect_vec(), &self.0)
+    }
+}
+impl From<Vec<String>> for TokenPath {
+    fn from(path: Vec<String>) -> Self {
+        TokenPath(path.into())
+    }
+}
+impl From<&[String]> for TokenPath {
+    fn from(path: &[String]) -> Self {
+        TokenPath(path.into())
+    }
+}
+impl std::borrow::Borrow<[String]> for TokenPath {
+    fn borrow(&self) -> &[String] {
+        &self.0
+    }
+}
+impl std::ops::Deref for TokenPath {
+    type Target = [String];
+    fn deref(&self) -> &[String] {
+        &self.0
+    }
+}
+impl std::fmt::Display for TokenPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("/"))
+    }
+}
+impl serde::Serialize for TokenPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum TokenType {
     #[default]
     None,
-    #[serde(rename = "border")]
+    Color,
+    Number,
+    Dimension,
+    String,
+    Boolean,
+    /// A file path or URL, e.g. an icon, written as `url()` in CSS.
+    Asset,
     Border,
-    #[serde(rename = "typography", alias = "custom-fontStyle")]
     Typography,
-    #[serde(other)]
-    Other,
+    Duration,
+    CubicBezier,
+    Transition,
+    StrokeStyle,
+    /// A `$type` this crate has no special handling for, kept verbatim.
+    Other(String),
+}
+impl TokenType {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "color" => TokenType::Color,
+            "number" => TokenType::Number,
+            "dimension" => TokenType::Dimension,
+            "string" => TokenType::String,
+            "boolean" => TokenType::Boolean,
+            "asset" => TokenType::Asset,
+            "border" => TokenType::Border,
+            "duration" => TokenType::Duration,
+            "cubicBezier" => TokenType::CubicBezier,
+            "transition" => TokenType::Transition,
+            "strokeStyle" => TokenType::StrokeStyle,
+            "typography" | "custom-fontStyle" => TokenType::Typography,
+            _ => TokenType::Other(name.to_string()),
+        }
+    }
+    fn is_none(&self) -> bool {
+        *self == TokenType::None
+    }
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenType::None => "none",
+            TokenType::Color => "color",
+            TokenType::Number => "number",
+            TokenType::Dimension => "dimension",
+            TokenType::String => "string",
+            TokenType::Boolean => "boolean",
+            TokenType::Asset => "asset",
+            TokenType::Border => "border",
+            TokenType::Duration => "duration",
+            TokenType::CubicBezier => "cubicBezier",
+            TokenType::Transition => "transition",
+            TokenType::StrokeStyle => "strokeStyle",
+            TokenType::Typography => "typography",
+            TokenType::Other(name) => name,
+        }
+    }
+}
+impl Serialize for TokenType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for TokenType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(TokenType::from_name(&String::deserialize(deserializer)?))
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// Deserialized in one pass without buffering: objects with a `$value` are tokens, other objects
+/// groups.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum TokenOrGroup {
     Token {
-        #[serde(alias = "$value")]
+        #[serde(rename = "$value", alias = "value")]
         value: TokenValue,
-        #[serde(rename = "type", alias = "$type")]
+        #[serde(
+            rename = "$type",
+            alias = "type",
+            default,
+            skip_serializing_if = "TokenType::is_none"
+        )]
         type_: TokenType,
-        #[serde(rename = "$extensions")]
+        #[serde(
+            rename = "$extensions",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
         extensions: Option<Extensions>,
+        #[serde(
+            rename = "$description",
+            alias = "description",
+            skip_serializing_if = "Option::is_none"
+        )]
+        description: Option<String>,
+        #[serde(
+            rename = "$deprecated",
+            alias = "deprecated",
+            skip_serializing_if = "Option::is_none"
+        )]
+        deprecated: Option<Deprecated>,
     },
     Group(IndexMap<String, TokenOrGroup>),
 }
 impl TokenOrGroup {
-    fn to_css(&self, tokens: &DesignTokens, root_class: &str, path: &str) -> String {
+    /// Reads literals by the `$type` of their token: `"true"` and `"false"` in `boolean` tokens as
+    /// booleans, numbers or colors in `string` tokens as text and paths in `asset` tokens as
+    /// written instead of as divisions.
+    fn apply_types(&mut self) {
         match self {
+            TokenOrGroup::Group(group) => group.values_mut().for_each(Self::apply_types),
+            TokenOrGroup::Token {
+                value: TokenValue::Single(expr),
+                type_: TokenType::Asset,
+                ..
+            } => {
+                if let Expression::Mul(..) | Expression::Div(..) | Expression::Value(_) = expr {
+                    *expr = Expression::Value(Value::Any(expr.to_source().replace(" / ", "/")));
+                }
+            }
             TokenOrGroup::Token {
-                value,
+                value: TokenValue::Single(expr),
+                type_: TokenType::CubicBezier,
+                ..
+            } => cubic_bezier(expr),
+            TokenOrGroup::Token {
+                value: TokenValue::Dict(dict),
+                type_: TokenType::Transition,
+                ..
+            } => {
+                if let Some(expr) = dict.get_mut("timingFunction") {
+                    cubic_bezier(expr);
+                }
+            }
+            TokenOrGroup::Token {
+                value: TokenValue::Single(Expression::Value(value)),
                 type_,
-                extensions,
-            } => match value {
-                TokenValue::Single(value) => {
-                    let value = match extensions {
-                        Some(Extensions::StudioTokens(ext)) => ext.to_css(&value.get_value(tokens)),
-                        _ => value.to_css(tokens),
-                    };
-                    format!(".{root_class} {{ -{path}: {}; }}", value)
+                ..
+            } => {
+                *value = match (&*type_, &*value) {
+                    (TokenType::Boolean, Value::Any(x)) if x == "true" || x == "false" => {
+                        Value::Bool(x == "true")
+                    }
+                    (TokenType::String, Value::Any(_)) => return,
+                    (TokenType::String, value) => Value::Any(value.to_css()),
+                    _ => return,
                 }
-                TokenValue::Dict(dict) => {
-                    let value = dict
-                        .iter()
-                        .map(|(key, value)| css_entry(tokens, type_, key, value))
-                        .join("\n");
-                    format!(".{root_class} .{} {{\n{}\n}}", &path[1..], value)
+            }
+            TokenOrGroup::Token { .. } => {}
+        }
+    }
+    /// Every expression the token's value depends on, including modifier arguments.
+    pub(crate) fn exprs(&self) -> Vec<&Expression> {
+        let TokenOrGroup::Token {
+            value, extensions, ..
+        } = self
+        else {
+            return Vec::new();
+        };
+        let mut exprs = match value {
+            TokenValue::Single(expr) => vec![expr],
+            TokenValue::Dict(dict) => dict.values().collect(),
+        };
+        if let Some(Extensions {
+            studio_tokens: Some(ext),
+            ..
+        }) = extensions
+        {
+            exprs.extend(ext.modifiers().iter().filter_map(|x| x.color.as_ref()));
+        }
+        exprs
+    }
+    fn to_css(
+        &self,
+        tokens: &DesignTokens,
+        options: &CssOptions,
+        selector: &str,
+        path: &[String],
+    ) -> String {
+        let mut out = String::new();
+        self.write_css(&mut out, tokens, options, selector, &mut path.to_vec())
+            .unwrap();
+        out
+    }
+    /// Writes the rules of this token, or of every token in this group separated by newlines.
+    fn write_css(
+        &self,
+        out: &mut impl fmt::Write,
+        tokens: &DesignTokens,
+        options: &CssOptions,
+        selector: &str,
+        path: &mut Vec<String>,
+    ) -> fmt::Result {
+        let TokenOrGroup::Token {
+            value,
+            type_,
+            extensions,
+            deprecated,
+            ..
+        } = self
+        else {
+            let TokenOrGroup::Group(group) = self else {
+                unreachable!()
+            };
+            for (i, (key, value)) in group.iter().enumerate() {
+                if i > 0 {
+                    out.write_char('\n')?;
                 }
-            },
-            TokenOrGroup::Group(group) => group
-                .iter()
-                .map(|(key, value)| {
-                    value.to_css(tokens, root_class, &format!("{path}-{}", slugify_css(key)))
-                })
-                .join("\n"),
+                path.push(key.clone());
+                value.write_css(out, tokens, options, selector, path)?;
+                path.pop();
+            }
+            return Ok(());
+        };
+        let naming = &tokens.options.naming;
+        let property = options.property_name(naming, path);
+        if options.provenance {
+            out.write_str(&css_provenance(tokens, path, self))?;
         }
+        let deprecated = Deprecated::to_css(deprecated);
+        match value {
+            TokenValue::Single(value) => {
+                let css = match extensions {
+                    _ if tokens.unresolvable(self) => value.to_css_with(options, naming),
+                    _ if Extensions::modifies(extensions, tokens) => {
+                        resolve_single(tokens, value, extensions).to_css_with(options)
+                    }
+                    _ if tokens.refs_fields(value) => value.get_value(tokens).to_css_with(options),
+                    _ => value.to_css_in(tokens, options),
+                };
+                match (type_, value) {
+                    (TokenType::Asset, Expression::Value(Value::Any(_))) => {
+                        write!(
+                            out,
+                            "{selector} {{ {property}: url({css:?});{deprecated} }}"
+                        )
+                    }
+                    _ => write!(out, "{selector} {{ {property}: {css};{deprecated} }}"),
+                }
+            }
+            TokenValue::Dict(dict) => {
+                if let Some(value) = composite::css_shorthand(tokens, options, type_, dict) {
+                    return write!(out, "{selector} {{ {property}: {value};{deprecated} }}");
+                }
+                let name = naming.name("css", path, None, "-");
+                writeln!(out, "{selector} .{name} {{{deprecated}")?;
+                for (i, (key, value)) in dict.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char('\n')?;
+                    }
+                    out.write_str(&css_entry(tokens, options, type_, key, value))?;
+                }
+                out.write_str("\n}")
+            }
+        }
+    }
+    /// The type and value of the Rust constant generated for a token.
+    pub(crate) fn rust_value(
+        &self,
+        tokens: &DesignTokens,
+        options: &RustOptions,
+    ) -> (String, String) {
+        let TokenOrGroup::Token {
+            value,
+            type_,
+            extensions,
+            ..
+        } = self
+        else {
+            unreachable!()
+        };
+        match value {
+            TokenValue::Single(value) => {
+                let value = resolve_single(tokens, value, extensions);
+                (
+                    value.to_rust_type_with(options).to_string(),
+                    value.to_rust_with(options),
+                )
+            }
+            TokenValue::Dict(dict) => {
+                if let Some(Ok((type_, value))) = composite::rust_composite(tokens, type_, dict) {
+                    return (type_.to_string(), value);
+                }
+                let value = dict
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("({:?}, {})", key, value.get_value(tokens).to_rust_string())
+                    })
+                    .join(", ");
+                ("&[(&str, &str)]".to_string(), format!("&[{value}]"))
+            }
+        }
+    }
+    fn to_rust(
+        &self,
+        tokens: &DesignTokens,
+        options: &RustOptions,
+        path: &[String],
+        name: &str,
+    ) -> String {
+        let TokenOrGroup::Token {
+            value: raw,
+            extensions,
+            description,
+            deprecated,
+            ..
+        } = self
+        else {
+            unreachable!()
+        };
+        let value = match raw {
+            TokenValue::Single(value) => Some(resolve_single(tokens, value, extensions)),
+            TokenValue::Dict(_) => None,
+        };
+        let (type_, literal) = self.rust_value(tokens, options);
+        format!(
+            "{}{}pub const {name}: {type_} = {literal};",
+            rust_doc(tokens, description, path, raw, value.as_ref()),
+            Deprecated::to_rust(deprecated),
+        )
     }
-    fn to_rust(&self, tokens: &DesignTokens, path: &str) -> String {
+    fn sort_keys(&mut self) {
         match self {
             TokenOrGroup::Token {
-                value, extensions, ..
-            } => match value {
-                TokenValue::Single(value) => {
-                    let value = match extensions {
-                        Some(Extensions::StudioTokens(ext)) => {
-                            ext.to_rust(&value.get_value(tokens))
+                value: TokenValue::Dict(dict),
+                ..
+            } => dict.sort_keys(),
+            TokenOrGroup::Token { .. } => {}
+            TokenOrGroup::Group(group) => {
+                group.sort_keys();
+                group.values_mut().for_each(Self::sort_keys);
+            }
+        }
+    }
+    fn merge(&mut self, other: TokenOrGroup) {
+        match (self, other) {
+            (TokenOrGroup::Group(group), TokenOrGroup::Group(other)) => {
+                for (key, value) in other {
+                    match group.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            group.insert(key, value);
                         }
-                        _ => value.get_value(tokens),
-                    };
-                    format!(
-                        "pub const {path}: {} = {};",
-                        value.to_rust_type(),
-                        value.to_rust()
-                    )
+                    }
                 }
-                TokenValue::Dict(dict) => {
-                    let value = dict
-                        .iter()
-                        .map(|(key, value)| {
-                            format!(
-                                "(\"{}\", {})",
-                                key,
-                                value.get_value(tokens).to_rust_string()
-                            )
-                        })
-                        .join(", ");
-                    format!(
-                        "pub const {path}: &'static [(&'static str, &'static str)] = &[{}];",
-                        value
-                    )
+            }
+            (this, other) => *this = other,
+        }
+    }
+    fn collect_tokens<'a>(
+        &'a self,
+        path: &mut Vec<String>,
+        out: &mut Vec<(TokenPath, &'a TokenOrGroup)>,
+    ) {
+        match self {
+            TokenOrGroup::Token { .. } => out.push((TokenPath::from(&path[..]), self)),
+            TokenOrGroup::Group(group) => {
+                for (key, value) in group {
+                    path.push(key.clone());
+                    value.collect_tokens(path, out);
+                    path.pop();
                 }
+            }
+        }
+    }
+    /// The fully resolved value as CSS, resolving references against `tokens`. Panics on
+    /// groups.
+    pub fn resolve_css(&self, tokens: &DesignTokens) -> String {
+        match self {
+            TokenOrGroup::Token {
+                value, extensions, ..
+            } => match value {
+                TokenValue::Single(value) => resolve_single(tokens, value, extensions).to_css(),
+                TokenValue::Dict(dict) => dict
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value.get_value(tokens).to_css()))
+                    .join("; "),
             },
-            TokenOrGroup::Group(group) => group
-                .iter()
-                .map(|(key, value)| {
-                    let key = slugify_rs(key).to_case(Case::UpperFlat);
-                    value.to_rust(
-                        tokens,
-                        &if path.is_empty() {
-                            key
-                        } else {
-                            format!("{path}_{}", key)
-                        },
-                    )
-                })
-                .join("\n"),
+            TokenOrGroup::Group(_) => panic!("Can't resolve a group"),
+        }
+    }
+    /// Like [`TokenOrGroup::resolve_css`], but fails on missing references and values that
+    /// can't be computed.
+    pub fn try_resolve_css(&self, tokens: &DesignTokens) -> Result<String, String> {
+        match self.resolve_error(tokens) {
+            Some(err) => Err(err),
+            None => Ok(self.resolve_css(tokens)),
+        }
+    }
+    /// Why the token can't be resolved against `tokens`, if it can't.
+    pub(crate) fn resolve_error(&self, tokens: &DesignTokens) -> Option<String> {
+        match tokens.missing_ref(self) {
+            Some(missing) => Some(format!("unresolved reference {{{}}}", missing.join("."))),
+            None => self
+                .exprs()
+                .into_iter()
+                .find_map(|x| x.try_get_value(tokens).err()),
         }
     }
-    fn get_value(&self, tokens: &DesignTokens, path: &[String]) -> Option<&TokenValue> {
+    fn get_value(&self, path: &[String]) -> Option<&TokenValue> {
         match self {
-            TokenOrGroup::Token { value, .. } => {
-                assert_eq!(path.len(), 0);
-                Some(value)
-            }
-            TokenOrGroup::Group(group) => group.get(&path[0])?.get_value(tokens, &path[1..]),
+            TokenOrGroup::Token { value, .. } if path.is_empty() => Some(value),
+            TokenOrGroup::Token { .. } => None,
+            TokenOrGroup::Group(group) => group.get(path.first()?)?.get_value(&path[1..]),
         }
     }
 }
-fn css_entry(tokens: &DesignTokens, type_: &TokenType, key: &str, value: &Expression) -> String {
+/// Reads a list of four numbers as the control points of a `cubic-bezier()`.
+fn cubic_bezier(expr: &mut Expression) {
+    let Expression::List(items) = expr else {
+        return;
+    };
+    let number = |x: &Expression| match x {
+        Expression::Value(Value::Number(v, NumberType::None)) => Some(*v),
+        _ => None,
+    };
+    if let Some(&[a, b, c, d]) = items
+        .iter()
+        .map(number)
+        .collect::<Option<Vec<_>>>()
+        .as_deref()
+    {
+        *expr = Expression::Value(Value::CubicBezier([a, b, c, d]));
+    }
+}
+/// Resolves a single-valued token, applying its modifier extensions.
+pub(crate) fn resolve_single(
+    tokens: &DesignTokens,
+    value: &Expression,
+    extensions: &Option<Extensions>,
+) -> Value {
+    match extensions {
+        Some(ext) => ext.apply(tokens, value.get_value(tokens)),
+        None => value.get_value(tokens),
+    }
+}
+pub(crate) fn css_entry(
+    tokens: &DesignTokens,
+    options: &CssOptions,
+    type_: &TokenType,
+    key: &str,
+    value: &Expression,
+) -> String {
     let prop = css_property(type_, key);
-    format!("{}: {};", prop, css_value(tokens, &prop, value))
+    if tokens.refs_fields(value) && tokens.missing_in(value, &mut Vec::new()).is_none() {
+        let value = Expression::Value(value.get_value(tokens));
+        return format!("{}: {};", prop, css_value(tokens, options, &prop, &value));
+    }
+    format!("{}: {};", prop, css_value(tokens, options, &prop, value))
 }
-fn css_property(type_: &TokenType, key: &str) -> String {
+pub(crate) fn css_property(type_: &TokenType, key: &str) -> String {
     match type_ {
-        TokenType::Border => match key {
-            "color" => "border-color".to_string(),
-            "width" => "border-width".to_string(),
-            "style" => "border-style".to_string(),
-            _ => key.to_case(Case::Kebab),
-        },
         TokenType::Typography => match key {
             "textCase" | "text-case" => "text-transform".to_string(),
             _ => key.to_case(Case::Kebab),
@@ -186,31 +1439,29 @@ fn css_property(type_: &TokenType, key: &str) -> String {
         _ => key.to_case(Case::Kebab),
     }
 }
-fn css_value(tokens: &DesignTokens, prop: &str, value: &Expression) -> String {
+fn css_value(
+    tokens: &DesignTokens,
+    options: &CssOptions,
+    prop: &str,
+    value: &Expression,
+) -> String {
     if prop == "font-weight" {
-        return value.to_css(tokens);
+        return value.to_css_in(tokens, options);
     }
     match value {
         Expression::Value(Value::Number(v, NumberType::None)) => {
-            Expression::Value(Value::Number(*v, NumberType::Pixels)).to_css(tokens)
+            Value::Number(*v, NumberType::Pixels).to_css_with(options)
         }
-        _ => value.to_css(tokens),
+        _ => value.to_css_in(tokens, options),
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum TokenValue {
     Single(Expression),
-    Dict(HashMap<String, Expression>),
-}
-impl TokenValue {
-    fn get_value(&self, tokens: &DesignTokens) -> Value {
-        match self {
-            TokenValue::Single(expr) => expr.get_value(tokens),
-            _ => panic!("Can't resolve"),
-        }
-    }
+    /// Fields of a composite token, in source order.
+    Dict(IndexMap<String, Expression>),
 }
 impl Default for TokenValue {
     fn default() -> Self {
@@ -218,30 +1469,799 @@ impl Default for TokenValue {
     }
 }
 
-pub(crate) fn slugify(s: &str, sep: &str) -> String {
-    // let chars = s.chars().map(|c| c.is_ascii_alphanumeric()).collect::<String>();
-    deunicode::deunicode(
-        &s.replace(',', "c")
-            .replace('+', "p")
-            .replace('.', "d")
-            .replace('(', "_")
-            .replace(')', "_")
-            .replace(' ', sep)
-            .to_ascii_lowercase(),
+fn css_escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') || !c.is_ascii() => {
+                c.to_string()
+            }
+            c if c.is_ascii_control() => format!("\\{:x} ", c as u32),
+            c => format!("\\{c}"),
+        })
+        .collect()
+}
+/// Doc comment lines for a generated constant: the description, then the token path and value,
+/// followed by the expression and file it came from when they add anything.
+fn rust_doc(
+    tokens: &DesignTokens,
+    description: &Option<String>,
+    path: &[String],
+    raw: &TokenValue,
+    value: Option<&Value>,
+) -> String {
+    let mut out = String::new();
+    if let Some(description) = description {
+        for line in description.lines() {
+            out += &format!("/// {line}\n");
+        }
+        out += "///\n";
+    }
+    out += &format!("/// `{}`", path.join("/"));
+    if let Some(value) = value {
+        let value = value.to_css();
+        out += &format!(": `{value}`");
+        let raw = emit::raw_value(raw);
+        if raw != value {
+            out += &format!(", from `{raw}`");
+        }
+    }
+    if let Some(file_name) = tokens.source_file(path) {
+        out += &format!(" in `{file_name}`");
+    }
+    out + "\n"
+}
+/// `/* file: path = expression */` for a CSS declaration.
+fn css_provenance(tokens: &DesignTokens, path: &[String], token: &TokenOrGroup) -> String {
+    let TokenOrGroup::Token { value, .. } = token else {
+        return String::new();
+    };
+    format!(
+        "/* {}: {} = {} */\n",
+        tokens.source_file(path).unwrap_or(tokens.get_name()),
+        path.join("/"),
+        emit::raw_value(value).replace("*/", "*\\/")
     )
 }
-pub(crate) fn slugify_rs(s: &str) -> String {
-    slugify(s, "_")
+/// The unsanitized Rust constant name for a token path, e.g. `BORDER_M`.
+pub(crate) fn rust_path(naming: &Naming, path: &[String]) -> String {
+    naming.name("rust", path, Some(Case::UpperFlat), "_")
 }
-pub(crate) fn slugify_css(s: &str) -> String {
-    slugify(s, "-")
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield", "union",
+];
+
+/// Makes `s` an identifier in Rust, Kotlin and Swift, keywords aside: invalid characters become
+/// `_` and a leading digit gets a `_` prefix.
+pub(crate) fn sanitize_ident(s: &str) -> String {
+    let x: String = s
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if x.is_empty() || x.starts_with(|c: char| c.is_ascii_digit()) || x == "_" {
+        format!("_{x}")
+    } else {
+        x
+    }
+}
+
+/// `raw` names made identifiers with `ident`, in the same order. When they collide, the first
+/// name that needed no sanitizing keeps it and the others are numbered in order, e.g. `SIZE2_2`.
+pub(crate) fn unique_idents(raw: &[String], ident: impl Fn(&str) -> String) -> Vec<String> {
+    let mut owners: HashMap<&str, usize> = HashMap::new();
+    for (i, raw) in raw.iter().enumerate() {
+        if ident(raw) == *raw {
+            owners.entry(raw).or_insert(i);
+        }
+    }
+    let mut taken = HashSet::new();
+    raw.iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let base = ident(raw);
+            let mut name = base.clone();
+            let mut n = 1;
+            while owners.get(name.as_str()).is_some_and(|x| *x != i) || !taken.insert(name.clone())
+            {
+                n += 1;
+                name = format!("{base}_{n}");
+            }
+            name
+        })
+        .collect()
+}
+
+/// Makes `s` a valid Rust identifier with [`sanitize_ident`]; keywords become raw identifiers
+/// (or get a `_` suffix where `r#` is not allowed).
+pub(crate) fn rust_ident(s: &str) -> String {
+    let x = sanitize_ident(s);
+    if ["self", "Self", "super", "crate"].contains(&x.as_str()) {
+        format!("{x}_")
+    } else if RUST_KEYWORDS.contains(&x.as_str()) {
+        format!("r#{x}")
+    } else {
+        x
+    }
 }
 
 #[test]
+#[cfg(feature = "ambient-tokens")]
 fn test() {
-    let tokens = get_design_tokens();
-    for tokens in tokens {
+    for tokens in design_tokens() {
         println!("{}", tokens.to_css());
         println!("{}", tokens.to_rust());
     }
+    assert!(std::ptr::eq(design_tokens(), design_tokens()));
+    assert_eq!(get_design_tokens().len(), design_tokens().len());
+}
+
+#[test]
+fn test_color_format() {
+    let tokens = parse_design_tokens(r##"{ "red": { "$value": "#ff0000" } }"##, None).unwrap();
+    let options = CssOptions {
+        color_format: ColorFormat::Oklch,
+        ..Default::default()
+    };
+    assert_eq!(
+        tokens[0].to_css_with(":root", &options),
+        ":root { --red: oklch(62.79% 0.2577 29.22); }"
+    );
+}
+
+#[test]
+fn test_rem_base() {
+    let tokens = parse_design_tokens(
+        r#"{
+            "gap": { "$value": "24px" },
+            "body": { "$value": { "fontSize": 14, "fontWeight": 400 }, "$type": "typography" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let options = CssOptions {
+        rem_base: Some(16.),
+        ..Default::default()
+    };
+    let css = tokens[0].to_css_with(":root", &options);
+    assert!(css.contains("--gap: 1.5rem;"));
+    assert!(css.contains("font-size: 0.875rem;"));
+    assert!(css.contains("font-weight: 400;"));
+    assert!(tokens[0].to_rust().contains("pub const GAP: f32 = 24.;"));
+}
+
+#[test]
+#[cfg(feature = "ambient-tokens")]
+fn test_themed() {
+    let tokens = design_tokens();
+    let options = CssOptions::default();
+    let css = to_css_themed(&tokens[..2], "light", ThemeSelector::Both, &options);
+    assert!(css.starts_with(":root { --border-m: 4; }"));
+    assert!(css.contains("@media (prefers-color-scheme: dark) {\n:root:not([data-theme]) {"));
+    assert!(css.contains("[data-theme=\"dark\"] { --border-m: 4; }"));
+    let css = to_css_themed(
+        &tokens[..2],
+        "light",
+        ThemeSelector::DataAttribute,
+        &options,
+    );
+    assert!(!css.contains("prefers-color-scheme"));
+}
+
+#[test]
+fn test_rust_ident() {
+    for kw in RUST_KEYWORDS {
+        let x = rust_ident(kw);
+        assert!(x == format!("r#{kw}") || x == format!("{kw}_"), "{x}");
+    }
+    assert_eq!(rust_ident("2XL"), "_2XL");
+    assert_eq!(rust_ident("A%B"), "A_B");
+    assert_eq!(rust_ident(""), "_");
+    assert_eq!(rust_ident("_"), "__");
+
+    let tokens = parse_design_tokens(
+        r#"{
+            "2xl": { "$value": 1 },
+            "type": { "match": { "$value": 2 } },
+            "a%b": { "$value": 3 },
+            "a": { "b": { "$value": 4 } }
+        }"#,
+        Some("Self.json"),
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const _2XL: f32 = 1"));
+    assert!(rust.contains("pub const TYPE_MATCH: f32 = 2"));
+    assert!(rust.contains("pub const A_B_2: f32 = 3"));
+    assert!(rust.contains("/// `a/b`: `4` in `Self.json`\npub const A_B: f32 = 4"));
+    assert_eq!(tokens[0].get_name_rust(), "SELF");
+
+    let tokens = parse_design_tokens(
+        r#"{ "Size 2": { "$value": 1 }, "size-2": { "$value": 2 }, "size2": { "$value": 3 } }"#,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const SIZE2: f32 = 1"));
+    assert!(rust.contains("pub const SIZE2_2: f32 = 2"));
+    assert!(rust.contains("pub const SIZE2_3: f32 = 3"));
+    assert_eq!(
+        tokens[0].rust_collisions(),
+        [(
+            "SIZE2".to_string(),
+            ["Size 2", "size-2", "size2"].map(TokenPath::parse).to_vec()
+        )]
+    );
+}
+
+#[test]
+fn test_merge() {
+    let layers = [
+        r##"{ "color": { "primary": { "$value": "#ff0000" }, "text": { "$value": "{color.primary}" } }, "size": { "$value": 4 } }"##,
+        r##"{ "color": { "primary": { "$value": "#0000ff" }, "accent": { "$value": "#00ff00" } } }"##,
+    ];
+    let merged = DesignTokens::merge(
+        layers
+            .iter()
+            .zip(["base.json", "brand.json"])
+            .flat_map(|(x, file)| parse_design_tokens(x, Some(file)).unwrap())
+            .collect(),
+    );
+    assert_eq!(
+        merged.to_css_with_selector(":root"),
+        ":root { --color-primary: #0000ff; }\n:root { --color-text: var(--color-primary); }\n:root { --color-accent: #00ff00; }\n:root { --size: 4; }"
+    );
+    assert!(merged
+        .to_rust()
+        .contains("pub const COLOR_TEXT: &str = \"#0000ff\";"));
+    assert_eq!(merged.get_name(), "base");
+    let source = |path| merged.source(&TokenPath::parse(path)).to_string();
+    assert_eq!(source("color/primary"), "brand.json#/color/primary");
+    assert_eq!(source("color/text"), "base.json#/color/text");
+
+    let list = r##"[{ "fileName": "a", "body": {} }, { "fileName": "b", "body": { "a/b": { "$value": 1 } } }]"##;
+    let sets = parse_design_tokens(list, None).unwrap();
+    assert_eq!(sets[1].flatten()[0].source.pointer, "/1/body/a~1b");
+}
+
+#[test]
+fn test_query() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "Brand": { "Primary": { "$value": "#ff0000", "$type": "color" }, "Radius": { "$value": 4, "$type": "number" } },
+            "Text": { "$value": "#000000", "$type": "color" }
+        }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let brand_colors = tokens
+        .filter_by_type(TokenType::Color)
+        .filter(|(path, _)| path.starts_with("Brand"))
+        .map(|(path, _)| path.to_string())
+        .collect_vec();
+    assert_eq!(brand_colors, ["Brand/Primary"]);
+    assert_eq!(tokens.iter().count(), 3);
+    assert!(matches!(tokens.get("Brand"), Some(TokenOrGroup::Group(_))));
+    assert!(tokens.get("Brand/Missing").is_none());
+    assert_eq!(tokens.resolve_css("Brand/Radius").unwrap(), "4");
+    assert!(tokens.resolve_css("Brand").is_none());
+}
+
+#[test]
+fn test_flatten() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "red": { "$value": "#ff0000", "$type": "color", "$description": "Errors" },
+            "danger": { "$value": "{red}", "$type": "color" },
+            "body": { "$value": { "fontSize": 16, "fontWeight": 400 }, "$type": "typography" }
+        }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let flat = tokens.flatten();
+    assert_eq!(flat.len(), 3);
+    assert_eq!(flat[0].description, Some("Errors"));
+    assert_eq!(flat[1].path.to_string(), "danger");
+    assert_eq!(flat[1].value, flat[0].value);
+    assert!(matches!(
+        flat[1].raw,
+        TokenValue::Single(Expression::Ref(_))
+    ));
+    let ResolvedValue::Dict(body) = &flat[2].value else {
+        panic!()
+    };
+    assert_eq!(body.keys().collect_vec(), ["fontSize", "fontWeight"]);
+}
+
+#[test]
+fn test_serialize() {
+    let json = r##"{
+  "color": {
+    "red": {
+      "$value": "#ff0000",
+      "$type": "color",
+      "$description": "Errors"
+    },
+    "faded": {
+      "$value": "{color.red}",
+      "$type": "color",
+      "$extensions": {
+        "studio.tokens": {
+          "modify": {
+            "type": "alpha",
+            "value": "0.5",
+            "space": "srgb"
+          }
+        }
+      }
+    }
+  },
+  "size": {
+    "$value": "{base} * 2"
+  },
+  "base": {
+    "$value": 4.0
+  },
+  "body": {
+    "$value": {
+      "fontFamily": "Inter",
+      "fontSize": 16.0
+    },
+    "$type": "typography"
+  }
+}"##;
+    let tokens = parse_design_tokens(json, None).unwrap().remove(0);
+    assert_eq!(tokens.to_json(), json);
+    let again = parse_design_tokens(&tokens.to_json(), None)
+        .unwrap()
+        .remove(0);
+    assert_eq!(again.to_json(), json);
+}
+
+#[test]
+fn test_rust_doc() {
+    let tokens = parse_design_tokens(
+        r##"{ "red": { "$value": "#ff0000", "$description": "Errors.\nUse sparingly." } }"##,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].to_rust(),
+        "/// Errors.\n/// Use sparingly.\n///\n/// `red`: `#ff0000`\npub const RED: &str = \"#ff0000\";"
+    );
+}
+
+#[test]
+fn test_deprecated() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "old": { "$value": 1, "$deprecated": "Use {new}" },
+            "gone": { "$value": 2, "$deprecated": true },
+            "new": { "$value": 3, "$deprecated": false }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("#[deprecated(note = \"Use {new}\")]\npub const OLD"));
+    assert!(rust.contains("#[deprecated]\npub const GONE"));
+    assert!(!rust.contains("#[deprecated]\npub const NEW"));
+    let css = tokens[0].to_css_with_selector(":root");
+    assert!(css.contains(":root { --old: 1; /* deprecated: Use {new} */ }"));
+    assert!(css.contains(":root { --new: 3; }"));
+}
+
+#[test]
+fn test_resolution_mode() {
+    let mut tokens = parse_design_tokens(
+        r#"{
+            "gap": { "$value": "4px" },
+            "pad": { "$value": "{gap} * 2" },
+            "broken": { "$value": "{missing} * 2" },
+            "alias": { "$value": "{broken}" }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        tokens
+            .unresolved()
+            .iter()
+            .map(|x| format!("{} -> {}", x.path, x.reference))
+            .collect_vec(),
+        ["broken -> missing", "alias -> missing"]
+    );
+    assert_eq!(
+        tokens.check(&CssOptions::default()),
+        Err("Unresolved reference {missing} in broken".to_string())
+    );
+    assert_eq!(
+        tokens.get("alias").unwrap().try_resolve_css(&tokens),
+        Err("unresolved reference {missing}".to_string())
+    );
+    assert_eq!(
+        tokens.get("pad").unwrap().try_resolve_css(&tokens),
+        Ok("8px".to_string())
+    );
+    let strict = tokens.to_rust();
+
+    tokens.options.mode = ResolutionMode::Lenient;
+    assert_eq!(tokens.check(&CssOptions::default()), Ok(()));
+    let rust = tokens.to_rust();
+    assert_eq!(rust, strict);
+    assert!(rust.contains("pub const PAD: f32 = 8.;"));
+    assert!(!rust.contains("BROKEN") && !rust.contains("ALIAS"));
+    let css = tokens.to_css_with_selector(":root");
+    assert!(css.contains(":root { --broken: calc(var(--missing) * 2); }"));
+    assert!(css.contains(":root { --alias: var(--broken); }"));
+}
+
+#[test]
+fn test_unit_algebra() {
+    let mut tokens = parse_design_tokens(
+        r#"{
+            "gap": { "$value": "4px" },
+            "base": { "$value": "16px" },
+            "ratio": { "$value": "{base} / {gap}" },
+            "area": { "$value": "{gap} * {gap}" },
+            "mixed": { "$value": "50% / {gap}" }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let value =
+        |tokens: &DesignTokens, name: &str| tokens.resolve(&[name.into()]).unwrap().to_css();
+    assert_eq!(value(&tokens, "ratio"), "4");
+    assert_eq!(
+        tokens
+            .unit_errors()
+            .iter()
+            .map(|x| format!("{}: {}", x.path, x.message))
+            .collect_vec(),
+        [
+            "area: `{gap} * {gap}`: cannot multiply px by px",
+            "mixed: `50% / {gap}`: cannot divide % by px"
+        ]
+    );
+    assert!(std::panic::catch_unwind(|| value(&tokens, "area")).is_err());
+
+    tokens.options.mode = ResolutionMode::Lenient;
+    assert_eq!(value(&tokens, "area"), "16px");
+    assert_eq!(value(&tokens, "mixed"), "12.5%");
+}
+
+#[test]
+fn test_css_naming() {
+    let escaped = CssOptions {
+        naming: CssNaming::Escaped,
+        ..Default::default()
+    };
+    #[cfg(feature = "ambient-tokens")]
+    for tokens in design_tokens() {
+        assert_eq!(tokens.css_collisions(&CssOptions::default()), []);
+        assert_eq!(tokens.css_collisions(&escaped), []);
+    }
+
+    let mut tokens = parse_design_tokens(
+        r#"{
+            "Space": { "1.5": { "$value": "6px" }, "1d5": { "$value": "1px" } },
+            "Gap": { "$value": "{Space.1d5}" },
+            "Font, Mono": { "$value": "monospace" }
+        }"#,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        tokens.css_collisions(&CssOptions::default()),
+        [(
+            "--space-1d5".to_string(),
+            vec![TokenPath::parse("Space/1.5"), TokenPath::parse("Space/1d5")]
+        )]
+    );
+    assert_eq!(
+        tokens.check(&CssOptions::default()),
+        Err("CSS property --space-1d5 is shared by Space/1.5, Space/1d5; rename the tokens or use CssNaming::Escaped".to_string())
+    );
+
+    let options = CssOptions {
+        prefix: Some("ds".to_string()),
+        ..escaped
+    };
+    assert_eq!(
+        tokens.to_css_with(":root", &options),
+        "\
+:root { --ds-Space-1\\.5: 6px; }
+:root { --ds-Space-1d5: 1px; }
+:root { --ds-Gap: var(--ds-Space-1d5); }
+:root { --ds-Font\\,\\ Mono: monospace; }"
+    );
+
+    tokens.options.mode = ResolutionMode::Lenient;
+    assert!(tokens
+        .to_css_with_selector(":root")
+        .contains(":root { --space-1d5: 1px; }"));
+}
+
+#[test]
+fn test_property_rules() {
+    let tokens = parse_design_tokens(
+        r##"[
+            { "fileName": "light.json", "body": {
+                "brand": { "$value": "#e11d48" },
+                "gap": { "$value": "16px" },
+                "indent": { "$value": "2rem" },
+                "opacity": { "$value": 0.5 },
+                "fast": { "$value": "150ms" },
+                "family": { "$value": "Inter" }
+            } },
+            { "fileName": "dark.json", "body": { "brand": { "$value": "#fb7185" } } }
+        ]"##,
+        None,
+    )
+    .unwrap();
+    let options = CssOptions {
+        rem_base: Some(16.),
+        ..Default::default()
+    };
+    assert_eq!(
+        to_css_property_rules(&tokens, &options),
+        "\
+@property --brand { syntax: '<color>'; inherits: true; initial-value: #e11d48; }
+@property --gap { syntax: '<length>'; inherits: true; initial-value: 16px; }
+@property --indent { syntax: '<length>'; inherits: true; initial-value: 0px; }
+@property --opacity { syntax: '<number>'; inherits: true; initial-value: 0.5; }
+@property --fast { syntax: '<time>'; inherits: true; initial-value: 150ms; }"
+    );
+    let css = emit::CssEmitter {
+        options: CssOptions {
+            register_properties: true,
+            ..Default::default()
+        },
+    }
+    .emit(&emit::ResolvedTokens::new(&tokens));
+    assert!(css.starts_with("@property --brand {"));
+    assert!(css.contains(".light { --gap: 16px; }"));
+}
+
+#[test]
+fn test_provenance() {
+    let tokens = parse_design_tokens(
+        r#"{ "Brand": { "Base": { "$value": "4px" }, "Large": { "$value": "{Brand.Base} * 2" } } }"#,
+        Some("tokens.json"),
+    )
+    .unwrap();
+    let options = CssOptions {
+        provenance: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        tokens[0].to_css_with(":root", &options),
+        "/* tokens.json: Brand/Base = 4px */\n:root { --brand-base: 4px; }\n/* tokens.json: Brand/Large = {Brand.Base} * 2 */\n:root { --brand-large: calc(var(--brand-base) * 2); }"
+    );
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("/// `Brand/Base`: `4px` in `tokens.json`\npub const BRAND_BASE"));
+    assert!(rust.contains("/// `Brand/Large`: `8px`, from `{Brand.Base} * 2` in `tokens.json`\n"));
+}
+
+#[test]
+fn test_rust_color() {
+    let tokens = parse_design_tokens(r##"{ "grey": { "$value": "#80808080" } }"##, None).unwrap();
+    let options = |color| RustOptions {
+        color,
+        ..Default::default()
+    };
+    assert!(tokens[0]
+        .to_rust_with(&options(RustColor::LinearRgba))
+        .contains("pub const GREY: [f32; 4] = [0.2158605, 0.2158605, 0.2158605, 0.5019608];"));
+    assert!(tokens[0]
+        .to_rust_with(&options(RustColor::GlamVec4))
+        .contains("pub const GREY: glam::Vec4 = glam::Vec4::new(0.2158605, "));
+}
+
+#[test]
+fn test_check() {
+    let json = |value: &str| {
+        format!(r#"{{ "size": {{ "$value": "4px" }}, "gap": {{ "$value": "{value}" }} }}"#)
+    };
+    let check = |value: &str, mode| {
+        let mut tokens = parse_design_tokens(&json(value), None).unwrap().remove(0);
+        tokens.options.mode = mode;
+        tokens.check(&CssOptions::default())
+    };
+    assert_eq!(check("{size} * 2", ResolutionMode::Strict), Ok(()));
+    assert_eq!(
+        check("{space}", ResolutionMode::Strict),
+        Err("Unresolved reference {space} in gap".to_string())
+    );
+    assert_eq!(check("{space}", ResolutionMode::Lenient), Ok(()));
+    assert_eq!(
+        check("{size} * 2s", ResolutionMode::Strict),
+        Err("gap: `{size} * 2s`: cannot multiply px by s".to_string())
+    );
+}
+
+#[test]
+fn test_eval() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "Space": { "base": { "$value": "4px" } },
+            "Brand": { "Primary": { "$value": "#ff0000" } },
+            "body": { "$value": { "fontSize": "16px" }, "$type": "typography" }
+        }"##,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        tokens[0].eval("{Space.base} * 2"),
+        Ok(Value::Number(8., NumberType::Pixels))
+    );
+    assert_eq!(
+        tokens[0].eval("{Brand.Primary}").unwrap().to_css(),
+        "#ff0000"
+    );
+    assert_eq!(
+        tokens[0].eval("{Space.huge}"),
+        Err("Unresolved reference {Space.huge}".to_string())
+    );
+    assert!(tokens[0].eval("{Space.base} * 2s").is_err());
+    for (expr, err) in [
+        (
+            "{Brand.Primary} * 0.5",
+            "`{Brand.Primary} * 0.5`: cannot multiply a color by a number",
+        ),
+        (
+            "{body} * 2",
+            "{body} is a composite token, reference one of its fields",
+        ),
+    ] {
+        assert_eq!(tokens[0].eval(expr), Err(err.to_string()));
+    }
+    assert_eq!(
+        tokens[0].eval("{body.fontSize} * 2"),
+        Ok(Value::Number(32., NumberType::Pixels))
+    );
+    for expr in ["#zz", "min()"] {
+        assert!(tokens[0].eval(expr).unwrap_err().contains("expected"));
+    }
+}
+
+#[test]
+fn test_path_matches() {
+    let path = TokenPath::parse("Brand/Primary/500");
+    assert!(path.matches("Brand/Primary/*"));
+    assert!(path.matches("Brand/**"));
+    assert!(path.matches("**/500"));
+    assert!(path.matches("Br*/*ary/5*0"));
+    assert!(!path.matches("Brand/*"));
+    assert!(!path.matches("Brand/Secondary/*"));
+}
+
+#[test]
+fn test_bool_and_string() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "reducedMotion": { "$value": true },
+            "darkMode": { "$value": "false", "$type": "boolean" },
+            "code": { "$value": 404, "$type": "string" },
+            "label": { "$value": "Hello", "$type": "string" }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const REDUCEDMOTION: bool = true;"));
+    assert!(rust.contains("pub const DARKMODE: bool = false;"));
+    assert!(rust.contains("pub const CODE: &str = \"404\";"));
+    assert!(rust.contains("pub const LABEL: &str = \"Hello\";"));
+    assert!(tokens[0].to_css().contains("--reducedmotion: true;"));
+    assert!(tokens[0].to_json().contains("\"$value\": true"));
+}
+
+#[test]
+fn test_dict_order() {
+    let json = r##"{ "body": { "$type": "typography", "$value": { "fontWeight": 400, "fontSize": "16px", "lineHeight": 1.5, "fontFamily": "Inter" } } }"##;
+    let emit = || {
+        let tokens = parse_design_tokens(json, None).unwrap();
+        (tokens[0].to_css(), tokens[0].to_rust(), tokens[0].to_json())
+    };
+    let (css, rust, json) = emit();
+    for _ in 0..8 {
+        assert_eq!(emit(), (css.clone(), rust.clone(), json.clone()));
+    }
+    assert!(
+        css.contains("font-weight: 400;\nfont-size: 16px;\n"),
+        "{css}"
+    );
+    assert!(
+        rust.contains("(\"fontWeight\", \"400.\"),\n    (\"fontSize\", \"16.\"),"),
+        "{rust}"
+    );
+}
+
+#[test]
+fn test_output_order() {
+    let json = r##"{
+        "z": { "b": { "$value": "2px" }, "a": { "$value": "1px" } },
+        "border": { "$type": "border", "$value": { "width": "1px", "color": "#000000", "style": "solid" } },
+        "m": { "$value": "#ff0000" }
+    }"##;
+    let mut tokens = parse_design_tokens(json, None).unwrap().remove(0);
+    let paths = |x: &DesignTokens| x.iter().map(|(x, _)| x.to_string()).collect_vec();
+    assert_eq!(paths(&tokens), ["z/b", "z/a", "border", "m"]);
+    tokens.reorder(OutputOrder::Alphabetical);
+    assert_eq!(paths(&tokens), ["border", "m", "z/a", "z/b"]);
+    assert_eq!(
+        tokens.resolve_css("border").unwrap(),
+        "color: #000000; style: solid; width: 1px"
+    );
+}
+
+#[test]
+fn test_css_references() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "red": { "$value": "#ff0000" },
+            "base": { "$value": "4px" },
+            "large": { "$value": "{base} * 2" },
+            "danger": { "$value": "{red}" },
+            "outline": { "$type": "border", "$value": { "width": "{base}", "style": "solid", "color": "{danger}" } }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let css = |references| {
+        let options = CssOptions {
+            references,
+            ..Default::default()
+        };
+        tokens[0].to_css_with(":root", &options)
+    };
+    assert!(css(CssReferences::Var).contains("--large: calc(var(--base) * 2);"));
+    let fallback = css(CssReferences::Fallback);
+    assert!(
+        fallback.contains("--large: calc(var(--base, 4px) * 2);"),
+        "{fallback}"
+    );
+    assert!(fallback.contains("--danger: var(--red, #ff0000);"));
+    assert!(fallback.contains("--outline: var(--base, 4px) solid var(--danger, #ff0000);"));
+    let inline = css(CssReferences::Inline);
+    assert!(inline.contains("--large: calc(4px * 2);"), "{inline}");
+    assert!(inline.contains("--outline: 4px solid #ff0000;"));
+}
+
+#[test]
+fn test_resolve_strategy() {
+    let tokens = parse_design_tokens(
+        r##"{
+            "base": { "$value": "4px" },
+            "large": { "$value": "{base} * 2" },
+            "pad": { "$type": "border", "$value": { "width": "{large}", "style": "solid", "color": "#000000" } }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let options = CssOptions {
+        resolve: ResolveStrategy::Inline,
+        ..Default::default()
+    };
+    let css = tokens[0].to_css_with(":root", &options);
+    assert!(css.contains("--large: 8px;"), "{css}");
+    assert!(css.contains("--pad: 8px solid #000000;"), "{css}");
+    assert!(tokens[0]
+        .to_css_with(":root", &CssOptions::default())
+        .contains("--large: calc(var(--base) * 2);"));
 }
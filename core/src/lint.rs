@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use convert_case::Casing;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    naming::NameCase,
+    validate::{Diagnostic, Severity},
+    DesignTokens, Expression, TokenOrGroup, TokenPath, Value,
+};
+
+/// Which rules [`DesignTokens::lint`] checks. Every rule is off until configured. Groups are
+/// globs, see [`TokenPath::matches`], e.g. `Semantic/**`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LintConfig {
+    /// `semantic-literal`: tokens here must reference other tokens instead of holding literals.
+    pub semantic: Vec<String>,
+    /// `color-outside-palette`: the only tokens allowed to hold color literals.
+    pub palette: Option<Vec<String>>,
+    /// `reference-depth`: the longest allowed chain of references, e.g. 1 when aliases must
+    /// point straight at literals.
+    pub max_depth: Option<usize>,
+    /// `key-case`: the case every group and token key must be written in.
+    pub key_case: Option<NameCase>,
+}
+
+fn matches_any(path: &TokenPath, patterns: &[String]) -> bool {
+    patterns.iter().any(|x| path.matches(x))
+}
+
+fn has_color(expr: &Expression) -> bool {
+    match expr {
+        Expression::Value(Value::Color(_)) => true,
+        Expression::Mul(a, b) | Expression::Div(a, b) => has_color(a) || has_color(b),
+        Expression::Call(_, args) | Expression::Concat(args) | Expression::List(args) => {
+            args.iter().any(has_color)
+        }
+        Expression::Ref(_) | Expression::Value(_) => false,
+    }
+}
+
+impl DesignTokens {
+    /// Checks the conventions enabled in `config`. Findings are warnings.
+    pub fn lint(&self, config: &LintConfig) -> Vec<Diagnostic> {
+        let graph = self.reference_graph();
+        let mut keys = HashSet::new();
+        let mut out = Vec::new();
+        let mut warn = |rule, path: &TokenPath, message: String| {
+            out.push(Diagnostic {
+                severity: Severity::Warning,
+                rule,
+                path: path.clone(),
+                message,
+            })
+        };
+        for (path, token) in self.iter() {
+            let TokenOrGroup::Token { .. } = token else {
+                continue;
+            };
+            let exprs = token.exprs();
+            if matches_any(&path, &config.semantic) && exprs.iter().any(|x| x.refs().is_empty()) {
+                warn(
+                    "semantic-literal",
+                    &path,
+                    "semantic token holds a literal instead of referencing a token".to_string(),
+                );
+            }
+            if let Some(palette) = &config.palette {
+                if !matches_any(&path, palette) && exprs.iter().any(|x| has_color(x)) {
+                    warn(
+                        "color-outside-palette",
+                        &path,
+                        "color literal outside the palette".to_string(),
+                    );
+                }
+            }
+            if let Some(max) = config.max_depth {
+                let depth = graph.depth(&path);
+                if depth > max {
+                    warn(
+                        "reference-depth",
+                        &path,
+                        format!("reference chain is {depth} deep, more than {max}"),
+                    );
+                }
+            }
+            if let Some(case) = config.key_case {
+                for i in 1..=path.len() {
+                    let key = &path[i - 1];
+                    let fixed = key.to_case(case.case());
+                    if *key != fixed && keys.insert(path[..i].to_vec()) {
+                        warn(
+                            "key-case",
+                            &TokenPath::from(&path[..i]),
+                            format!("key {key:?} should be {fixed:?}"),
+                        );
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "palette": { "red": { "$value": "#ff0000" } },
+            "Semantic": {
+                "danger": { "$value": "{palette.red}" },
+                "alert": { "$value": "{Semantic.danger}" },
+                "warning": { "$value": "#ffaa00" }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let config = LintConfig {
+        semantic: vec!["Semantic/**".to_string()],
+        palette: Some(vec!["palette/*".to_string()]),
+        max_depth: Some(1),
+        key_case: Some(NameCase::Kebab),
+    };
+    let found = tokens[0]
+        .lint(&config)
+        .into_iter()
+        .map(|x| (x.rule, x.path.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            ("key-case", "Semantic".to_string()),
+            ("reference-depth", "Semantic/alert".to_string()),
+            ("semantic-literal", "Semantic/warning".to_string()),
+            ("color-outside-palette", "Semantic/warning".to_string()),
+        ]
+    );
+    assert!(tokens[0].lint(&LintConfig::default()).is_empty());
+}
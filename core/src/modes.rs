@@ -0,0 +1,75 @@
+use indexmap::IndexMap;
+
+use crate::{DesignTokens, TokenOrGroup};
+
+/// A Figma variable collection with one token tree per mode, built from the
+/// `Collection.mode.tokens.json` sets of an `exportedVariables.json` export.
+#[derive(Debug)]
+pub struct Collection {
+    pub name: String,
+    pub modes: IndexMap<String, TokenOrGroup>,
+}
+impl Collection {
+    /// Groups sets by collection. Sets without a mode in their file name get a `default` mode.
+    pub fn from_sets(sets: Vec<DesignTokens>) -> Vec<Collection> {
+        let mut out: IndexMap<String, Collection> = IndexMap::new();
+        for set in sets {
+            let (name, mode) = match set
+                .file_name
+                .as_deref()
+                .map(|x| x.split('.').collect::<Vec<_>>())
+            {
+                Some(parts) if parts.len() > 2 => (parts[0].to_string(), parts[1].to_string()),
+                _ => (set.get_name().to_string(), "default".to_string()),
+            };
+            out.entry(name.clone())
+                .or_insert_with(|| Collection {
+                    name,
+                    modes: IndexMap::new(),
+                })
+                .modes
+                .insert(mode, set.body);
+        }
+        out.into_values().collect()
+    }
+    pub fn mode_names(&self) -> impl Iterator<Item = &str> {
+        self.modes.keys().map(|x| x.as_str())
+    }
+    /// The tokens of a single mode.
+    pub fn into_mode(mut self, mode: &str) -> Option<DesignTokens> {
+        let body = self.modes.shift_remove(mode)?;
+        Some(self.set(mode, body))
+    }
+    /// One set per mode, named after the mode, e.g. for [`crate::to_css_themed`].
+    pub fn into_themes(mut self) -> Vec<DesignTokens> {
+        let modes = std::mem::take(&mut self.modes);
+        modes
+            .into_iter()
+            .map(|(mode, body)| self.set(&mode, body))
+            .collect()
+    }
+    fn set(&self, mode: &str, body: TokenOrGroup) -> DesignTokens {
+        DesignTokens {
+            file_name: Some(format!("{}.{mode}.tokens.json", self.name)),
+            body,
+            options: Default::default(),
+            origin: Default::default(),
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "ambient-tokens")]
+fn test() {
+    let collections = Collection::from_sets(crate::get_design_tokens());
+    assert_eq!(collections.len(), 2);
+    let ambient = collections.into_iter().next().unwrap();
+    assert_eq!(ambient.name, "Ambient");
+    assert_eq!(
+        ambient.mode_names().collect::<Vec<_>>(),
+        ["light", "dark", "brand-light", "roda_huset"]
+    );
+    let dark = ambient.into_mode("dark").unwrap();
+    assert_eq!(dark.get_name(), "dark");
+    assert!(dark.to_css().starts_with(".dark {"));
+}
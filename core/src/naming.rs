@@ -0,0 +1,165 @@
+use convert_case::{Case, Casing};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::TokenPath;
+
+/// A case style for generated names, overriding the one each emitter uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NameCase {
+    /// `brand-primary`
+    Kebab,
+    /// `brand_primary`
+    Snake,
+    /// `BRAND_PRIMARY`
+    UpperSnake,
+    /// `brandPrimary`
+    Camel,
+    /// `BrandPrimary`
+    Pascal,
+    /// `brandprimary`
+    Flat,
+    /// `BRANDPRIMARY`
+    UpperFlat,
+}
+impl NameCase {
+    pub fn case(self) -> Case {
+        match self {
+            NameCase::Kebab => Case::Kebab,
+            NameCase::Snake => Case::Snake,
+            NameCase::UpperSnake => Case::UpperSnake,
+            NameCase::Camel => Case::Camel,
+            NameCase::Pascal => Case::Pascal,
+            NameCase::Flat => Case::Flat,
+            NameCase::UpperFlat => Case::UpperFlat,
+        }
+    }
+}
+
+/// How token paths become names in every emitter, see [`crate::ResolveOptions::naming`].
+/// Each emitter has its own case and separator, e.g. `BRAND_PRIMARY` in Rust and
+/// `--brand-primary` in CSS, which `case` and `separator` override.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Naming {
+    pub case: Option<NameCase>,
+    /// Between the segments of a path.
+    pub separator: Option<String>,
+    /// Added as the first segment of every token name, e.g. `ds` for `--ds-brand-primary`.
+    pub prefix: Option<String>,
+    /// Replacements applied to each segment before anything else, e.g. `Background` to `bg`.
+    /// By default `,`, `+` and `.` are spelled out as `c`, `p` and `d`, so `1.5` becomes `1d5`.
+    pub abbreviations: IndexMap<String, String>,
+    /// Functions naming tokens in place of [`Self::join`], by emitter. Only the emitters that
+    /// name a token by its whole path use them: `css`, `rust` and `android`.
+    #[serde(skip)]
+    pub transforms: IndexMap<String, fn(&TokenPath) -> String>,
+}
+impl Default for Naming {
+    fn default() -> Self {
+        Naming {
+            case: None,
+            separator: None,
+            prefix: None,
+            abbreviations: [(",", "c"), ("+", "p"), (".", "d")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            transforms: IndexMap::new(),
+        }
+    }
+}
+impl Naming {
+    /// Names tokens with `f` in the output of `emitter`, e.g. `rust`.
+    pub fn transform(mut self, emitter: &str, f: fn(&TokenPath) -> String) -> Self {
+        self.transforms.insert(emitter.to_string(), f);
+        self
+    }
+    /// `default`, unless [`Self::case`] overrides it.
+    pub fn case(&self, default: Case) -> Case {
+        self.case.map(NameCase::case).unwrap_or(default)
+    }
+    /// One path segment as lowercase ASCII with the abbreviations applied, spaces replaced by
+    /// `sep` and parentheses by `_`.
+    pub fn slug(&self, segment: &str, sep: &str) -> String {
+        let mut s = segment.to_string();
+        for (from, to) in &self.abbreviations {
+            s = s.replace(from.as_str(), to);
+        }
+        deunicode::deunicode(
+            &s.replace(['(', ')'], "_")
+                .replace(' ', sep)
+                .to_ascii_lowercase(),
+        )
+    }
+    /// One path segment in `case`, or as a slug when `case` is `None`, unless overridden.
+    pub fn segment(&self, segment: &str, case: Option<Case>, sep: &str) -> String {
+        let slug = self.slug(segment, sep);
+        match self.case.map(NameCase::case).or(case) {
+            Some(case) => slug.to_case(case),
+            None => slug,
+        }
+    }
+    /// The name of the token at `path`: the prefix and each segment, see [`Self::segment`],
+    /// joined by `sep` unless overridden.
+    pub fn join(&self, path: &[String], case: Option<Case>, sep: &str) -> String {
+        let sep = self.separator.as_deref().unwrap_or(sep);
+        self.prefix
+            .iter()
+            .chain(path)
+            .map(|x| self.segment(x, case, sep))
+            .join(sep)
+    }
+    /// The name of the token at `path` in the output of `emitter`, from its transform if it has
+    /// one and [`Self::join`] otherwise.
+    pub fn name(&self, emitter: &str, path: &[String], case: Option<Case>, sep: &str) -> String {
+        match self.transforms.get(emitter) {
+            Some(f) => f(&TokenPath::from(path)),
+            None => self.join(path, case, sep),
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let path = ["Background".to_string(), "Space 1.5".to_string()];
+    let naming = Naming::default();
+    assert_eq!(naming.join(&path, None, "-"), "background-space-1d5");
+    assert_eq!(
+        naming.join(&path, Some(Case::UpperFlat), "_"),
+        "BACKGROUND_SPACE1D5"
+    );
+    let naming = Naming {
+        case: Some(NameCase::Snake),
+        prefix: Some("ds".to_string()),
+        abbreviations: [("Background", "bg"), (".", "_")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    assert_eq!(naming.join(&path, None, "-"), "ds-bg-space_1_5");
+    assert_eq!(
+        naming.join(&path, Some(Case::UpperFlat), "_"),
+        "ds_bg_space_1_5"
+    );
+}
+
+#[test]
+fn test_transform() {
+    let mut tokens = crate::parse_design_tokens(
+        r#"{ "Set": { "Space": { "m": { "$value": "8px" } } } }"#,
+        None,
+    )
+    .unwrap();
+    tokens[0].options.naming = Naming::default()
+        .transform("rust", |path| path.0[1..].join("_").to_uppercase())
+        .transform("css", |path| path.0.last().unwrap().clone());
+    assert!(tokens[0].to_rust().contains("pub const SPACE_M: f32 = 8.;"));
+    assert_eq!(
+        crate::CssOptions::default().property_name(&tokens[0].options.naming, &["Set".into()]),
+        "--Set"
+    );
+}
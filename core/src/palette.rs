@@ -0,0 +1,148 @@
+use csscolorparser::Color;
+use indexmap::IndexMap;
+
+use crate::{
+    expression::{Expression, Value},
+    extensions::{from_oklch, to_oklch},
+    DesignTokens, TokenOrGroup, TokenPath, TokenType, TokenValue,
+};
+
+/// The steps of a color ramp with their OKLCH lightness, lightest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RampScale {
+    pub steps: Vec<(String, f64)>,
+}
+impl Default for RampScale {
+    /// `50` to `900`, with `500` at medium lightness.
+    fn default() -> Self {
+        let steps = [
+            (50, 0.97),
+            (100, 0.93),
+            (200, 0.87),
+            (300, 0.79),
+            (400, 0.7),
+            (500, 0.61),
+            (600, 0.52),
+            (700, 0.44),
+            (800, 0.36),
+            (900, 0.28),
+        ];
+        Self {
+            steps: steps.map(|(step, l)| (step.to_string(), l)).to_vec(),
+        }
+    }
+}
+
+/// A shade for every step of `scale`, keeping the hue of `base`. Chroma shrinks towards white
+/// and black and is reduced further where the shade would fall outside sRGB. The step closest
+/// in lightness to `base` is `base` itself.
+pub fn ramp(base: &Color, scale: &RampScale) -> Vec<(String, Color)> {
+    let (base_l, base_c, h, alpha) = to_oklch(base);
+    let closest = scale
+        .steps
+        .iter()
+        .map(|(_, l)| (l - base_l).abs())
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i);
+    scale
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, (step, l))| {
+            if Some(i) == closest {
+                return (step.clone(), base.clone());
+            }
+            let extent = |l: f64| l.min(1. - l).max(1e-3);
+            let mut c = base_c * (extent(*l) / extent(base_l)).min(1.);
+            let mut color = from_oklch(*l, c, h, alpha);
+            while c > 1e-4
+                && ![color.r, color.g, color.b]
+                    .iter()
+                    .all(|x| (-1e-4..=1.0001).contains(x))
+            {
+                c *= 0.95;
+                color = from_oklch(*l, c, h, alpha);
+            }
+            (
+                step.clone(),
+                Color::new(
+                    color.r.clamp(0., 1.),
+                    color.g.clamp(0., 1.),
+                    color.b.clamp(0., 1.),
+                    alpha,
+                ),
+            )
+        })
+        .collect()
+}
+
+impl DesignTokens {
+    /// Generates a [`ramp`] from the color token at `base` and writes it as color tokens into the
+    /// group at `target` (both `/`-separated), replacing existing tokens with the same step names.
+    /// Returns `false` when `base` doesn't resolve to a color.
+    pub fn add_ramp(&mut self, base: &str, target: &str, scale: &RampScale) -> bool {
+        let Some(Value::Color(color)) = self.resolve(&TokenPath::parse(base)) else {
+            return false;
+        };
+        let mut node = &mut self.body;
+        for key in TokenPath::parse(target).iter() {
+            node = as_group(node)
+                .entry(key.clone())
+                .or_insert_with(|| TokenOrGroup::Group(IndexMap::new()));
+        }
+        let group = as_group(node);
+        for (step, color) in ramp(&color, scale) {
+            group.insert(
+                step,
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(Expression::Value(Value::Color(color))),
+                    type_: TokenType::Color,
+                    extensions: None,
+                    description: Some(format!("Generated from {base}")),
+                    deprecated: None,
+                },
+            );
+        }
+        true
+    }
+}
+
+/// The children of `node`, replacing it with an empty group if it is a token.
+pub(crate) fn as_group(node: &mut TokenOrGroup) -> &mut IndexMap<String, TokenOrGroup> {
+    if let TokenOrGroup::Token { .. } = node {
+        *node = TokenOrGroup::Group(IndexMap::new());
+    }
+    match node {
+        TokenOrGroup::Group(group) => group,
+        TokenOrGroup::Token { .. } => unreachable!(),
+    }
+}
+
+#[test]
+fn test() {
+    let base = csscolorparser::parse("#3b82f6").unwrap();
+    let shades = ramp(&base, &RampScale::default());
+    assert_eq!(shades.len(), 10);
+    assert_eq!(shades[0].0, "50");
+    let lightness = shades
+        .iter()
+        .map(|(_, c)| to_oklch(c).0)
+        .collect::<Vec<_>>();
+    assert!(lightness.windows(2).all(|x| x[0] > x[1]), "{lightness:?}");
+    assert!(shades.iter().any(|(_, c)| *c == base));
+    let hue = to_oklch(&base).2;
+    assert!((to_oklch(&shades[7].1).2 - hue).abs() < 0.05);
+
+    let mut tokens = crate::parse_design_tokens(
+        r##"{ "brand": { "$value": "#3b82f6", "$type": "color" } }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert!(tokens.add_ramp("brand", "palette/blue", &RampScale::default()));
+    assert!(!tokens.add_ramp("missing", "palette/red", &RampScale::default()));
+    let css = tokens.to_css_with_selector(":root");
+    assert!(css.contains(":root { --palette-blue-50: #"));
+    assert!(css.contains(":root { --palette-blue-500: #3b82f6; }"));
+}
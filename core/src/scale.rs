@@ -0,0 +1,225 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::{
+    expression::{Expression, NumberType, Value},
+    palette::as_group,
+    resolve_single, DesignTokens, TokenOrGroup, TokenPath, TokenType, TokenValue,
+};
+
+/// The steps of a spacing scale, named `1` to `steps`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpacingScale {
+    pub steps: usize,
+    /// Each step is this many times the one before, starting from the base. Without it, step
+    /// `n` is `n` times the base.
+    pub ratio: Option<f64>,
+}
+impl Default for SpacingScale {
+    /// 12 linear steps.
+    fn default() -> Self {
+        Self {
+            steps: 12,
+            ratio: None,
+        }
+    }
+}
+impl SpacingScale {
+    /// The factor step `n` multiplies the base by.
+    pub fn factor(&self, n: usize) -> f64 {
+        match self.ratio {
+            Some(ratio) => ratio.powi(n as i32 - 1),
+            None => n as f64,
+        }
+    }
+}
+
+/// The named steps of a modular type scale, each with the power of the ratio it multiplies the
+/// base size by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeScale {
+    pub steps: Vec<(String, i32)>,
+}
+impl Default for TypeScale {
+    /// `xs` and `sm` below the base, `md` at the base, then `lg` to `4xl`.
+    fn default() -> Self {
+        let steps = [
+            ("xs", -2),
+            ("sm", -1),
+            ("md", 0),
+            ("lg", 1),
+            ("xl", 2),
+            ("2xl", 3),
+            ("3xl", 4),
+            ("4xl", 5),
+        ];
+        Self {
+            steps: steps
+                .map(|(name, power)| (name.to_string(), power))
+                .to_vec(),
+        }
+    }
+}
+
+impl DesignTokens {
+    /// Writes a [`SpacingScale`] of the dimension token at `base` as tokens `1`, `2`, ... into
+    /// the group at `target` (both `/`-separated), replacing existing tokens with the same names.
+    /// Returns `false` when `base` doesn't resolve to a dimension.
+    pub fn add_spacing(&mut self, base: &str, target: &str, scale: &SpacingScale) -> bool {
+        let Some(Value::Number(value, unit)) = self.resolve(&TokenPath::parse(base)) else {
+            return false;
+        };
+        if unit == NumberType::None {
+            return false;
+        }
+        let group = group_at(&mut self.body, target);
+        for n in 1..=scale.steps {
+            let value = (value * scale.factor(n) * 1000.).round() / 1000.;
+            group.insert(
+                n.to_string(),
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(Expression::Value(Value::Number(
+                        value,
+                        unit.clone(),
+                    ))),
+                    type_: TokenType::Dimension,
+                    extensions: None,
+                    description: Some(format!("Generated from {base}")),
+                    deprecated: None,
+                },
+            );
+        }
+        true
+    }
+    /// Writes a [`TypeScale`] as font sizes into the group at `target`, each step being the
+    /// dimension token at `base` times the unitless token at `ratio` to the power of the step, e.g.
+    /// `lg` as `base * ratio`. Paths are `/`-separated. Returns `false` when `base` or `ratio`
+    /// doesn't resolve to a value of that kind.
+    pub fn add_type_scale(
+        &mut self,
+        base: &str,
+        ratio: &str,
+        target: &str,
+        scale: &TypeScale,
+    ) -> bool {
+        let Some(Value::Number(value, unit)) = self.resolve(&TokenPath::parse(base)) else {
+            return false;
+        };
+        let Some(Value::Number(ratio_value, NumberType::None)) =
+            self.resolve(&TokenPath::parse(ratio))
+        else {
+            return false;
+        };
+        if unit == NumberType::None {
+            return false;
+        }
+        let group = group_at(&mut self.body, target);
+        for (name, power) in &scale.steps {
+            let value = (value * ratio_value.powi(*power) * 1000.).round() / 1000.;
+            group.insert(
+                name.clone(),
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(Expression::Value(Value::Number(
+                        value,
+                        unit.clone(),
+                    ))),
+                    type_: TokenType::Dimension,
+                    extensions: None,
+                    description: Some(format!("Generated from {base} and {ratio}")),
+                    deprecated: None,
+                },
+            );
+        }
+        true
+    }
+    /// The resolved dimensions directly in the group at the `/`-separated `path`, in order.
+    pub fn spacing(&self, path: &str) -> Vec<Value> {
+        let group = TokenPath::parse(path).to_vec();
+        self.tokens()
+            .into_iter()
+            .filter(|(path, _)| path.len() == group.len() + 1 && path[..].starts_with(&group))
+            .filter(|(_, token)| self.missing_ref(token).is_none())
+            .filter_map(|(_, token)| match token {
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(expr),
+                    extensions,
+                    ..
+                } => match resolve_single(self, expr, extensions) {
+                    Value::Number(_, NumberType::None) => None,
+                    value @ Value::Number(..) => Some(value),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+    /// `pub const SPACING`, or nothing when the group has no dimensions.
+    pub(crate) fn rust_spacing(&self, path: Option<&str>) -> Option<String> {
+        let spacing = self.spacing(path?);
+        if spacing.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "/// Spacing steps, in the unit of each token.\npub const SPACING: &[f32] = &[{}];",
+            spacing.iter().map(|x| x.to_rust()).join(", ")
+        ))
+    }
+}
+
+/// The group at the `/`-separated `path`, creating it and replacing tokens in the way.
+fn group_at<'a>(node: &'a mut TokenOrGroup, path: &str) -> &'a mut IndexMap<String, TokenOrGroup> {
+    let mut node = node;
+    for key in TokenPath::parse(path).iter() {
+        node = as_group(node)
+            .entry(key.clone())
+            .or_insert_with(|| TokenOrGroup::Group(IndexMap::new()));
+    }
+    as_group(node)
+}
+
+#[test]
+fn test() {
+    let mut tokens = crate::parse_design_tokens(
+        r##"{ "base": { "$value": "4px" }, "red": { "$value": "#ff0000" } }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    assert!(tokens.add_spacing("base", "space", &SpacingScale::default()));
+    assert!(!tokens.add_spacing("red", "other", &SpacingScale::default()));
+    let css = tokens.to_css_with_selector(":root");
+    assert!(css.contains(":root { --space-1: 4px; }"));
+    assert!(css.contains(":root { --space-12: 48px; }"));
+    let options = crate::RustOptions {
+        spacing: Some("space".to_string()),
+        ..Default::default()
+    };
+    assert!(tokens
+        .to_rust_with(&options)
+        .contains("pub const SPACING: &[f32] = &[4., 8., 12.,"));
+
+    let scale = SpacingScale {
+        steps: 3,
+        ratio: Some(1.5),
+    };
+    assert!(tokens.add_spacing("base", "ratio", &scale));
+    let values = tokens.spacing("ratio").iter().map(|x| x.to_css()).join(" ");
+    assert_eq!(values, "4px 6px 9px");
+}
+
+#[test]
+fn test_type_scale() {
+    let mut tokens = crate::parse_design_tokens(
+        r##"{ "font": { "base": { "$value": "16px" }, "ratio": { "$value": "1.25" } } }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let scale = TypeScale::default();
+    assert!(tokens.add_type_scale("font/base", "font/ratio", "font/size", &scale));
+    assert!(!tokens.add_type_scale("font/ratio", "font/base", "font/other", &scale));
+    let css = tokens.to_css_with_selector(":root");
+    assert!(css.contains(":root { --font-size-sm: 12.8px; }"));
+    assert!(css.contains(":root { --font-size-md: 16px; }"));
+    assert!(css.contains(":root { --font-size-xl: 25px; }"));
+}
@@ -0,0 +1,215 @@
+use serde_json::{json, Map, Value as Json};
+
+use crate::expression::expr_parser;
+
+/// A problem found by [`validate_json`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value, e.g. `/color/red/$value`.
+    pub pointer: String,
+    pub reason: String,
+}
+
+/// A JSON Schema for the token files accepted by [`crate::parse_design_tokens`]: either a token tree
+/// or a list of `{ fileName, body }` sets as exported by Figma.
+pub fn schema() -> Json {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Design tokens",
+        "oneOf": [
+            { "$ref": "#/$defs/group" },
+            {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "fileName": { "type": "string" },
+                        "body": { "$ref": "#/$defs/group" }
+                    },
+                    "required": ["body"]
+                }
+            }
+        ],
+        "$defs": {
+            "group": {
+                "type": "object",
+                "additionalProperties": {
+                    "oneOf": [{ "$ref": "#/$defs/token" }, { "$ref": "#/$defs/group" }]
+                }
+            },
+            "token": {
+                "type": "object",
+                "properties": {
+                    "$value": { "$ref": "#/$defs/value" },
+                    "value": { "$ref": "#/$defs/value" },
+                    "$type": { "type": "string" },
+                    "type": { "type": "string" },
+                    "$description": { "type": "string" },
+                    "description": { "type": "string" },
+                    "$extensions": { "type": "object" },
+                    "$deprecated": { "type": ["boolean", "string"] }
+                },
+                "oneOf": [{ "required": ["$value"] }, { "required": ["value"] }]
+            },
+            "value": {
+                "oneOf": [
+                    { "$ref": "#/$defs/expression" },
+                    { "type": "object", "additionalProperties": { "$ref": "#/$defs/expression" } }
+                ]
+            },
+            "expression": {
+                "description": "A number, a boolean, a string such as `#ff0000`, `4px`, `{a.b} * 2` or `clamp(...)`, or a list of those",
+                "type": ["string", "number", "boolean", "array"],
+                "items": { "$ref": "#/$defs/expression" }
+            }
+        }
+    })
+}
+
+/// Checks `json` against the token format before parsing, reporting every problem with a JSON
+/// pointer instead of the single opaque error serde gives for untagged enums.
+pub fn validate_json(json: &str) -> Result<(), Vec<SchemaError>> {
+    let value: Json = serde_json::from_str(json).map_err(|err| {
+        vec![SchemaError {
+            pointer: String::new(),
+            reason: err.to_string(),
+        }]
+    })?;
+    let mut errors = Vec::new();
+    match &value {
+        Json::Array(sets) => {
+            for (i, set) in sets.iter().enumerate() {
+                let pointer = format!("/{i}");
+                let Json::Object(set) = set else {
+                    error(
+                        &mut errors,
+                        &pointer,
+                        "expected a `{ fileName, body }` object",
+                    );
+                    continue;
+                };
+                if let Some(name) = set.get("fileName") {
+                    if !name.is_string() {
+                        error(
+                            &mut errors,
+                            &format!("{pointer}/fileName"),
+                            "expected a string",
+                        );
+                    }
+                }
+                match set.get("body") {
+                    Some(body) => check_group(&mut errors, &format!("{pointer}/body"), body),
+                    None => error(&mut errors, &pointer, "missing `body`"),
+                }
+            }
+        }
+        value => check_group(&mut errors, "", value),
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn error(errors: &mut Vec<SchemaError>, pointer: &str, reason: impl Into<String>) {
+    errors.push(SchemaError {
+        pointer: pointer.to_string(),
+        reason: reason.into(),
+    });
+}
+
+fn child(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn check_group(errors: &mut Vec<SchemaError>, pointer: &str, value: &Json) {
+    let Json::Object(group) = value else {
+        return error(errors, pointer, "expected a token or group object");
+    };
+    for (key, value) in group {
+        let pointer = child(pointer, key);
+        match value {
+            Json::Object(x) if x.contains_key("$value") || x.contains_key("value") => {
+                check_token(errors, &pointer, x)
+            }
+            Json::Object(_) => check_group(errors, &pointer, value),
+            _ if key.starts_with('$') => {
+                error(errors, &pointer, "group properties are not supported")
+            }
+            _ => error(errors, &pointer, "expected a token or group object"),
+        }
+    }
+}
+
+fn check_token(errors: &mut Vec<SchemaError>, pointer: &str, token: &Map<String, Json>) {
+    for (key, value) in token {
+        let pointer = child(pointer, key);
+        match key.as_str() {
+            "$value" | "value" => match value {
+                Json::Object(dict) => {
+                    for (key, value) in dict {
+                        check_expression(errors, &child(&pointer, key), value);
+                    }
+                }
+                value => check_expression(errors, &pointer, value),
+            },
+            "$type" | "type" | "$description" | "description" if !value.is_string() => {
+                error(errors, &pointer, "expected a string")
+            }
+            "$extensions" if !value.is_object() => error(errors, &pointer, "expected an object"),
+            "$deprecated" | "deprecated" if !value.is_boolean() && !value.is_string() => {
+                error(errors, &pointer, "expected a boolean or string")
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_expression(errors: &mut Vec<SchemaError>, pointer: &str, value: &Json) {
+    match value {
+        Json::Number(_) | Json::Bool(_) => {}
+        Json::Array(items) => {
+            for (i, x) in items.iter().enumerate() {
+                check_expression(errors, &format!("{pointer}/{i}"), x);
+            }
+        }
+        Json::String(x) => {
+            if let Err(err) = expr_parser::expr(x) {
+                error(errors, pointer, format!("invalid expression {x:?}: {err}"));
+            }
+        }
+        _ => error(
+            errors,
+            pointer,
+            "expected a string, number, boolean or list",
+        ),
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(
+        validate_json(include_str!("./exportedVariables.json")),
+        Ok(())
+    );
+    assert_eq!(
+        validate_json(include_str!("./design-tokens.tokens.json")),
+        Ok(())
+    );
+    let errors = validate_json(
+        r#"{
+            "a/b": { "$value": "{x" },
+            "c": { "$value": null, "$type": 3 },
+            "d": 4
+        }"#,
+    )
+    .unwrap_err()
+    .into_iter()
+    .map(|x| x.pointer)
+    .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(
+        errors.into_iter().collect::<Vec<_>>(),
+        ["/a~1b/$value", "/c/$type", "/c/$value", "/d"]
+    );
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::{DesignTokens, TokenOrGroup, TokenPath};
+
+/// Which variant of the other sets references resolve against when several define a token,
+/// e.g. the dark mode of a palette for a shared component set.
+///
+/// Each name matches a `.`-separated part of a set's file name, e.g. mode `dark` matches
+/// `Ambient.dark.tokens.json` and platform `ios` matches `Spacing.ios.tokens.json`. Sets
+/// matching more of the names take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolveContext {
+    pub theme: Option<String>,
+    pub mode: Option<String>,
+    pub platform: Option<String>,
+}
+impl ResolveContext {
+    fn matches(&self, set: &DesignTokens) -> usize {
+        let parts = set.file_name.as_deref().unwrap_or_default().split('.');
+        [&self.theme, &self.mode, &self.platform]
+            .into_iter()
+            .flatten()
+            .filter(|name| parts.clone().any(|x| x.eq_ignore_ascii_case(name)))
+            .count()
+    }
+}
+
+/// Token files that are resolved together, so references in one file can point into another,
+/// e.g. a theme aliasing a shared palette.
+///
+/// A set always resolves its own tokens first. Missing references are looked up in the other
+/// sets, earlier sets taking precedence over later ones when several define the same path.
+#[derive(Debug, Clone)]
+pub struct TokenSet {
+    sets: Vec<DesignTokens>,
+}
+impl TokenSet {
+    pub fn new(sets: Vec<DesignTokens>) -> Self {
+        Self { sets }
+    }
+    /// Moves the sets with these names (see [`DesignTokens::get_name`]) to the front, in the
+    /// given order. The remaining sets keep their order.
+    pub fn precedence(mut self, names: &[&str]) -> Self {
+        self.sets.sort_by_key(|x| {
+            names
+                .iter()
+                .position(|name| *name == x.get_name())
+                .unwrap_or(names.len())
+        });
+        self
+    }
+    pub fn sets(&self) -> &[DesignTokens] {
+        &self.sets
+    }
+    /// Looks up a token or group by its `/`-separated path in the first set defining it.
+    pub fn get(&self, path: &str) -> Option<&TokenOrGroup> {
+        let path = TokenPath::parse(path);
+        self.sets.iter().find_map(|x| x.get_token(&path))
+    }
+    /// The sets, each one falling back to the others for references it doesn't define.
+    ///
+    /// References inside a borrowed token are resolved from the borrowing set, so aliases
+    /// follow its overrides.
+    pub fn into_sets(self) -> Vec<DesignTokens> {
+        self.into_sets_in(&ResolveContext::default())
+    }
+    /// Like [`TokenSet::into_sets`], but references prefer the sets matching `context` over the
+    /// order of the sets. Resolving once per mode gives e.g. light and dark constants for the
+    /// same alias.
+    pub fn into_sets_in(self, context: &ResolveContext) -> Vec<DesignTokens> {
+        (0..self.sets.len())
+            .map(|i| {
+                // The merge lets later sets win, so the best match goes last and ties go to the first set.
+                let others = (0..self.sets.len())
+                    .rev()
+                    .filter(|&j| j != i)
+                    .sorted_by_key(|&j| context.matches(&self.sets[j]))
+                    .map(|j| self.sets[j].clone())
+                    .collect();
+                let mut set = self.sets[i].clone();
+                set.options.fallback = Some(Arc::new(DesignTokens::merge(others).body));
+                set
+            })
+            .collect()
+    }
+}
+impl From<Vec<DesignTokens>> for TokenSet {
+    fn from(sets: Vec<DesignTokens>) -> Self {
+        Self::new(sets)
+    }
+}
+
+#[test]
+fn test() {
+    let parse = |json: &str, name: &str| crate::parse_design_tokens(json, Some(name)).unwrap();
+    let sets = [
+        parse(
+            r##"{ "red": { "$value": "#ff0000" }, "gap": { "$value": "4px" } }"##,
+            "palette.json",
+        ),
+        parse(
+            r##"{ "red": { "$value": "#cc0000" }, "gap": { "$value": "2px" } }"##,
+            "compact.json",
+        ),
+        parse(
+            r#"{ "danger": { "$value": "{red}" }, "pad": { "$value": "{gap} * 2" } }"#,
+            "theme.json",
+        ),
+    ]
+    .concat();
+    let linked = TokenSet::new(sets.clone()).into_sets();
+    let theme = &linked[2];
+    assert_eq!(
+        theme.resolve(&["danger".into()]).unwrap().to_css(),
+        "#ff0000"
+    );
+    assert_eq!(theme.resolve(&["pad".into()]).unwrap().to_css(), "8px");
+    assert_eq!(linked[0].resolve(&["gap".into()]).unwrap().to_css(), "4px");
+
+    let linked = TokenSet::new(sets).precedence(&["compact"]).into_sets();
+    let theme = linked.iter().find(|x| x.get_name() == "theme").unwrap();
+    assert_eq!(
+        theme.resolve(&["danger".into()]).unwrap().to_css(),
+        "#cc0000"
+    );
+    assert_eq!(theme.resolve(&["pad".into()]).unwrap().to_css(), "4px");
+
+    let sets = [
+        parse(
+            r##"{ "text": { "primary": { "$value": "#000000" } } }"##,
+            "Ambient.light.tokens.json",
+        ),
+        parse(
+            r##"{ "text": { "primary": { "$value": "#ffffff" } } }"##,
+            "Ambient.dark.tokens.json",
+        ),
+        parse(
+            r#"{ "label": { "$value": "{text.primary}" } }"#,
+            "components.json",
+        ),
+    ]
+    .concat();
+    let label = |mode: Option<&str>| {
+        let context = ResolveContext {
+            mode: mode.map(|x| x.to_string()),
+            ..Default::default()
+        };
+        TokenSet::new(sets.clone()).into_sets_in(&context)[2].to_rust()
+    };
+    assert!(label(None).contains("pub const LABEL: &str = \"#000000\";"));
+    assert!(label(Some("dark")).contains("pub const LABEL: &str = \"#ffffff\";"));
+    assert!(label(Some("Light")).contains("pub const LABEL: &str = \"#000000\";"));
+}
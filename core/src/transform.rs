@@ -0,0 +1,163 @@
+pub use convert_case::Case;
+use convert_case::Casing;
+use indexmap::IndexMap;
+
+use crate::{
+    expression::Expression, resolve_single, DesignTokens, NumberType, TokenOrGroup, TokenPath,
+    TokenType, TokenValue, Value,
+};
+
+pub type TokenFilter = Box<dyn Fn(&TokenPath, &TokenType) -> bool + Send + Sync>;
+pub type ValueTransform = Box<dyn Fn(&TokenPath, &TokenType, Value) -> Value + Send + Sync>;
+pub type NameTransform = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Filters and transforms applied to resolved values before emission, so platform conventions
+/// (rem units, hex8 colors, casing) stay out of the token source.
+///
+/// [`TransformPipeline::apply`] returns a new set whose tokens hold their final values, with
+/// references and modifiers already resolved.
+#[derive(Default)]
+pub struct TransformPipeline {
+    filters: Vec<TokenFilter>,
+    values: Vec<ValueTransform>,
+    names: Vec<NameTransform>,
+}
+impl TransformPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Only keeps tokens for which every filter returns `true`. Emptied groups are dropped.
+    pub fn filter(
+        mut self,
+        f: impl Fn(&TokenPath, &TokenType) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filters.push(Box::new(f));
+        self
+    }
+    /// Transforms every resolved value, including each field of composite tokens, in order.
+    pub fn value(
+        mut self,
+        f: impl Fn(&TokenPath, &TokenType, Value) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.values.push(Box::new(f));
+        self
+    }
+    /// Renames every group and token key, in order.
+    pub fn name(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.names.push(Box::new(f));
+        self
+    }
+    pub fn apply(&self, tokens: &DesignTokens) -> DesignTokens {
+        DesignTokens {
+            file_name: tokens.file_name.clone(),
+            body: self
+                .apply_node(tokens, &tokens.body, &mut Vec::new())
+                .unwrap_or_else(|| TokenOrGroup::Group(IndexMap::new())),
+            options: tokens.options.clone(),
+            origin: tokens.origin.clone(),
+        }
+    }
+    fn apply_node(
+        &self,
+        tokens: &DesignTokens,
+        node: &TokenOrGroup,
+        path: &mut Vec<String>,
+    ) -> Option<TokenOrGroup> {
+        match node {
+            TokenOrGroup::Token {
+                value,
+                type_,
+                extensions,
+                description,
+                deprecated,
+            } => {
+                let token_path = TokenPath::from(&path[..]);
+                if !self.filters.iter().all(|f| f(&token_path, type_)) {
+                    return None;
+                }
+                let transform = |value: Value| {
+                    let value = self
+                        .values
+                        .iter()
+                        .fold(value, |value, f| f(&token_path, type_, value));
+                    Expression::Value(value)
+                };
+                let value = match value {
+                    TokenValue::Single(expr) => {
+                        TokenValue::Single(transform(resolve_single(tokens, expr, extensions)))
+                    }
+                    TokenValue::Dict(dict) => TokenValue::Dict(
+                        dict.iter()
+                            .map(|(k, v)| (k.clone(), transform(v.get_value(tokens))))
+                            .collect(),
+                    ),
+                };
+                Some(TokenOrGroup::Token {
+                    value,
+                    type_: type_.clone(),
+                    extensions: None,
+                    description: description.clone(),
+                    deprecated: deprecated.clone(),
+                })
+            }
+            TokenOrGroup::Group(group) => {
+                let mut out = IndexMap::new();
+                for (key, value) in group {
+                    path.push(key.clone());
+                    if let Some(value) = self.apply_node(tokens, value, path) {
+                        let key = self.names.iter().fold(key.clone(), |key, f| f(&key));
+                        out.insert(key, value);
+                    }
+                    path.pop();
+                }
+                (!out.is_empty() || path.is_empty()).then_some(TokenOrGroup::Group(out))
+            }
+        }
+    }
+}
+
+/// Converts pixel values to `rem` relative to `base`.
+pub fn px_to_rem(base: f64) -> impl Fn(&TokenPath, &TokenType, Value) -> Value {
+    move |_, _, value| match value {
+        Value::Number(v, NumberType::Pixels) => Value::Number(v / base, NumberType::Rem),
+        value => value,
+    }
+}
+
+/// Writes colors as 8-digit `#rrggbbaa` hex, even when opaque.
+pub fn color_hex8(_: &TokenPath, _: &TokenType, value: Value) -> Value {
+    match value {
+        Value::Color(c) => {
+            let [r, g, b, a] = c.to_rgba8();
+            Value::Any(format!("#{r:02x}{g:02x}{b:02x}{a:02x}"))
+        }
+        value => value,
+    }
+}
+
+pub fn name_case(case: Case) -> impl Fn(&str) -> String {
+    move |key| key.to_case(case)
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "Brand Colors": { "Primary": { "$value": "#ff0000", "$type": "color" } },
+            "Spacing": { "Small": { "$value": "8px", "$type": "dimension" } },
+            "Font": { "Family": { "$value": "Inter", "$type": "string" } }
+        }"##,
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    let pipeline = TransformPipeline::new()
+        .filter(|_, type_| *type_ != TokenType::String)
+        .value(px_to_rem(16.))
+        .value(color_hex8)
+        .name(name_case(Case::Kebab));
+    assert_eq!(
+        pipeline.apply(&tokens).to_css_with_selector(":root"),
+        ":root { --brand-colors-primary: #ff0000ff; }\n:root { --spacing-small: 0.5rem; }"
+    );
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use crate::{CssOptions, DesignTokens, TokenPath};
+
+impl DesignTokens {
+    /// Tokens that none of `sources` use, by Rust constant or by CSS custom property as named
+    /// with `options`. Tokens referenced by a used token count as used.
+    pub fn unused<'a>(
+        &self,
+        options: &CssOptions,
+        sources: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<TokenPath> {
+        let mut words = HashSet::new();
+        for source in sources {
+            words.extend(source.split(|c: char| !c.is_alphanumeric() && c != '_'));
+            words.extend(source.split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-'));
+        }
+        let tokens = self.tokens();
+        let mut used = tokens
+            .iter()
+            .zip(self.rust_names())
+            .filter(|((path, _), rust)| {
+                words.contains(rust.as_str())
+                    || words.contains(options.property_name(&self.options.naming, path).as_str())
+            })
+            .map(|((path, _), _)| path.clone())
+            .collect::<HashSet<_>>();
+        let graph = self.reference_graph();
+        let mut queue = used.iter().cloned().collect::<Vec<_>>();
+        while let Some(path) = queue.pop() {
+            for target in graph.references(&path) {
+                if used.insert(target.clone()) {
+                    queue.push(target.clone());
+                }
+            }
+        }
+        tokens
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| !used.contains(path))
+            .collect()
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "red": { "$value": "#ff0000" },
+            "danger": { "$value": "{red}" },
+            "gap": { "$value": "4px" },
+            "gap-l": { "$value": "8px" },
+            "unused": { "$value": "1px" }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let sources = ["color: var(--danger);", "let x = GAP * 2.;"];
+    assert_eq!(
+        tokens[0].unused(&CssOptions::default(), sources),
+        [TokenPath::parse("gap-l"), TokenPath::parse("unused")]
+    );
+}
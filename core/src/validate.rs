@@ -0,0 +1,333 @@
+use std::{collections::HashMap, path::Path};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    expression::{Expression, Value},
+    CssOptions, DesignTokens, TokenOrGroup, TokenPath, TokenType, TokenValue,
+};
+
+/// Types from the DTCG spec and Tokens Studio that are accepted without special handling.
+const KNOWN_TYPES: &[&str] = &[
+    "fontFamily",
+    "fontFamilies",
+    "fontWeight",
+    "fontWeights",
+    "fontSizes",
+    "lineHeights",
+    "letterSpacing",
+    "paragraphSpacing",
+    "textCase",
+    "textDecoration",
+    "shadow",
+    "boxShadow",
+    "gradient",
+    "spacing",
+    "sizing",
+    "borderRadius",
+    "borderWidth",
+    "opacity",
+    "other",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the check that produced this diagnostic, e.g. `missing-reference`.
+    pub rule: &'static str,
+    /// Path of the offending token, serialized `/`-separated.
+    pub path: TokenPath,
+    pub message: String,
+}
+
+impl DesignTokens {
+    /// Checks references, types and generated names up front instead of failing during codegen.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        let mut css_names: HashMap<String, Vec<TokenPath>> = HashMap::new();
+        for (path, token) in self.tokens() {
+            let TokenOrGroup::Token { type_, .. } = token else {
+                continue;
+            };
+            // Modifier arguments included, see `TokenOrGroup::exprs`.
+            let mut missing = false;
+            for target in token.exprs().iter().flat_map(|x| x.refs()) {
+                let message = match self.get_value(target) {
+                    None if self.get_field(target).is_some() => continue,
+                    None => "does not resolve",
+                    Some(TokenValue::Dict(_)) => "points at a composite token",
+                    Some(TokenValue::Single(_)) => continue,
+                };
+                missing = true;
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    rule: "missing-reference",
+                    path: path.clone(),
+                    message: format!("reference {{{}}} {message}", target.join(".")),
+                });
+            }
+            // `missing_ref` also follows aliases, which end in a token that exists on a cycle.
+            if let Some(target) = self.missing_ref(token) {
+                let exists = self.get_value(&target).is_some() || self.get_field(&target).is_some();
+                if !missing && exists {
+                    out.push(Diagnostic {
+                        severity: Severity::Error,
+                        rule: "reference-cycle",
+                        path: path.clone(),
+                        message: format!("reference {{{}}} leads back to itself", target.join(".")),
+                    });
+                }
+            }
+            if let TokenType::Other(type_) = type_ {
+                if !KNOWN_TYPES.contains(&type_.as_str()) {
+                    out.push(Diagnostic {
+                        severity: Severity::Warning,
+                        rule: "unknown-type",
+                        path: path.clone(),
+                        message: format!("unknown $type {type_:?}"),
+                    });
+                }
+            }
+            if let Some(err) = self.composite_error(token) {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    rule: "unsupported-composite",
+                    path: path.clone(),
+                    message: format!("{err}, written as fields in Rust"),
+                });
+            }
+            let css = CssOptions::default().property_name(&self.options.naming, &path);
+            css_names.entry(css).or_default().push(path);
+        }
+        for (slug, paths) in css_names.into_iter().sorted() {
+            if paths.len() < 2 {
+                continue;
+            }
+            for path in &paths {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    rule: "duplicate-slug",
+                    path: path.clone(),
+                    message: format!(
+                        "CSS property name {slug} is shared by {}",
+                        paths.iter().filter(|x| *x != path).join(", ")
+                    ),
+                });
+            }
+        }
+        // The Rust output tells these apart with a number, see `DesignTokens::rust_names`.
+        for (name, paths) in self.rust_collisions() {
+            for path in &paths {
+                out.push(Diagnostic {
+                    severity: Severity::Warning,
+                    rule: "duplicate-rust-name",
+                    path: path.clone(),
+                    message: format!(
+                        "Rust constant name {name} is shared by {}, so the constants are numbered",
+                        paths.iter().filter(|x| *x != path).join(", ")
+                    ),
+                });
+            }
+        }
+        out.extend(
+            self.unsupported_modifiers()
+                .into_iter()
+                .map(|x| Diagnostic {
+                    severity: Severity::Warning,
+                    rule: "unsupported-modifier",
+                    path: x.path,
+                    message: x.message,
+                }),
+        );
+        out
+    }
+    /// Checks that every `asset` token that isn't a URL names a file under `dir`.
+    pub fn validate_assets(&self, dir: &Path) -> Vec<Diagnostic> {
+        self.iter()
+            .filter_map(|(path, token)| match token {
+                TokenOrGroup::Token {
+                    value: TokenValue::Single(Expression::Value(Value::Any(file))),
+                    type_: TokenType::Asset,
+                    ..
+                } if !file.contains("://") && !file.starts_with("data:") => {
+                    (!dir.join(file).exists()).then(|| Diagnostic {
+                        severity: Severity::Error,
+                        rule: "missing-asset",
+                        path: path.clone(),
+                        message: format!("{} does not exist", dir.join(file).display()),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test() {
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "a": { "$value": "{missing.token}" },
+            "b": { "$value": 4, "$type": "colour" },
+            "Size 2": { "$value": 2 },
+            "size 2": { "$value": 2 }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let diagnostics = tokens[0].validate();
+    assert_eq!(
+        serde_json::to_value(&diagnostics[0]).unwrap(),
+        serde_json::json!({
+            "severity": "error",
+            "rule": "missing-reference",
+            "path": "a",
+            "message": "reference {missing.token} does not resolve"
+        })
+    );
+    let rules = diagnostics
+        .into_iter()
+        .map(|x| (x.rule, x.path.to_string()))
+        .collect_vec();
+    assert!(rules.contains(&("missing-reference", "a".to_string())));
+    assert!(rules.contains(&("unknown-type", "b".to_string())));
+    assert!(rules.contains(&("duplicate-slug", "Size 2".to_string())));
+    assert!(rules.contains(&("duplicate-slug", "size 2".to_string())));
+
+    // Keys that only collide once sanitized into Rust identifiers still generate.
+    let tokens =
+        crate::parse_design_tokens(r#"{ "2x": { "$value": 2 }, "_2x": { "$value": 3 } }"#, None)
+            .unwrap();
+    assert_eq!(
+        tokens[0].validate(),
+        vec![
+            Diagnostic {
+                severity: Severity::Warning,
+                rule: "duplicate-rust-name",
+                path: TokenPath::parse("2x"),
+                message: "Rust constant name _2X is shared by _2x, so the constants are numbered"
+                    .to_string(),
+            },
+            Diagnostic {
+                severity: Severity::Warning,
+                rule: "duplicate-rust-name",
+                path: TokenPath::parse("_2x"),
+                message: "Rust constant name _2X is shared by 2x, so the constants are numbered"
+                    .to_string(),
+            },
+        ]
+    );
+    let rust = tokens[0].to_rust();
+    assert!(rust.contains("pub const _2X: f32 = 2.;"));
+    assert!(rust.contains("pub const _2X_2: f32 = 3.;"));
+
+    #[cfg(feature = "ambient-tokens")]
+    for tokens in crate::design_tokens() {
+        let errors = tokens
+            .validate()
+            .into_iter()
+            .filter(|x| x.rule == "missing-reference")
+            .collect_vec();
+        assert_eq!(errors, vec![]);
+    }
+}
+
+#[test]
+fn test_field_refs() {
+    let json = r#"{
+        "body": { "$type": "typography", "$value": { "fontSize": 16, "fontWeight": 400 } },
+        "caption": { "$type": "typography", "$value": { "fontSize": "{body.fontSize} / 2" } },
+        "icon": { "$value": "{body.fontSize}" }
+    }"#;
+    let tokens = crate::parse_design_tokens(json, None).unwrap();
+    let bad = json.replacen('{', r#"{ "bad": { "$value": "{body.missing}" },"#, 1);
+    let paths = crate::parse_design_tokens(&bad, None).unwrap()[0]
+        .validate()
+        .into_iter()
+        .map(|x| x.path.to_string())
+        .collect_vec();
+    assert_eq!(paths, ["bad"]);
+    assert!(tokens[0].to_css().contains(".ambient { --icon: 16; }"));
+    assert!(tokens[0].to_css().contains("font-size: 8px;"));
+    assert!(tokens[0].to_rust().contains("pub const ICON: f32 = 16.;"));
+}
+
+#[test]
+fn test_assets() {
+    let dir = std::env::temp_dir().join("validate_assets");
+    std::fs::create_dir_all(dir.join("icons")).unwrap();
+    std::fs::write(dir.join("icons/close.svg"), "<svg/>").unwrap();
+    let tokens = crate::parse_design_tokens(
+        r#"{
+            "close": { "$value": "icons/close.svg", "$type": "asset" },
+            "open": { "$value": "icons/open.svg", "$type": "asset" },
+            "logo": { "$value": "https://example.com/logo.png", "$type": "asset" }
+        }"#,
+        None,
+    )
+    .unwrap();
+    let css = tokens[0].to_css();
+    assert!(css.contains("--close: url(\"icons/close.svg\");"));
+    assert!(css.contains("--logo: url(\"https://example.com/logo.png\");"));
+    assert!(tokens[0]
+        .to_rust()
+        .contains("pub const CLOSE: &str = \"icons/close.svg\";"));
+    let missing = tokens[0].validate_assets(&dir);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].path, TokenPath::parse("open"));
+}
+
+#[test]
+fn test_cycles_and_modifiers() {
+    let tokens = crate::parse_design_tokens(
+        r##"{
+            "a": { "$value": "{b}" },
+            "b": { "$value": "{a}" },
+            "red": { "$value": "#ff0000", "$type": "color",
+                "$extensions": { "studio.tokens": {
+                    "modify": { "type": "mix", "value": "0.5", "space": "srgb", "color": "{white}" }
+                } }
+            }
+        }"##,
+        None,
+    )
+    .unwrap();
+    let found = tokens[0]
+        .validate()
+        .into_iter()
+        .map(|x| (x.rule, x.path.to_string(), x.message))
+        .collect_vec();
+    assert_eq!(
+        found,
+        [
+            (
+                "reference-cycle",
+                "a".to_string(),
+                "reference {b} leads back to itself".to_string()
+            ),
+            (
+                "reference-cycle",
+                "b".to_string(),
+                "reference {a} leads back to itself".to_string()
+            ),
+            (
+                "missing-reference",
+                "red".to_string(),
+                "reference {white} does not resolve".to_string()
+            ),
+            (
+                "unsupported-modifier",
+                "red".to_string(),
+                "Mix needs a color to mix with, left unapplied".to_string(),
+            ),
+        ]
+    );
+}
@@ -0,0 +1,54 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+/// Events arriving within this window are batched into a single callback.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Blocks, calling `on_change` with the changed files whenever any of `paths` is modified.
+/// Returns once `on_change` returns `false` or the watcher shuts down.
+pub fn watch(
+    paths: &[impl AsRef<Path>],
+    mut on_change: impl FnMut(&[PathBuf]) -> bool,
+) -> notify::Result<()> {
+    let files = paths
+        .iter()
+        .map(|x| x.as_ref().canonicalize())
+        .collect::<Result<Vec<_>, _>>()?;
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // Watch the parent directories; editors often save by replacing the file, which drops
+    // a watch placed on the file itself.
+    let mut dirs = files.iter().filter_map(|x| x.parent()).collect::<Vec<_>>();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    while let Ok(event) = rx.recv() {
+        let mut changed = Vec::new();
+        collect(event?, &files, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect(event?, &files, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        changed.sort();
+        changed.dedup();
+        if !on_change(&changed) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn collect(event: Event, files: &[PathBuf], changed: &mut Vec<PathBuf>) {
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        changed.extend(event.paths.into_iter().filter(|x| files.contains(x)));
+    }
+}
@@ -1,32 +1,14 @@
-use std::{env, fs, path::Path};
-
-use ambient_design_tokens_core::get_design_tokens;
+use design_tokens_build::{Builder, RustOptions};
 
 fn main() {
-    let data = get_design_tokens();
-
-    let out_dir = env::var_os("OUT_DIR").unwrap();
-    {
-        let dest_path = Path::new(&out_dir).join("ambient.css");
-        fs::write(
-            &dest_path,
-            data.iter()
-                .map(|x| x.to_css())
-                .collect::<Vec<_>>()
-                .join("\n"),
-        )
-        .unwrap();
-    }
-    {
-        let dest_path = Path::new(&out_dir).join("ambient.rs");
-        fs::write(
-            &dest_path,
-            data.iter()
-                .map(|x| format!("pub mod {} {{ {} }}", x.get_name_rust(), x.to_rust()))
-                .collect::<Vec<_>>()
-                .join("\n"),
-        )
+    Builder::new()
+        .rust_options(RustOptions {
+            lookup: true,
+            theme: true,
+            metadata: true,
+            ..Default::default()
+        })
+        .compile()
         .unwrap();
-    }
     println!("cargo:rerun-if-changed=build.rs");
 }
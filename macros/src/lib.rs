@@ -0,0 +1,70 @@
+//! Macros for using generated tokens without writing the `include!` by hand.
+
+use std::path::PathBuf;
+
+use ambient_design_tokens_core::{
+    emit::{Emitter, ResolvedTokens, RustEmitter},
+    load_design_tokens,
+};
+use proc_macro::{TokenStream, TokenTree};
+
+/// Includes `OUT_DIR/ambient.rs` written by `design_tokens_build::Builder`, or
+/// `OUT_DIR/<name>.rs` for `include_design_tokens!("name")`.
+#[proc_macro]
+pub fn include_design_tokens(input: TokenStream) -> TokenStream {
+    let name = if input.is_empty() {
+        "ambient".to_string()
+    } else {
+        match string_literal(input) {
+            Ok(name) => name,
+            Err(err) => return err,
+        }
+    };
+    format!("include!(concat!(env!(\"OUT_DIR\"), \"/{name}.rs\"));")
+        .parse()
+        .unwrap()
+}
+
+/// Parses a token file at compile time and expands to the generated Rust modules, without a build
+/// script. The path is relative to the crate's `Cargo.toml`.
+#[proc_macro]
+pub fn design_tokens(input: TokenStream) -> TokenStream {
+    let path = match string_literal(input) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
+    let full = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join(&path);
+    let data = match load_design_tokens(&full) {
+        Ok(data) => data,
+        Err(err) => return compile_error(&format!("{}: {err}", full.display())),
+    };
+    // Recompile when the token file changes.
+    let track = format!(
+        "const _: &str = include_str!({:?});",
+        full.to_string_lossy()
+    );
+    format!(
+        "{track}\n{}",
+        RustEmitter::default().emit(&ResolvedTokens::new(&data))
+    )
+    .parse()
+    .unwrap()
+}
+
+fn string_literal(input: TokenStream) -> Result<String, TokenStream> {
+    let mut tokens = input.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(lit)), None) => {
+            let lit = lit.to_string();
+            match lit.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+                Some(x) => Ok(x.to_string()),
+                None => Err(compile_error("expected a string literal")),
+            }
+        }
+        _ => Err(compile_error("expected a string literal")),
+    }
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("compile_error!({message:?});").parse().unwrap()
+}
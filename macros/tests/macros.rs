@@ -0,0 +1,10 @@
+design_tokens_macros::design_tokens!("../core/src/design-tokens.tokens.json");
+
+#[test]
+fn test() {
+    let family = TOKENS::FONT_BODY_M_400
+        .iter()
+        .find(|(key, _)| *key == "fontFamily")
+        .map(|(_, value)| *value);
+    assert_eq!(family, Some("ABC Diatype Variable"));
+}
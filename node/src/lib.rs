@@ -0,0 +1,68 @@
+//! Node.js bindings, so JS build pipelines such as Vite or webpack plugins can call the Rust
+//! implementation directly. Build with `npm run build`.
+
+use ambient_design_tokens_core::{
+    emit::{EmitterRegistry, ResolvedTokens},
+    load_design_tokens, parse_design_tokens,
+    token_set::TokenSet,
+    DesignTokens,
+};
+use napi::{Error, Result};
+use napi_derive::napi;
+
+fn error(err: impl ToString) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+/// Parsed token sets. References may point into any of the sets.
+#[napi]
+pub struct Tokens {
+    sets: Vec<DesignTokens>,
+}
+#[napi]
+impl Tokens {
+    /// Parses a token tree or a list of `{ fileName, body }` sets.
+    #[napi(factory)]
+    pub fn parse(json: String, file_name: Option<String>) -> Result<Self> {
+        let sets = parse_design_tokens(&json, file_name.as_deref()).map_err(error)?;
+        Ok(Self::new(sets))
+    }
+    /// Loads one or more token files.
+    #[napi(factory)]
+    pub fn load(paths: Vec<String>) -> Result<Self> {
+        let mut sets = Vec::new();
+        for path in paths {
+            sets.extend(load_design_tokens(&path).map_err(|err| error(format!("{path}: {err}")))?);
+        }
+        Ok(Self::new(sets))
+    }
+    fn new(sets: Vec<DesignTokens>) -> Self {
+        Self {
+            sets: TokenSet::new(sets).into_sets(),
+        }
+    }
+    #[napi(getter)]
+    pub fn set_names(&self) -> Vec<String> {
+        self.sets.iter().map(|x| x.get_name().to_string()).collect()
+    }
+    /// The resolved CSS value of a `/`-separated token path, from the first set defining it.
+    #[napi]
+    pub fn resolve(&self, path: String) -> Option<String> {
+        self.sets.iter().find_map(|x| x.resolve_css(&path))
+    }
+    /// Output of a built-in format, e.g. `css`, `rust` or `json`.
+    #[napi]
+    pub fn emit(&self, format: String) -> Result<String> {
+        EmitterRegistry::with_builtins()
+            .emit(&format, &ResolvedTokens::new(&self.sets))
+            .ok_or_else(|| error(format!("unknown format {format:?}")))
+    }
+    /// Names accepted by [`Tokens::emit`].
+    #[napi]
+    pub fn formats() -> Vec<String> {
+        EmitterRegistry::with_builtins()
+            .names()
+            .map(|x| x.to_string())
+            .collect()
+    }
+}
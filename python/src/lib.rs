@@ -0,0 +1,93 @@
+//! Python bindings for design-ops tooling. Build with `maturin develop` in this directory.
+
+use ambient_design_tokens_core::{
+    emit::{EmitterRegistry, ResolvedTokens},
+    load_design_tokens, parse_design_tokens, schema,
+    token_set::TokenSet,
+    validate::Severity,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+fn error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Parsed token sets. References may point into any of the sets.
+#[pyclass]
+struct DesignTokens {
+    sets: Vec<ambient_design_tokens_core::DesignTokens>,
+}
+impl DesignTokens {
+    fn new(sets: Vec<ambient_design_tokens_core::DesignTokens>) -> Self {
+        Self {
+            sets: TokenSet::new(sets).into_sets(),
+        }
+    }
+}
+#[pymethods]
+impl DesignTokens {
+    /// Parses a token tree or a list of `{ fileName, body }` sets.
+    #[staticmethod]
+    #[pyo3(signature = (json, file_name = None))]
+    fn parse(json: &str, file_name: Option<&str>) -> PyResult<Self> {
+        Ok(Self::new(parse_design_tokens(json, file_name).map_err(error)?))
+    }
+    #[staticmethod]
+    fn load(paths: Vec<String>) -> PyResult<Self> {
+        let mut sets = Vec::new();
+        for path in paths {
+            sets.extend(load_design_tokens(&path).map_err(|err| error(format!("{path}: {err}")))?);
+        }
+        Ok(Self::new(sets))
+    }
+    #[getter]
+    fn set_names(&self) -> Vec<String> {
+        self.sets.iter().map(|x| x.get_name().to_string()).collect()
+    }
+    /// The resolved CSS value of a `/`-separated token path, from the first set defining it.
+    fn resolve(&self, path: &str) -> Option<String> {
+        self.sets.iter().find_map(|x| x.resolve_css(path))
+    }
+    /// `(severity, rule, path, message)` for every problem found in the sets.
+    fn validate(&self) -> Vec<(&'static str, &'static str, String, String)> {
+        self.sets
+            .iter()
+            .flat_map(|set| {
+                set.validate().into_iter().map(|x| {
+                    let severity = match x.severity {
+                        Severity::Warning => "warning",
+                        Severity::Error => "error",
+                    };
+                    (
+                        severity,
+                        x.rule,
+                        format!("{}/{}", set.get_name(), x.path),
+                        x.message,
+                    )
+                })
+            })
+            .collect()
+    }
+    /// Output of a built-in format, e.g. `css`, `rust` or `json`.
+    fn emit(&self, format: &str) -> PyResult<String> {
+        EmitterRegistry::with_builtins()
+            .emit(format, &ResolvedTokens::new(&self.sets))
+            .ok_or_else(|| error(format!("unknown format {format:?}")))
+    }
+}
+
+/// `(pointer, reason)` for every schema problem in a token file, before parsing it.
+#[pyfunction]
+fn validate_json(json: &str) -> Vec<(String, String)> {
+    match schema::validate_json(json) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|x| (x.pointer, x.reason)).collect(),
+    }
+}
+
+#[pymodule]
+fn design_tokens(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<DesignTokens>()?;
+    m.add_function(wrap_pyfunction!(validate_json, m)?)?;
+    Ok(())
+}
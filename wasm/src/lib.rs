@@ -0,0 +1,78 @@
+//! Browser bindings, e.g. for a Figma plugin, so tokens are converted with the same expression
+//! semantics as the CLI. Build with `wasm-pack build wasm`.
+
+use ambient_design_tokens_core::{
+    emit::{CssEmitter, Emitter, EmitterRegistry, ResolvedTokens},
+    parse_design_tokens,
+    token_set::TokenSet,
+    ColorFormat, DesignTokens,
+};
+use wasm_bindgen::prelude::*;
+
+/// Parsed token sets, kept on the Rust side between calls.
+#[wasm_bindgen]
+pub struct Tokens {
+    sets: Vec<DesignTokens>,
+}
+#[wasm_bindgen]
+impl Tokens {
+    #[wasm_bindgen(getter, js_name = setNames)]
+    pub fn set_names(&self) -> Vec<String> {
+        self.sets.iter().map(|x| x.get_name().to_string()).collect()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct CssOptions {
+    color_format: ColorFormat,
+    #[wasm_bindgen(js_name = remBase)]
+    pub rem_base: Option<f64>,
+    pub provenance: bool,
+}
+#[wasm_bindgen]
+impl CssOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// `hex`, `hex-rgba`, `rgb`, `hsl` or `oklch`.
+    #[wasm_bindgen(setter, js_name = colorFormat)]
+    pub fn set_color_format(&mut self, format: &str) -> Result<(), JsError> {
+        self.color_format = format.parse().map_err(|err: String| JsError::new(&err))?;
+        Ok(())
+    }
+}
+
+/// Parses a token tree or a list of `{ fileName, body }` sets. References may point into any of
+/// the parsed sets.
+#[wasm_bindgen(js_name = parseTokens)]
+pub fn parse_tokens(json: &str, file_name: Option<String>) -> Result<Tokens, JsError> {
+    let sets = parse_design_tokens(json, file_name.as_deref())
+        .map_err(|err| JsError::new(&err.to_string()))?;
+    Ok(Tokens {
+        sets: TokenSet::new(sets).into_sets(),
+    })
+}
+
+#[wasm_bindgen(js_name = toCss)]
+pub fn to_css(tokens: &Tokens, options: Option<CssOptions>) -> String {
+    let options = options.unwrap_or_default();
+    let emitter = CssEmitter {
+        options: ambient_design_tokens_core::CssOptions {
+            color_format: options.color_format,
+            rem_base: options.rem_base,
+            provenance: options.provenance,
+            ..Default::default()
+        },
+    };
+    emitter.emit(&ResolvedTokens::new(&tokens.sets))
+}
+
+/// Output of any built-in format, e.g. `rust`, `swift` or `json`.
+#[wasm_bindgen]
+pub fn emit(tokens: &Tokens, format: &str) -> Result<String, JsError> {
+    EmitterRegistry::with_builtins()
+        .emit(format, &ResolvedTokens::new(&tokens.sets))
+        .ok_or_else(|| JsError::new(&format!("unknown format {format:?}")))
+}